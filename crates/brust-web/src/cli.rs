@@ -1,6 +1,9 @@
 //! CLI argument definitions.
 
+use std::path::PathBuf;
+
 use clap::{Parser, Subcommand};
+use clap_complete::Shell;
 
 /// Top-level CLI for brust-web.
 #[derive(Debug, Parser)]
@@ -18,6 +21,10 @@ pub enum Commands {
     Serve(ServeArgs),
     /// Print the version string.
     Version,
+    /// Print a shell completion script.
+    Completions(CompletionsArgs),
+    /// Write man pages for brust-web and every subcommand.
+    Man(ManArgs),
 }
 
 /// Arguments for the `serve` subcommand.
@@ -27,3 +34,19 @@ pub struct ServeArgs {
     #[arg(long, default_value = "0.0.0.0:3000", value_name = "ADDR")]
     pub bind: String,
 }
+
+/// Arguments for the `completions` subcommand.
+#[derive(Debug, clap::Args)]
+pub struct CompletionsArgs {
+    /// Shell to generate the completion script for.
+    #[arg(value_enum)]
+    pub shell: Shell,
+}
+
+/// Arguments for the `man` subcommand.
+#[derive(Debug, clap::Args)]
+pub struct ManArgs {
+    /// Directory to write the roff pages into (created if missing).
+    #[arg(value_name = "DIR")]
+    pub out_dir: PathBuf,
+}
@@ -0,0 +1,114 @@
+//! Shell completion scripts and man pages for the `brust-web` CLI.
+//!
+//! Thin wrappers binding [`brust::libs::generate`] to [`Cli`].
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use brust::libs::generate;
+use clap_complete::Shell;
+use clap_mangen::Man;
+
+use crate::cli::Cli;
+
+/// Completion script for `shell`, covering every subcommand and flag.
+#[must_use]
+pub fn completions(shell: Shell) -> String {
+    generate::completions::<Cli>(shell)
+}
+
+/// One man page per command: `brust-web(1)` first, then a page for every
+/// subcommand (`brust-web-serve(1)`, `brust-web-version(1)`, ...).
+#[must_use]
+pub fn man_pages() -> Vec<Man> {
+    generate::man_pages::<Cli>()
+}
+
+/// Write every page of [`man_pages`] into `dir`, creating it if needed.
+///
+/// Returns the written paths in page order.
+///
+/// # Errors
+///
+/// Returns an error if `dir` cannot be created or a page cannot be written.
+pub fn write_man_pages(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    generate::write_man_pages::<Cli>(dir)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    #![allow(clippy::indexing_slicing)]
+
+    use clap::{Command, CommandFactory as _, ValueEnum as _};
+    use clap_complete::Shell;
+
+    use super::{completions, man_pages};
+    use crate::cli::Cli;
+
+    /// Every command in the tree with the long flags it accepts, including
+    /// propagated global flags.
+    fn long_flags() -> Vec<(String, Vec<String>)> {
+        fn walk(cmd: &Command, out: &mut Vec<(String, Vec<String>)>) {
+            let flags = cmd
+                .get_arguments()
+                .filter(|arg| !arg.is_hide_set())
+                .filter_map(|arg| arg.get_long().map(String::from))
+                .collect();
+            out.push((String::from(cmd.get_name()), flags));
+            for sub in cmd.get_subcommands() {
+                walk(sub, out);
+            }
+        }
+        let mut cmd = Cli::command().disable_help_subcommand(true);
+        cmd.build();
+        let mut out = Vec::new();
+        walk(&cmd, &mut out);
+        out
+    }
+
+    #[test]
+    fn completions_list_every_flag_for_every_shell() {
+        let commands = long_flags();
+        for &shell in Shell::value_variants() {
+            let script = completions(shell);
+            for (name, flags) in &commands {
+                assert!(script.contains(name.as_str()), "{shell}: missing {name}");
+                for flag in flags {
+                    // fish names long options with `-l NAME`; the others spell `--NAME`.
+                    let expected = if shell == Shell::Fish {
+                        format!("-l {flag}")
+                    } else {
+                        format!("--{flag}")
+                    };
+                    assert!(
+                        script.contains(&expected),
+                        "{shell}: {name}: missing {expected}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn man_pages_cover_every_subcommand_and_flag() {
+        let pages = man_pages();
+        let names: Vec<String> = pages.iter().map(clap_mangen::Man::get_filename).collect();
+        assert_eq!(
+            names,
+            [
+                "brust-web.1",
+                "brust-web-serve.1",
+                "brust-web-version.1",
+                "brust-web-completions.1",
+                "brust-web-man.1",
+            ]
+        );
+
+        let mut serve = Vec::new();
+        pages[1].render(&mut serve).unwrap();
+        let serve = String::from_utf8(serve).unwrap();
+        assert!(serve.contains("\\-\\-bind"), "{serve}");
+        assert!(serve.contains("\\-\\-help"), "{serve}");
+    }
+}
@@ -2,6 +2,7 @@
 
 pub mod assets;
 pub mod cli;
+pub mod generate;
 pub mod routes;
 pub mod telemetry;
 pub mod trace;
@@ -12,7 +12,7 @@ use tower_http::trace::TraceLayer;
 use brust_web::{
     assets,
     cli::{Cli, Commands},
-    routes,
+    generate, routes,
     telemetry::{self, metrics::Meters},
     trace::{OtelHttpServerMakeSpan, OtelOnResponse, server_metrics_mw},
 };
@@ -28,6 +28,21 @@ async fn main() -> anyhow::Result<()> {
                 println!("{}", brust_web::app_version());
             }
         }
+        Commands::Completions(args) => {
+            #[allow(clippy::print_stdout)]
+            {
+                print!("{}", generate::completions(args.shell));
+            }
+        }
+        Commands::Man(args) => {
+            let paths = generate::write_man_pages(&args.out_dir).with_context(|| {
+                format!("failed to write man pages to {}", args.out_dir.display())
+            })?;
+            #[allow(clippy::print_stdout)]
+            for path in paths {
+                println!("{}", path.display());
+            }
+        }
         Commands::Serve(args) => {
             let telemetry = telemetry::init_telemetry(
                 env!("CARGO_PKG_NAME"),
@@ -159,3 +159,28 @@ fn serve_subcommand_binds_and_responds() {
     let status = child.wait().expect("failed to wait for child");
     assert!(status.success(), "brust-web exited with {status}");
 }
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn completions_subcommand_prints_script() {
+    let mut cmd = cargo_bin_cmd!("brust-web");
+    cmd.args(["completions", "zsh"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("#compdef brust-web"))
+        .stdout(predicate::str::contains("--bind"));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn man_subcommand_writes_pages() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut cmd = cargo_bin_cmd!("brust-web");
+    cmd.arg("man")
+        .arg(dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("brust-web-serve.1"));
+    let serve = std::fs::read_to_string(dir.path().join("brust-web-serve.1")).unwrap();
+    assert!(serve.contains("\\-\\-bind"));
+}
@@ -0,0 +1,482 @@
+//! CLI argument definitions.
+
+use std::num::NonZeroU32;
+use std::path::PathBuf;
+
+use clap::error::ErrorKind;
+use clap::{ArgAction, CommandFactory as _, Parser, Subcommand};
+use clap_complete::Shell;
+
+use brust::libs::batch::Format;
+use brust::libs::count::{Delay, ErrorRate, IterationError};
+use brust::libs::hello::honorific::Gender;
+use brust::libs::http::Header;
+use brust::libs::http::retry::{Jitter, RetryableError};
+use brust::libs::units::{ByteSize, HumanDuration, Rate};
+use reqwest::Method;
+
+use crate::APP_VERSION;
+use crate::error::{self, CliError, ErrorFormat};
+use crate::output::OutputFormat;
+
+/// Top-level CLI for brust.
+///
+/// Running without a subcommand falls back to the deprecated flat flags in
+/// [`LegacyArgs`], which greet and then optionally run count and fetch.
+///
+/// Values left unset on the command line fall back to `BRUST_*` environment
+/// variables, then the config file, then built-in defaults.
+#[derive(Debug, Parser)]
+#[command(about, version = APP_VERSION)]
+pub struct Cli {
+    /// Config file to load [default: `$XDG_CONFIG_HOME/brust/config.toml`]
+    #[arg(long, global = true, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+    /// Log verbosity.
+    #[command(flatten)]
+    pub verbosity: Verbosity,
+    /// How a failure is reported on stderr
+    #[arg(long, global = true, value_enum, default_value_t)]
+    pub error_format: ErrorFormat,
+    /// Subcommand to run.
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+    /// Deprecated top-level flags (used only when no subcommand is given).
+    #[command(flatten)]
+    pub legacy: LegacyArgs,
+}
+
+impl Cli {
+    /// Parse `std::env::args`, exiting with a usage error on invalid input.
+    ///
+    /// Unlike [`Parser::parse`], this also rejects deprecated top-level flags
+    /// combined with a subcommand (`brust --url U greet`), and reports usage
+    /// errors as JSON when `--error-format json` appears on the command line.
+    #[must_use]
+    pub fn parse_args() -> Self {
+        match Self::try_parse().and_then(|cli| cli.check_legacy().map(|()| cli)) {
+            Ok(cli) => cli,
+            // --help and --version are not failures; clap prints them to stdout.
+            Err(e) if !e.use_stderr() => e.exit(),
+            Err(e) => {
+                if ErrorFormat::from_args(std::env::args_os()) == ErrorFormat::Json {
+                    let e = CliError::from(e);
+                    // Nothing is left to report a failed stderr write to.
+                    let _ = error::report(&e, ErrorFormat::Json, std::io::stderr().lock());
+                    std::process::exit(i32::from(e.kind.exit_code()));
+                }
+                e.exit()
+            }
+        }
+    }
+
+    /// Reject deprecated top-level flags when a subcommand is present.
+    ///
+    /// `args_conflicts_with_subcommands` cannot be used here because it also
+    /// rejects the global `--config` flag.
+    fn check_legacy(&self) -> Result<(), clap::Error> {
+        let legacy = &self.legacy;
+        let legacy_used = legacy.name.is_some()
+            || legacy.gender.is_some()
+            || legacy.count.is_some()
+            || legacy.url.is_some();
+        if self.command.is_some() && legacy_used {
+            return Err(Self::command().error(
+                ErrorKind::ArgumentConflict,
+                "deprecated top-level flags cannot be combined with a subcommand",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Output format requested by the subcommand; `text` when it has none.
+    #[must_use]
+    pub const fn output_format(&self) -> OutputFormat {
+        match &self.command {
+            Some(
+                Commands::Greet(GreetArgs { output, .. })
+                | Commands::Count(CountArgs { output, .. })
+                | Commands::Fetch(FetchArgs { output, .. }),
+            ) => output.output,
+            _ => OutputFormat::Text,
+        }
+    }
+}
+
+/// `--quiet`/`--verbose` flags controlling how much is logged to stderr.
+///
+/// They only affect the diagnostic log, never command results on stdout.
+#[derive(Debug, Clone, Copy, Default, clap::Args)]
+pub struct Verbosity {
+    /// Log less: `-q` warnings and errors, `-qq` errors only, `-qqq` nothing
+    #[arg(short, long, global = true, action = ArgAction::Count, conflicts_with = "verbose")]
+    pub quiet: u8,
+    /// Log more: `-v` debug, `-vv` trace
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    pub verbose: u8,
+}
+
+impl Verbosity {
+    /// `EnvFilter` level selected by the flags, or `None` when neither was
+    /// given and `RUST_LOG` or the default level applies.
+    #[must_use]
+    pub const fn level(self) -> Option<&'static str> {
+        match (self.quiet, self.verbose) {
+            (0, 0) => None,
+            (0, 1) => Some("debug"),
+            (0, _) => Some("trace"),
+            (1, _) => Some("warn"),
+            (2, _) => Some("error"),
+            _ => Some("off"),
+        }
+    }
+}
+
+/// Available subcommands.
+#[derive(Debug, Subcommand)]
+pub enum Commands {
+    /// Print a greeting.
+    Greet(GreetArgs),
+    /// Run iterations with random delays (metrics demo).
+    Count(CountArgs),
+    /// Send HTTP requests to one or more URLs (HTTP client metrics demo).
+    Fetch(FetchArgs),
+    /// Greet every record of a CSV or JSON Lines input.
+    Batch(BatchArgs),
+    /// Inspect the layered configuration.
+    #[command(subcommand)]
+    Config(ConfigCommand),
+    /// Print a shell completion script.
+    Completions(CompletionsArgs),
+    /// Write man pages for brust and every subcommand.
+    Man(ManArgs),
+}
+
+/// Subcommands of `brust config`.
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommand {
+    /// Print the effective merged configuration and where each value came from.
+    Show,
+}
+
+/// Arguments for the `greet` subcommand.
+#[derive(Debug, clap::Args)]
+pub struct GreetArgs {
+    /// Name of the person to greet [default: Youre]
+    #[arg(short, long)]
+    pub name: Option<String>,
+    /// Built-in honorific to greet with
+    #[arg(short, long, value_enum)]
+    pub gender: Option<Gender>,
+    /// Custom honorific prefix, e.g. `Sir` (at most 32 characters)
+    #[arg(long, value_name = "TEXT", conflicts_with = "gender")]
+    pub honorific: Option<String>,
+    /// Greeting line template, e.g. `"Good {time_of_day}, {title} {name}!"`
+    /// (placeholders: name, title, addressee, locale, `time_of_day`)
+    #[arg(short, long, value_name = "TEMPLATE")]
+    pub template: Option<String>,
+    /// Greeting language, e.g. `en` or `ja_JP` [default: from `LC_ALL`/`LC_MESSAGES`/`LANG`]
+    #[arg(short, long)]
+    pub locale: Option<String>,
+    /// Output options.
+    #[command(flatten)]
+    pub output: OutputArgs,
+}
+
+/// Arguments for the `count` subcommand.
+#[derive(Debug, clap::Args)]
+pub struct CountArgs {
+    /// Number of iterations to run [config: `count.iterations`]
+    #[arg(short, long)]
+    pub count: Option<u32>,
+    /// Delay distribution in milliseconds: `fixed:MS`, `uniform:MIN,MAX`,
+    /// `exponential:MEAN`, `normal:MEAN,STDDEV` or `lognormal:MEAN,STDDEV`
+    /// [default: uniform:1000,5000] [config: `count.delay`]
+    #[arg(short, long, value_name = "DIST")]
+    pub delay: Option<Delay>,
+    /// Seed for reproducible delays [config: `count.seed`]
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Number of iterations that may run at once [default: 1]
+    /// [config: `count.concurrency`]
+    #[arg(short = 'j', long, value_name = "N")]
+    pub concurrency: Option<NonZeroU32>,
+    /// Keep starting iterations for this long, e.g. `30s`, `10m` or `1h30m`;
+    /// with `--count` too, whichever limit is reached first stops the run
+    /// [config: `count.duration`]
+    #[arg(long, value_name = "TIME")]
+    pub duration: Option<HumanDuration>,
+    /// Start iterations on a fixed schedule, e.g. `20/s`, `5/m` or `3/10s`,
+    /// and report how late each one started [config: `count.rate`]
+    #[arg(long, value_name = "COUNT/PERIOD")]
+    pub rate: Option<Rate>,
+    /// Also write every iteration to FILE as CSV
+    /// (`iteration,delay_secs,lag_secs,error`), even when interrupted
+    #[arg(long, value_name = "FILE")]
+    pub csv: Option<PathBuf>,
+    /// Probability between 0 and 1 that an iteration fails on purpose
+    /// [default: 0] [config: `count.error_rate`]
+    #[arg(long, value_name = "P")]
+    pub error_rate: Option<ErrorRate>,
+    /// Comma-separated kinds failing iterations report: `timeout`,
+    /// `panic_like`, `invalid` [default: all] [config: `count.error_kinds`]
+    #[arg(long, value_name = "KINDS", value_delimiter = ',')]
+    pub error_kinds: Option<Vec<IterationError>>,
+    /// Link each iteration span to the one started before it
+    /// [config: `count.span_links`]
+    #[arg(long, overrides_with = "no_span_links")]
+    pub span_links: bool,
+    /// Do not link iteration spans, even if `count.span_links` is set
+    #[arg(long, overrides_with = "span_links")]
+    pub no_span_links: bool,
+    /// Output options.
+    #[command(flatten)]
+    pub output: OutputArgs,
+}
+
+/// Arguments for the `fetch` subcommand.
+#[derive(Debug, clap::Args)]
+pub struct FetchArgs {
+    /// URL to fetch; repeat to fetch several in turn over one client
+    /// [config: `http.url`]
+    #[arg(short, long = "url", value_name = "URL")]
+    pub urls: Vec<String>,
+    /// File with more URLs to fetch, one per line; `-` reads stdin
+    #[arg(long, value_name = "FILE")]
+    pub url_file: Option<PathBuf>,
+    /// Request method, e.g. `POST` [default: GET, or POST with --data]
+    #[arg(short = 'X', long, value_name = "METHOD", value_parser = parse_method)]
+    pub method: Option<Method>,
+    /// Request header `NAME: VALUE`; repeat for more headers
+    #[arg(short = 'H', long = "header", value_name = "NAME: VALUE")]
+    pub headers: Vec<Header>,
+    /// Request body: the text itself, `@FILE` to read a file or `@-` to read
+    /// stdin; JSON object and array bodies are sent as `application/json`
+    #[arg(short, long, value_name = "DATA")]
+    pub data: Option<String>,
+    /// Write the response body to FILE as it arrives; `-` writes it to
+    /// stdout instead of the result line. Takes a single URL
+    #[arg(long, value_name = "FILE")]
+    pub save: Option<PathBuf>,
+    /// Longest wait to establish a connection [default: 10s]
+    /// [config: `http.connect_timeout`]
+    #[arg(long, value_name = "TIME")]
+    pub connect_timeout: Option<HumanDuration>,
+    /// Longest wait for each read from the connection [default: none]
+    /// [config: `http.read_timeout`]
+    #[arg(long, value_name = "TIME")]
+    pub read_timeout: Option<HumanDuration>,
+    /// Longest time for each attempt, from connecting to reading the body
+    /// [default: 30s] [config: `http.timeout`]
+    #[arg(long, value_name = "TIME")]
+    pub timeout: Option<HumanDuration>,
+    /// Largest response body to read, e.g. `512KiB` or `10MB`
+    /// [default: none] [config: `http.max_size`]
+    #[arg(long, value_name = "SIZE")]
+    pub max_size: Option<ByteSize>,
+    /// Attempts per request, including the first [default: 1]
+    /// [config: `http.max_attempts`]
+    #[arg(long, value_name = "N")]
+    pub max_attempts: Option<NonZeroU32>,
+    /// Wait before the first retry, doubled for each further one
+    /// [default: 100ms] [config: `http.retry_base_delay`]
+    #[arg(long, value_name = "TIME")]
+    pub retry_base_delay: Option<HumanDuration>,
+    /// Longest wait between attempts; a longer `Retry-After` stops retrying
+    /// [default: 10s] [config: `http.retry_max_delay`]
+    #[arg(long, value_name = "TIME")]
+    pub retry_max_delay: Option<HumanDuration>,
+    /// Largest share between 0 and 1 randomly cut from each wait
+    /// [default: 0.5] [config: `http.retry_jitter`]
+    #[arg(long, value_name = "SHARE")]
+    pub retry_jitter: Option<Jitter>,
+    /// Comma-separated response statuses to retry
+    /// [default: 429,502,503,504] [config: `http.retry_statuses`]
+    #[arg(long, value_name = "CODES", value_delimiter = ',')]
+    pub retry_statuses: Option<Vec<u16>>,
+    /// Comma-separated request errors to retry: `timeout`,
+    /// `connection_refused`, `dns`, `tls`, `body_read`, `network`
+    /// [default: `timeout`, `connection_refused`, `body_read`, `network`]
+    /// [config: `http.retry_errors`]
+    #[arg(long, value_name = "KINDS", value_delimiter = ',')]
+    pub retry_errors: Option<Vec<RetryableError>>,
+    /// Retry `POST`, `PATCH` and other non-idempotent requests too
+    /// [config: `http.retry_non_idempotent`]
+    #[arg(long, overrides_with = "no_retry_non_idempotent")]
+    pub retry_non_idempotent: bool,
+    /// Send non-idempotent requests once, even if `http.retry_non_idempotent`
+    /// is set
+    #[arg(long, overrides_with = "retry_non_idempotent")]
+    pub no_retry_non_idempotent: bool,
+    /// Output options.
+    #[command(flatten)]
+    pub output: OutputArgs,
+}
+
+/// Value of a `--flag` / `--no-flag` pair: `None` when neither is given,
+/// otherwise whichever came last.
+pub const fn flag_pair(flag: bool, no_flag: bool) -> Option<bool> {
+    if flag {
+        Some(true)
+    } else if no_flag {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Parse an HTTP method case-insensitively, so `post` is `POST`.
+fn parse_method(s: &str) -> Result<Method, String> {
+    Method::from_bytes(s.to_ascii_uppercase().as_bytes())
+        .map_err(|_| format!("invalid HTTP method {s:?}"))
+}
+
+/// Output selection shared by `greet`, `count` and `fetch`.
+#[derive(Debug, clap::Args)]
+pub struct OutputArgs {
+    /// Output format; `json` and `yaml` print one versioned document to stdout
+    #[arg(short, long, value_enum, default_value_t)]
+    pub output: OutputFormat,
+}
+
+/// Arguments for the `batch` subcommand.
+#[derive(Debug, clap::Args)]
+pub struct BatchArgs {
+    /// Input file with `name`, `gender` and `locale` fields; `-` or omitted reads stdin
+    #[arg(value_name = "FILE")]
+    pub input: Option<PathBuf>,
+    /// Input and output record format [default: from the input file extension]
+    #[arg(short, long, value_enum)]
+    pub format: Option<Format>,
+}
+
+/// Arguments for the `completions` subcommand.
+#[derive(Debug, clap::Args)]
+pub struct CompletionsArgs {
+    /// Shell to generate the completion script for
+    #[arg(value_enum)]
+    pub shell: Shell,
+}
+
+/// Arguments for the `man` subcommand.
+#[derive(Debug, clap::Args)]
+pub struct ManArgs {
+    /// Directory to write the roff pages into (created if missing)
+    #[arg(value_name = "DIR")]
+    pub out_dir: PathBuf,
+}
+
+/// Flat flags from before subcommands were introduced.
+///
+/// Kept as a compatibility path: `brust --name X --count N --url U` still
+/// greets, then runs count and fetch. Prefer the subcommands instead.
+#[derive(Debug, clap::Args)]
+#[command(next_help_heading = "Deprecated options (use subcommands instead)")]
+pub struct LegacyArgs {
+    /// Name of the person to greet [deprecated: use `brust greet --name`]
+    #[arg(short, long)]
+    pub name: Option<String>,
+    /// Built-in honorific to greet with [deprecated: use `brust greet --gender`]
+    ///
+    /// Kept as free text: an unknown value greets without an honorific and
+    /// exits with the invalid-input code instead of failing to parse.
+    #[arg(short, long, value_name = "GENDER")]
+    pub gender: Option<String>,
+    /// Number of iterations to run [deprecated: use `brust count --count`]
+    #[arg(short = 'c', long = "count")]
+    pub count: Option<u32>,
+    /// URL to fetch via HTTP GET [deprecated: use `brust fetch --url`]
+    #[arg(short = 'u', long = "url")]
+    pub url: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::{CommandFactory as _, Parser as _};
+
+    use super::{Cli, Commands, ConfigCommand, Verbosity};
+    use brust::libs::hello::honorific::Gender;
+
+    #[test]
+    fn cli_definition_is_valid() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn parses_fetch_subcommand_without_greeting_flags() {
+        let cli = Cli::try_parse_from(["brust", "fetch", "--url", "http://127.0.0.1/"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Fetch(ref args)) if args.urls == ["http://127.0.0.1/"]
+        ));
+    }
+
+    #[test]
+    fn parses_gender_aliases_and_rejects_custom_with_gender() {
+        let cli = Cli::try_parse_from(["brust", "greet", "--gender", "mx"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Greet(ref args)) if args.gender == Some(Gender::Neutral)
+        ));
+        assert!(Cli::try_parse_from(["brust", "greet", "--gender", "other"]).is_err());
+        assert!(
+            Cli::try_parse_from(["brust", "greet", "--gender", "dr", "--honorific", "Sir"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn parses_legacy_flags_without_subcommand() {
+        let cli = Cli::try_parse_from(["brust", "-n", "Alice", "-c", "2"]).unwrap();
+        assert!(cli.command.is_none());
+        assert_eq!(cli.legacy.name.as_deref(), Some("Alice"));
+        assert_eq!(cli.legacy.count, Some(2));
+    }
+
+    #[test]
+    fn parses_global_config_flag_after_subcommand() {
+        let cli =
+            Cli::try_parse_from(["brust", "config", "show", "--config", "brust.toml"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Config(ConfigCommand::Show))
+        ));
+        assert_eq!(
+            cli.config.as_deref(),
+            Some(std::path::Path::new("brust.toml"))
+        );
+    }
+
+    #[test]
+    fn verbosity_flags_map_to_levels() {
+        let level = |args: &[&str]| {
+            Cli::try_parse_from(args)
+                .map(|cli| cli.verbosity.level())
+                .unwrap()
+        };
+        assert_eq!(level(&["brust", "greet"]), None);
+        assert_eq!(level(&["brust", "-v", "greet"]), Some("debug"));
+        assert_eq!(level(&["brust", "greet", "-vv"]), Some("trace"));
+        assert_eq!(level(&["brust", "count", "-q"]), Some("warn"));
+        assert_eq!(level(&["brust", "-qq", "fetch"]), Some("error"));
+        assert_eq!(
+            Verbosity {
+                quiet: 5,
+                verbose: 0
+            }
+            .level(),
+            Some("off")
+        );
+        assert!(Cli::try_parse_from(["brust", "-q", "-v", "greet"]).is_err());
+    }
+
+    #[test]
+    fn rejects_legacy_flags_mixed_with_subcommand() {
+        let cli = Cli::try_parse_from(["brust", "--url", "http://127.0.0.1/", "greet"]).unwrap();
+        let result = cli.check_legacy();
+        assert!(
+            result.is_err(),
+            "legacy flags must not combine with subcommands"
+        );
+    }
+}
@@ -0,0 +1,379 @@
+//! Layered configuration for brust.
+//!
+//! Values are merged in increasing precedence:
+//! built-in defaults < TOML config file < `BRUST_*` env vars < CLI flags.
+//! The merge records which layer supplied each key so `brust config show`
+//! can explain where the effective value came from.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use brust::libs::hello::honorific::{Gender, Honorific};
+use brust::libs::http::{self, retry};
+
+use crate::cli::Cli;
+
+use self::keys::KEYS;
+use self::layer::Layer;
+
+mod keys;
+mod layer;
+mod merge;
+
+pub use self::keys::Source;
+
+/// Count demo settings.
+pub use brust::libs::count::Settings as CountConfig;
+
+/// Name greeted when none is configured.
+pub const DEFAULT_NAME: &str = "Youre";
+
+/// `User-Agent` sent by the HTTP client when none is configured.
+pub const DEFAULT_USER_AGENT: &str =
+    concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+/// Greeting defaults.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GreetConfig {
+    /// Name of the person to greet.
+    pub name: String,
+    /// Built-in honorific choice.
+    pub gender: Option<Gender>,
+    /// Custom honorific prefix; takes priority over `gender`.
+    pub honorific: Option<String>,
+    /// Greeting locale tag; the `LC_ALL`/`LC_MESSAGES`/`LANG` chain applies when unset.
+    pub locale: Option<String>,
+    /// Greeting line template; the locale's message catalog is used when unset.
+    pub template: Option<String>,
+}
+
+/// HTTP client settings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpConfig {
+    /// URL fetched by `brust fetch`.
+    pub url: Option<String>,
+    /// `User-Agent` header sent with every request.
+    pub user_agent: String,
+    /// Limit on establishing a connection.
+    pub connect_timeout: Option<Duration>,
+    /// Limit on each read from the connection.
+    pub read_timeout: Option<Duration>,
+    /// Limit on a whole attempt.
+    pub timeout: Option<Duration>,
+    /// Limit on the size of a response body in bytes.
+    pub max_size: Option<u64>,
+    /// When failed requests are sent again.
+    pub retry: retry::Policy,
+}
+
+/// Telemetry settings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TelemetryConfig {
+    /// OTLP/HTTP base endpoint; telemetry export is disabled when unset.
+    pub endpoint: Option<String>,
+    /// `service.name` resource attribute.
+    pub service_name: String,
+}
+
+/// Effective configuration after merging every layer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    /// Greeting defaults.
+    pub greet: GreetConfig,
+    /// Count demo settings.
+    pub count: CountConfig,
+    /// HTTP client settings.
+    pub http: HttpConfig,
+    /// Telemetry settings.
+    pub telemetry: TelemetryConfig,
+    /// Config file that was considered, if any, and whether it was loaded.
+    file: Option<(PathBuf, bool)>,
+    /// Layer that supplied each key.
+    sources: BTreeMap<&'static str, Source>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            greet: GreetConfig {
+                name: String::from(DEFAULT_NAME),
+                gender: None,
+                honorific: None,
+                locale: None,
+                template: None,
+            },
+            count: CountConfig::default(),
+            http: HttpConfig {
+                url: None,
+                user_agent: String::from(DEFAULT_USER_AGENT),
+                connect_timeout: Some(http::DEFAULT_CONNECT_TIMEOUT),
+                read_timeout: None,
+                timeout: Some(http::DEFAULT_TIMEOUT),
+                max_size: None,
+                retry: retry::Policy::default(),
+            },
+            telemetry: TelemetryConfig {
+                endpoint: None,
+                service_name: String::from(env!("CARGO_PKG_NAME")),
+            },
+            file: None,
+            sources: KEYS
+                .iter()
+                .map(|&(key, _, _)| (key, Source::Default))
+                .collect(),
+        }
+    }
+}
+
+impl GreetConfig {
+    /// Honorific to greet with: the custom prefix if set, else `gender`.
+    #[must_use]
+    pub fn honorific(&self) -> Option<Honorific> {
+        self.honorific
+            .clone()
+            .map(Honorific::Custom)
+            .or_else(|| self.gender.map(Honorific::from))
+    }
+}
+
+impl HttpConfig {
+    /// Options to build the HTTP client with.
+    #[must_use]
+    pub fn client_options(&self) -> http::ClientOptions {
+        http::ClientOptions {
+            user_agent: Some(self.user_agent.clone()),
+            connect_timeout: self.connect_timeout,
+            read_timeout: self.read_timeout,
+            timeout: self.timeout,
+            retry: self.retry.clone(),
+            max_body_size: self.max_size,
+        }
+    }
+}
+
+impl Config {
+    /// Load and merge every configuration layer for the parsed CLI.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an explicit `--config` file is missing, a config
+    /// file cannot be read or parsed, or an env var holds an invalid value.
+    pub fn load(cli: &Cli) -> anyhow::Result<Self> {
+        Self::load_with(cli, |var| std::env::var(var).ok())
+    }
+
+    /// Load configuration using `env` to look up environment variables.
+    fn load_with(cli: &Cli, env: impl Fn(&str) -> Option<String>) -> anyhow::Result<Self> {
+        let mut config = Self::default();
+
+        let explicit = cli.config.clone();
+        if let Some(path) = explicit.clone().or_else(|| default_path(&env)) {
+            let found = explicit.is_some() || path.is_file();
+            if found {
+                let layer = Layer::from_file(&path)?;
+                config.merge(layer, |_| Source::File(path.clone()));
+            }
+            config.file = Some((path, found));
+        }
+
+        let (layer, vars) = Layer::from_env(&env)?;
+        config.merge(layer, |key| {
+            vars.get(key)
+                .map_or(Source::Default, |&var| Source::Env(var))
+        });
+
+        config.merge(Layer::from_cli(cli), |key| {
+            KEYS.iter()
+                .find(|&&(k, _, _)| k == key)
+                .and_then(|&(_, _, flag)| flag)
+                .map_or(Source::Default, Source::Flag)
+        });
+
+        Ok(config)
+    }
+
+    /// Layer that supplied the effective value for `key` (e.g. `"greet.name"`).
+    #[must_use]
+    pub fn source(&self, key: &str) -> Option<&Source> {
+        self.sources.get(key)
+    }
+
+    /// Endpoint to set on the OTLP exporters: `telemetry.endpoint`, unless it
+    /// came from `OTEL_EXPORTER_OTLP_ENDPOINT`.
+    ///
+    /// The exporters read that variable themselves, together with the
+    /// per-signal `OTEL_EXPORTER_OTLP_{TRACES,LOGS,METRICS}_ENDPOINT` that an
+    /// endpoint set in code would override.
+    #[must_use]
+    #[cfg_attr(not(feature = "otel"), expect(dead_code))]
+    pub fn exporter_endpoint(&self) -> Option<&str> {
+        let from_otel_env = matches!(
+            self.source("telemetry.endpoint"),
+            Some(Source::Env("OTEL_EXPORTER_OTLP_ENDPOINT"))
+        );
+        self.telemetry
+            .endpoint
+            .as_deref()
+            .filter(|_| !from_otel_env)
+    }
+}
+
+/// Default config file path: `$XDG_CONFIG_HOME/brust/config.toml`, falling
+/// back to `$HOME/.config/brust/config.toml`.
+fn default_path(env: &impl Fn(&str) -> Option<String>) -> Option<PathBuf> {
+    env("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            env("HOME")
+                .filter(|home| !home.is_empty())
+                .map(|home| Path::new(&home).join(".config"))
+        })
+        .map(|dir| dir.join(env!("CARGO_PKG_NAME")).join("config.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use clap::Parser as _;
+
+    use super::{Config, DEFAULT_NAME, Source};
+    use brust::libs::hello::honorific::{Gender, Honorific};
+
+    use crate::cli::Cli;
+
+    /// Load the configuration for `args` with `vars` as the environment.
+    pub(super) fn load(args: &[&str], vars: &[(&str, &str)]) -> anyhow::Result<Config> {
+        let cli = Cli::try_parse_from(args).unwrap();
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|&(k, v)| (k.to_owned(), v.to_owned()))
+            .collect();
+        Config::load_with(&cli, |var| vars.get(var).cloned())
+    }
+
+    /// Write `body` to a `config.toml` in `dir`.
+    pub(super) fn write_config(dir: &tempfile::TempDir, body: &str) -> std::path::PathBuf {
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, body).unwrap();
+        path
+    }
+
+    #[test]
+    fn defaults_when_no_layers_present() {
+        let config = load(&["brust", "greet"], &[]).unwrap();
+        assert_eq!(config.greet.name, DEFAULT_NAME);
+        assert_eq!(config.source("greet.name"), Some(&Source::Default));
+        assert_eq!(config.telemetry.service_name, env!("CARGO_PKG_NAME"));
+    }
+
+    #[test]
+    fn file_then_env_then_flag_precedence() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_config(
+            &dir,
+            "[greet]\nname = \"File\"\ngender = \"woman\"\n[count]\niterations = 3\n",
+        );
+        let path_str = path.to_str().unwrap();
+
+        let config = load(
+            &["brust", "--config", path_str, "greet", "--name", "Flag"],
+            &[("BRUST_GREET_NAME", "Env"), ("BRUST_COUNT_ITERATIONS", "7")],
+        )
+        .unwrap();
+
+        assert_eq!(config.greet.name, "Flag");
+        assert_eq!(config.source("greet.name"), Some(&Source::Flag("--name")));
+        assert_eq!(config.greet.gender, Some(Gender::Woman));
+        assert_eq!(config.source("greet.gender"), Some(&Source::File(path)));
+        assert_eq!(config.count.iterations, Some(7));
+        assert_eq!(
+            config.source("count.iterations"),
+            Some(&Source::Env("BRUST_COUNT_ITERATIONS"))
+        );
+    }
+
+    #[test]
+    fn default_path_uses_xdg_config_home() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("brust")).unwrap();
+        std::fs::write(
+            dir.path().join("brust").join("config.toml"),
+            "[http]\nurl = \"http://127.0.0.1/\"\n",
+        )
+        .unwrap();
+
+        let config = load(
+            &["brust", "fetch"],
+            &[("XDG_CONFIG_HOME", dir.path().to_str().unwrap())],
+        )
+        .unwrap();
+        assert_eq!(config.http.url.as_deref(), Some("http://127.0.0.1/"));
+    }
+
+    #[test]
+    fn missing_default_file_is_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = load(
+            &["brust", "greet"],
+            &[("XDG_CONFIG_HOME", dir.path().to_str().unwrap())],
+        )
+        .unwrap();
+        assert!(config.render().contains("(not found)"));
+    }
+
+    #[test]
+    fn missing_explicit_file_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("absent.toml");
+        let result = load(&["brust", "--config", path.to_str().unwrap(), "greet"], &[]);
+        assert!(result.is_err(), "explicit --config must exist");
+    }
+
+    #[test]
+    fn higher_layer_gender_replaces_lower_layer_honorific() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_config(&dir, "[greet]\nhonorific = \"Sir\"\n");
+        let path_str = path.to_str().unwrap();
+
+        let config = load(&["brust", "--config", path_str, "greet"], &[]).unwrap();
+        assert_eq!(
+            config.greet.honorific(),
+            Some(Honorific::Custom(String::from("Sir")))
+        );
+
+        let config = load(
+            &["brust", "--config", path_str, "greet", "--gender", "dr"],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(config.greet.honorific(), Some(Honorific::Dr));
+        assert_eq!(config.source("greet.honorific"), Some(&Source::Default));
+    }
+
+    #[test]
+    fn exporter_endpoint_is_left_to_the_exporter_for_otel_env() {
+        let otel = [("OTEL_EXPORTER_OTLP_ENDPOINT", "http://127.0.0.1:4318")];
+        let config = load(&["brust", "greet"], &otel).unwrap();
+        assert_eq!(
+            config.telemetry.endpoint.as_deref(),
+            Some("http://127.0.0.1:4318")
+        );
+        assert_eq!(config.exporter_endpoint(), None);
+
+        let brust = [("BRUST_TELEMETRY_ENDPOINT", "http://127.0.0.1:4319")];
+        let config = load(&["brust", "greet"], &brust).unwrap();
+        assert_eq!(config.exporter_endpoint(), Some("http://127.0.0.1:4319"));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_config(&dir, "[telemetry]\nendpoint = \"http://127.0.0.1:4320\"\n");
+        let path_str = path.to_str().unwrap();
+        let config = load(&["brust", "--config", path_str, "greet"], &[]).unwrap();
+        assert_eq!(config.exporter_endpoint(), Some("http://127.0.0.1:4320"));
+        // The environment still beats the file.
+        let config = load(&["brust", "--config", path_str, "greet"], &otel).unwrap();
+        assert_eq!(config.exporter_endpoint(), None);
+    }
+}
@@ -0,0 +1,337 @@
+//! Configurable keys, the layer each value came from, and the annotated
+//! TOML that `brust config show` prints.
+
+use std::fmt::{self, Write as _};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use brust::libs::hello::honorific::Gender;
+use brust::libs::units::{ByteSize, HumanDuration};
+
+use super::{Config, CountConfig, HttpConfig};
+
+/// Every configurable key with its env vars (highest priority first) and CLI flag.
+pub(super) const KEYS: [(&str, &[&str], Option<&str>); 29] = [
+    ("greet.name", &["BRUST_GREET_NAME"], Some("--name")),
+    ("greet.gender", &["BRUST_GREET_GENDER"], Some("--gender")),
+    (
+        "greet.honorific",
+        &["BRUST_GREET_HONORIFIC"],
+        Some("--honorific"),
+    ),
+    ("greet.locale", &["BRUST_GREET_LOCALE"], Some("--locale")),
+    (
+        "greet.template",
+        &["BRUST_GREET_TEMPLATE"],
+        Some("--template"),
+    ),
+    (
+        "count.iterations",
+        &["BRUST_COUNT_ITERATIONS"],
+        Some("--count"),
+    ),
+    ("count.delay", &["BRUST_COUNT_DELAY"], Some("--delay")),
+    ("count.seed", &["BRUST_COUNT_SEED"], Some("--seed")),
+    (
+        "count.concurrency",
+        &["BRUST_COUNT_CONCURRENCY"],
+        Some("--concurrency"),
+    ),
+    (
+        "count.duration",
+        &["BRUST_COUNT_DURATION"],
+        Some("--duration"),
+    ),
+    ("count.rate", &["BRUST_COUNT_RATE"], Some("--rate")),
+    (
+        "count.error_rate",
+        &["BRUST_COUNT_ERROR_RATE"],
+        Some("--error-rate"),
+    ),
+    (
+        "count.error_kinds",
+        &["BRUST_COUNT_ERROR_KINDS"],
+        Some("--error-kinds"),
+    ),
+    (
+        "count.span_links",
+        &["BRUST_COUNT_SPAN_LINKS"],
+        Some("--[no-]span-links"),
+    ),
+    ("http.url", &["BRUST_HTTP_URL"], Some("--url")),
+    ("http.user_agent", &["BRUST_HTTP_USER_AGENT"], None),
+    (
+        "http.connect_timeout",
+        &["BRUST_HTTP_CONNECT_TIMEOUT"],
+        Some("--connect-timeout"),
+    ),
+    (
+        "http.read_timeout",
+        &["BRUST_HTTP_READ_TIMEOUT"],
+        Some("--read-timeout"),
+    ),
+    ("http.timeout", &["BRUST_HTTP_TIMEOUT"], Some("--timeout")),
+    (
+        "http.max_size",
+        &["BRUST_HTTP_MAX_SIZE"],
+        Some("--max-size"),
+    ),
+    (
+        "http.max_attempts",
+        &["BRUST_HTTP_MAX_ATTEMPTS"],
+        Some("--max-attempts"),
+    ),
+    (
+        "http.retry_base_delay",
+        &["BRUST_HTTP_RETRY_BASE_DELAY"],
+        Some("--retry-base-delay"),
+    ),
+    (
+        "http.retry_max_delay",
+        &["BRUST_HTTP_RETRY_MAX_DELAY"],
+        Some("--retry-max-delay"),
+    ),
+    (
+        "http.retry_jitter",
+        &["BRUST_HTTP_RETRY_JITTER"],
+        Some("--retry-jitter"),
+    ),
+    (
+        "http.retry_statuses",
+        &["BRUST_HTTP_RETRY_STATUSES"],
+        Some("--retry-statuses"),
+    ),
+    (
+        "http.retry_errors",
+        &["BRUST_HTTP_RETRY_ERRORS"],
+        Some("--retry-errors"),
+    ),
+    (
+        "http.retry_non_idempotent",
+        &["BRUST_HTTP_RETRY_NON_IDEMPOTENT"],
+        Some("--[no-]retry-non-idempotent"),
+    ),
+    (
+        "telemetry.endpoint",
+        &["BRUST_TELEMETRY_ENDPOINT", "OTEL_EXPORTER_OTLP_ENDPOINT"],
+        None,
+    ),
+    (
+        "telemetry.service_name",
+        &["BRUST_TELEMETRY_SERVICE_NAME", "OTEL_SERVICE_NAME"],
+        None,
+    ),
+];
+
+/// Where an effective configuration value came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// Built-in default.
+    Default,
+    /// TOML config file at the given path.
+    File(PathBuf),
+    /// Environment variable with the given name.
+    Env(&'static str),
+    /// Command-line flag with the given name.
+    Flag(&'static str),
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::File(path) => write!(f, "file {}", path.display()),
+            Self::Env(var) => write!(f, "env {var}"),
+            Self::Flag(flag) => write!(f, "flag {flag}"),
+        }
+    }
+}
+
+impl Config {
+    /// Render the effective configuration as TOML annotated with sources.
+    ///
+    /// Unset optional keys are emitted as comments so the output can be used
+    /// as a starting point for a config file.
+    #[must_use]
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        match &self.file {
+            Some((path, true)) => {
+                let _ = writeln!(out, "# config file: {}", path.display());
+            }
+            Some((path, false)) => {
+                let _ = writeln!(out, "# config file: {} (not found)", path.display());
+            }
+            None => {
+                let _ = writeln!(out, "# config file: none");
+            }
+        }
+
+        let string = |s: &str| Some(toml::Value::String(s.to_owned()));
+        let sections = [
+            (
+                "greet",
+                vec![
+                    ("name", string(&self.greet.name)),
+                    (
+                        "gender",
+                        self.greet.gender.map(Gender::as_str).and_then(string),
+                    ),
+                    (
+                        "honorific",
+                        self.greet.honorific.as_deref().and_then(string),
+                    ),
+                    ("locale", self.greet.locale.as_deref().and_then(string)),
+                    ("template", self.greet.template.as_deref().and_then(string)),
+                ],
+            ),
+            ("count", count_entries(&self.count)),
+            ("http", http_entries(&self.http)),
+            (
+                "telemetry",
+                vec![
+                    (
+                        "endpoint",
+                        self.telemetry.endpoint.as_deref().and_then(string),
+                    ),
+                    ("service_name", string(&self.telemetry.service_name)),
+                ],
+            ),
+        ];
+
+        for (section, entries) in sections {
+            let _ = writeln!(out, "\n[{section}]");
+            for (name, value) in entries {
+                let source = self
+                    .source(&format!("{section}.{name}"))
+                    .map_or_else(String::new, ToString::to_string);
+                match value {
+                    Some(value) => {
+                        let _ = writeln!(out, "{name} = {value} # {source}");
+                    }
+                    None => {
+                        let _ = writeln!(out, "# {name} is unset # {source}");
+                    }
+                }
+            }
+        }
+
+        out
+    }
+}
+
+/// `[count]` keys and their values for [`Config::render`]; `None` for
+/// unset keys.
+fn count_entries(count: &CountConfig) -> Vec<(&'static str, Option<toml::Value>)> {
+    let string = |s: &str| Some(toml::Value::String(s.to_owned()));
+    vec![
+        (
+            "iterations",
+            count.iterations.map(|n| toml::Value::Integer(i64::from(n))),
+        ),
+        ("delay", string(&count.delay.to_string())),
+        (
+            "seed",
+            count
+                .seed
+                .and_then(|n| i64::try_from(n).ok())
+                .map(toml::Value::Integer),
+        ),
+        (
+            "concurrency",
+            Some(toml::Value::Integer(i64::from(count.concurrency.get()))),
+        ),
+        (
+            "duration",
+            count
+                .duration
+                .and_then(|d| string(&HumanDuration(d).to_string())),
+        ),
+        ("rate", count.rate.and_then(|r| string(&r.to_string()))),
+        (
+            "error_rate",
+            Some(toml::Value::Float(count.error_rate.get())),
+        ),
+        (
+            "error_kinds",
+            Some(toml::Value::Array(
+                count
+                    .error_kinds
+                    .iter()
+                    .filter_map(|kind| string(kind.as_str()))
+                    .collect(),
+            )),
+        ),
+        ("span_links", Some(toml::Value::Boolean(count.span_links))),
+    ]
+}
+
+/// `[http]` keys and their values for [`Config::render`]; `None` for
+/// unset keys.
+fn http_entries(http: &HttpConfig) -> Vec<(&'static str, Option<toml::Value>)> {
+    let string = |s: &str| Some(toml::Value::String(s.to_owned()));
+    let duration = |d: Duration| string(&HumanDuration(d).to_string());
+    let retry = &http.retry;
+    vec![
+        ("url", http.url.as_deref().and_then(string)),
+        ("user_agent", string(&http.user_agent)),
+        ("connect_timeout", http.connect_timeout.and_then(duration)),
+        ("read_timeout", http.read_timeout.and_then(duration)),
+        ("timeout", http.timeout.and_then(duration)),
+        (
+            "max_size",
+            http.max_size.and_then(|s| string(&ByteSize(s).to_string())),
+        ),
+        (
+            "max_attempts",
+            Some(toml::Value::Integer(i64::from(retry.max_attempts.get()))),
+        ),
+        (
+            "retry_base_delay",
+            string(&HumanDuration(retry.base_delay).to_string()),
+        ),
+        (
+            "retry_max_delay",
+            string(&HumanDuration(retry.max_delay).to_string()),
+        ),
+        ("retry_jitter", Some(toml::Value::Float(retry.jitter.get()))),
+        (
+            "retry_statuses",
+            Some(toml::Value::Array(
+                retry
+                    .statuses
+                    .iter()
+                    .map(|&status| toml::Value::Integer(i64::from(status)))
+                    .collect(),
+            )),
+        ),
+        (
+            "retry_errors",
+            Some(toml::Value::Array(
+                retry
+                    .errors
+                    .iter()
+                    .filter_map(|kind| string(kind.as_str()))
+                    .collect(),
+            )),
+        ),
+        (
+            "retry_non_idempotent",
+            Some(toml::Value::Boolean(retry.non_idempotent)),
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::load;
+
+    #[test]
+    fn render_lists_values_with_sources() {
+        let config = load(&["brust", "greet", "-n", "Alice"], &[]).unwrap();
+        let out = config.render();
+        assert!(out.contains("[greet]"), "{out}");
+        assert!(out.contains("name = \"Alice\" # flag --name"), "{out}");
+        assert!(out.contains("# gender is unset # default"), "{out}");
+    }
+}
@@ -0,0 +1,337 @@
+//! Configuration layers read from a TOML file, `BRUST_*` env vars and CLI
+//! flags.
+
+use std::collections::BTreeMap;
+use std::num::NonZeroU32;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::Context as _;
+use serde::Deserialize;
+
+use brust::libs::count::{Delay, ErrorRate, IterationError};
+use brust::libs::hello::honorific::Gender;
+use brust::libs::http::retry::{Jitter, RetryableError};
+use brust::libs::units::{ByteSize, HumanDuration, Rate};
+
+use crate::cli::{Cli, Commands, flag_pair};
+
+use super::KEYS;
+
+/// One configuration layer: every value is optional.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(super) struct Layer {
+    pub(super) greet: GreetLayer,
+    pub(super) count: CountLayer,
+    pub(super) http: HttpLayer,
+    pub(super) telemetry: TelemetryLayer,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(super) struct GreetLayer {
+    pub(super) name: Option<String>,
+    pub(super) gender: Option<Gender>,
+    pub(super) honorific: Option<String>,
+    pub(super) locale: Option<String>,
+    pub(super) template: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(super) struct CountLayer {
+    pub(super) iterations: Option<u32>,
+    pub(super) delay: Option<Delay>,
+    pub(super) seed: Option<u64>,
+    pub(super) concurrency: Option<NonZeroU32>,
+    pub(super) duration: Option<HumanDuration>,
+    pub(super) rate: Option<Rate>,
+    pub(super) error_rate: Option<ErrorRate>,
+    pub(super) error_kinds: Option<Vec<IterationError>>,
+    pub(super) span_links: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(super) struct HttpLayer {
+    pub(super) url: Option<String>,
+    pub(super) user_agent: Option<String>,
+    pub(super) connect_timeout: Option<HumanDuration>,
+    pub(super) read_timeout: Option<HumanDuration>,
+    pub(super) timeout: Option<HumanDuration>,
+    pub(super) max_size: Option<ByteSize>,
+    pub(super) max_attempts: Option<NonZeroU32>,
+    pub(super) retry_base_delay: Option<HumanDuration>,
+    pub(super) retry_max_delay: Option<HumanDuration>,
+    pub(super) retry_jitter: Option<Jitter>,
+    pub(super) retry_statuses: Option<Vec<u16>>,
+    pub(super) retry_errors: Option<Vec<RetryableError>>,
+    pub(super) retry_non_idempotent: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(super) struct TelemetryLayer {
+    pub(super) endpoint: Option<String>,
+    pub(super) service_name: Option<String>,
+}
+
+impl HttpLayer {
+    /// Collect `http.*` values from `env`.
+    fn from_env(env: &mut EnvReader<'_, impl Fn(&str) -> Option<String>>) -> anyhow::Result<Self> {
+        Ok(Self {
+            url: env.string("http.url"),
+            user_agent: env.string("http.user_agent"),
+            connect_timeout: env.parse("http.connect_timeout")?,
+            read_timeout: env.parse("http.read_timeout")?,
+            timeout: env.parse("http.timeout")?,
+            max_size: env.parse("http.max_size")?,
+            max_attempts: env.parse("http.max_attempts")?,
+            retry_base_delay: env.parse("http.retry_base_delay")?,
+            retry_max_delay: env.parse("http.retry_max_delay")?,
+            retry_jitter: env.parse("http.retry_jitter")?,
+            retry_statuses: env.parse_list("http.retry_statuses")?,
+            retry_errors: env.parse_list("http.retry_errors")?,
+            retry_non_idempotent: env.parse("http.retry_non_idempotent")?,
+        })
+    }
+}
+
+/// Parse the value `raw` of the env var `var`.
+///
+/// # Errors
+///
+/// Returns an error naming `var` and `raw` when `raw` does not parse.
+fn parse_env<T>(var: &str, raw: &str) -> anyhow::Result<T>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    raw.parse().with_context(|| format!("invalid {var}: {raw}"))
+}
+
+/// Looks up the env vars of config keys, recording which variable supplied
+/// each key.
+struct EnvReader<'a, F> {
+    env: &'a F,
+    vars: BTreeMap<&'static str, &'static str>,
+}
+
+impl<F: Fn(&str) -> Option<String>> EnvReader<'_, F> {
+    /// Raw value of `key` and the variable it came from, from the first of
+    /// its env vars that is set and not empty.
+    fn raw(&mut self, key: &'static str) -> Option<(&'static str, String)> {
+        let names = KEYS
+            .iter()
+            .find(|&&(k, _, _)| k == key)
+            .map_or(&[][..], |&(_, names, _)| names);
+        let (var, value) = names
+            .iter()
+            .find_map(|&var| Some((var, (self.env)(var).filter(|v| !v.is_empty())?)))?;
+        self.vars.insert(key, var);
+        Some((var, value))
+    }
+
+    /// Value of a text `key`.
+    fn string(&mut self, key: &'static str) -> Option<String> {
+        self.raw(key).map(|(_, value)| value)
+    }
+
+    /// Value of `key` parsed with [`parse_env`].
+    fn parse<T>(&mut self, key: &'static str) -> anyhow::Result<Option<T>>
+    where
+        T: FromStr,
+        T::Err: std::error::Error + Send + Sync + 'static,
+    {
+        self.raw(key)
+            .map(|(var, value)| parse_env(var, &value))
+            .transpose()
+    }
+
+    /// Comma-separated values of `key`, each parsed with [`parse_env`].
+    fn parse_list<T>(&mut self, key: &'static str) -> anyhow::Result<Option<Vec<T>>>
+    where
+        T: FromStr,
+        T::Err: std::error::Error + Send + Sync + 'static,
+    {
+        self.raw(key)
+            .map(|(var, value)| {
+                value
+                    .split(',')
+                    .map(|item| parse_env(var, item.trim()))
+                    .collect()
+            })
+            .transpose()
+    }
+}
+
+impl Layer {
+    /// Parse a TOML config file.
+    pub(super) fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read config file: {}", path.display()))?;
+        toml::from_str(&text)
+            .with_context(|| format!("failed to parse config file: {}", path.display()))
+    }
+
+    /// Collect values from environment variables.
+    ///
+    /// Returns the layer plus the variable that supplied each key. Empty
+    /// variables are treated as unset.
+    pub(super) fn from_env(
+        env: &impl Fn(&str) -> Option<String>,
+    ) -> anyhow::Result<(Self, BTreeMap<&'static str, &'static str>)> {
+        let mut env = EnvReader {
+            env,
+            vars: BTreeMap::new(),
+        };
+        let layer = Self {
+            greet: GreetLayer {
+                name: env.string("greet.name"),
+                gender: env.parse("greet.gender")?,
+                honorific: env.string("greet.honorific"),
+                locale: env.string("greet.locale"),
+                template: env.string("greet.template"),
+            },
+            count: CountLayer {
+                iterations: env.parse("count.iterations")?,
+                delay: env.parse("count.delay")?,
+                seed: env.parse("count.seed")?,
+                concurrency: env.parse("count.concurrency")?,
+                duration: env.parse("count.duration")?,
+                rate: env.parse("count.rate")?,
+                error_rate: env.parse("count.error_rate")?,
+                error_kinds: env.parse_list("count.error_kinds")?,
+                span_links: env.parse("count.span_links")?,
+            },
+            http: HttpLayer::from_env(&mut env)?,
+            telemetry: TelemetryLayer {
+                endpoint: env.string("telemetry.endpoint"),
+                service_name: env.string("telemetry.service_name"),
+            },
+        };
+
+        Ok((layer, env.vars))
+    }
+
+    /// Collect values given as CLI flags.
+    pub(super) fn from_cli(cli: &Cli) -> Self {
+        let mut layer = Self::default();
+        match &cli.command {
+            Some(Commands::Greet(args)) => {
+                layer.greet.name.clone_from(&args.name);
+                layer.greet.gender = args.gender;
+                layer.greet.honorific.clone_from(&args.honorific);
+                layer.greet.locale.clone_from(&args.locale);
+                layer.greet.template.clone_from(&args.template);
+            }
+            Some(Commands::Count(args)) => {
+                layer.count.iterations = args.count;
+                layer.count.delay = args.delay;
+                layer.count.seed = args.seed;
+                layer.count.concurrency = args.concurrency;
+                layer.count.duration = args.duration;
+                layer.count.rate = args.rate;
+                layer.count.error_rate = args.error_rate;
+                layer.count.error_kinds.clone_from(&args.error_kinds);
+                layer.count.span_links = flag_pair(args.span_links, args.no_span_links);
+            }
+            Some(Commands::Fetch(args)) => {
+                layer.http.url = args.urls.first().cloned();
+                layer.http.connect_timeout = args.connect_timeout;
+                layer.http.read_timeout = args.read_timeout;
+                layer.http.timeout = args.timeout;
+                layer.http.max_size = args.max_size;
+                layer.http.max_attempts = args.max_attempts;
+                layer.http.retry_base_delay = args.retry_base_delay;
+                layer.http.retry_max_delay = args.retry_max_delay;
+                layer.http.retry_jitter = args.retry_jitter;
+                layer.http.retry_statuses.clone_from(&args.retry_statuses);
+                layer.http.retry_errors.clone_from(&args.retry_errors);
+                layer.http.retry_non_idempotent =
+                    flag_pair(args.retry_non_idempotent, args.no_retry_non_idempotent);
+            }
+            Some(
+                Commands::Batch(_)
+                | Commands::Config(_)
+                | Commands::Completions(_)
+                | Commands::Man(_),
+            ) => {}
+            None => {
+                layer.greet.name.clone_from(&cli.legacy.name);
+                // An invalid legacy gender is reported when greeting.
+                layer.greet.gender = cli.legacy.gender.as_deref().and_then(|g| g.parse().ok());
+                layer.count.iterations = cli.legacy.count;
+                layer.http.url.clone_from(&cli.legacy.url);
+            }
+        }
+        layer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Source;
+    use super::super::tests::{load, write_config};
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_config(&dir, "[greet]\nnmae = \"typo\"\n");
+        let result = load(&["brust", "--config", path.to_str().unwrap(), "greet"], &[]);
+        assert!(result.is_err(), "typo in config key must be reported");
+    }
+
+    #[test]
+    fn invalid_env_iterations_is_an_error() {
+        let result = load(&["brust", "count"], &[("BRUST_COUNT_ITERATIONS", "many")]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn invalid_env_values_name_the_variable_and_value() {
+        for (args, var, value) in [
+            (&["brust", "count"][..], "BRUST_COUNT_SPAN_LINKS", "yes"),
+            (&["brust", "fetch"][..], "BRUST_HTTP_TIMEOUT", "soon"),
+            (&["brust", "count"][..], "BRUST_COUNT_SEED", "-1"),
+            (
+                &["brust", "fetch"][..],
+                "BRUST_HTTP_RETRY_ERRORS",
+                "timeout,http",
+            ),
+        ] {
+            let error = load(args, &[(var, value)]).unwrap_err();
+            let message = format!("{error:#}");
+            let item = value.rsplit(',').next().unwrap();
+            assert!(
+                message.starts_with(&format!("invalid {var}: {item}: ")),
+                "{message}"
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_env_gender_is_an_error() {
+        let result = load(&["brust", "greet"], &[("BRUST_GREET_GENDER", "other")]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn brust_env_overrides_otel_env() {
+        let config = load(
+            &["brust", "greet"],
+            &[
+                ("OTEL_SERVICE_NAME", "from-otel"),
+                ("BRUST_TELEMETRY_SERVICE_NAME", "from-brust"),
+                ("OTEL_EXPORTER_OTLP_ENDPOINT", "http://127.0.0.1:4318"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(config.telemetry.service_name, "from-brust");
+        assert_eq!(
+            config.source("telemetry.endpoint"),
+            Some(&Source::Env("OTEL_EXPORTER_OTLP_ENDPOINT"))
+        );
+    }
+}
@@ -0,0 +1,494 @@
+//! Merging a configuration layer over the values below it.
+
+use brust::libs::units::HumanDuration;
+
+use super::layer::{HttpLayer, Layer};
+use super::{Config, HttpConfig, Source};
+
+impl Config {
+    /// Overwrite fields present in `layer`, recording `source(key)` for each.
+    ///
+    /// `greet.gender` and `greet.honorific` are alternatives: a layer that
+    /// sets only one of them resets the other to its default.
+    pub(super) fn merge(&mut self, layer: Layer, source: impl Fn(&'static str) -> Source) {
+        let Layer {
+            greet,
+            count,
+            http,
+            telemetry,
+        } = layer;
+
+        if greet.gender.is_some() && greet.honorific.is_none() {
+            self.greet.honorific = None;
+            self.sources.insert("greet.honorific", Source::Default);
+        } else if greet.honorific.is_some() && greet.gender.is_none() {
+            self.greet.gender = None;
+            self.sources.insert("greet.gender", Source::Default);
+        }
+
+        let updated = [
+            ("greet.name", assign(&mut self.greet.name, greet.name)),
+            (
+                "greet.gender",
+                assign(&mut self.greet.gender, greet.gender.map(Some)),
+            ),
+            (
+                "greet.honorific",
+                assign(&mut self.greet.honorific, greet.honorific.map(Some)),
+            ),
+            (
+                "greet.locale",
+                assign(&mut self.greet.locale, greet.locale.map(Some)),
+            ),
+            (
+                "greet.template",
+                assign(&mut self.greet.template, greet.template.map(Some)),
+            ),
+            (
+                "count.iterations",
+                assign(&mut self.count.iterations, count.iterations.map(Some)),
+            ),
+            ("count.delay", assign(&mut self.count.delay, count.delay)),
+            (
+                "count.seed",
+                assign(&mut self.count.seed, count.seed.map(Some)),
+            ),
+            (
+                "count.concurrency",
+                assign(&mut self.count.concurrency, count.concurrency),
+            ),
+            (
+                "count.duration",
+                assign(&mut self.count.duration, count.duration.map(|d| Some(d.0))),
+            ),
+            (
+                "count.rate",
+                assign(&mut self.count.rate, count.rate.map(Some)),
+            ),
+            (
+                "count.error_rate",
+                assign(&mut self.count.error_rate, count.error_rate),
+            ),
+            (
+                "count.error_kinds",
+                assign(&mut self.count.error_kinds, count.error_kinds),
+            ),
+            (
+                "count.span_links",
+                assign(&mut self.count.span_links, count.span_links),
+            ),
+            (
+                "telemetry.endpoint",
+                assign(&mut self.telemetry.endpoint, telemetry.endpoint.map(Some)),
+            ),
+            (
+                "telemetry.service_name",
+                assign(&mut self.telemetry.service_name, telemetry.service_name),
+            ),
+        ];
+
+        let http = self.http.merge(http);
+        for (key, present) in updated.into_iter().chain(http) {
+            if present {
+                self.sources.insert(key, source(key));
+            }
+        }
+    }
+}
+
+impl HttpConfig {
+    /// Overwrite fields present in `http`; returns each key and whether it
+    /// was present.
+    fn merge(&mut self, http: HttpLayer) -> [(&'static str, bool); 13] {
+        let some = |d: HumanDuration| Some(d.0);
+        [
+            ("http.url", assign(&mut self.url, http.url.map(Some))),
+            (
+                "http.user_agent",
+                assign(&mut self.user_agent, http.user_agent),
+            ),
+            (
+                "http.connect_timeout",
+                assign(&mut self.connect_timeout, http.connect_timeout.map(some)),
+            ),
+            (
+                "http.read_timeout",
+                assign(&mut self.read_timeout, http.read_timeout.map(some)),
+            ),
+            (
+                "http.timeout",
+                assign(&mut self.timeout, http.timeout.map(some)),
+            ),
+            (
+                "http.max_size",
+                assign(&mut self.max_size, http.max_size.map(|s| Some(s.0))),
+            ),
+            (
+                "http.max_attempts",
+                assign(&mut self.retry.max_attempts, http.max_attempts),
+            ),
+            (
+                "http.retry_base_delay",
+                assign(
+                    &mut self.retry.base_delay,
+                    http.retry_base_delay.map(|d| d.0),
+                ),
+            ),
+            (
+                "http.retry_max_delay",
+                assign(&mut self.retry.max_delay, http.retry_max_delay.map(|d| d.0)),
+            ),
+            (
+                "http.retry_jitter",
+                assign(&mut self.retry.jitter, http.retry_jitter),
+            ),
+            (
+                "http.retry_statuses",
+                assign(&mut self.retry.statuses, http.retry_statuses),
+            ),
+            (
+                "http.retry_errors",
+                assign(&mut self.retry.errors, http.retry_errors),
+            ),
+            (
+                "http.retry_non_idempotent",
+                assign(&mut self.retry.non_idempotent, http.retry_non_idempotent),
+            ),
+        ]
+    }
+}
+
+/// Store `value` into `slot` when present; returns whether it was present.
+fn assign<T>(slot: &mut T, value: Option<T>) -> bool {
+    value.map(|v| *slot = v).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use brust::libs::count::{Delay, ErrorRate, IterationError};
+    use brust::libs::http::retry::{self, Jitter, RetryableError};
+
+    use super::super::Source;
+    use super::super::tests::{load, write_config};
+
+    #[test]
+    fn count_delay_and_seed_merge_across_layers() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_config(&dir, "[count]\ndelay = \"fixed:10\"\nseed = 3\n");
+        let path_str = path.to_str().unwrap();
+
+        let config = load(
+            &["brust", "--config", path_str, "count", "--seed", "9"],
+            &[("BRUST_COUNT_DELAY", "normal:100,20")],
+        )
+        .unwrap();
+        assert_eq!(
+            config.count.delay,
+            Delay::Normal {
+                mean: 100,
+                stddev: 20
+            }
+        );
+        assert_eq!(
+            config.source("count.delay"),
+            Some(&Source::Env("BRUST_COUNT_DELAY"))
+        );
+        assert_eq!(config.count.seed, Some(9));
+        assert_eq!(config.source("count.seed"), Some(&Source::Flag("--seed")));
+
+        let path = write_config(&dir, "[count]\ndelay = \"gamma:1\"\n");
+        let result = load(&["brust", "--config", path.to_str().unwrap(), "count"], &[]);
+        assert!(
+            result.is_err(),
+            "unknown distribution in config must be reported"
+        );
+    }
+
+    #[test]
+    fn count_concurrency_defaults_to_one_and_rejects_zero() {
+        let config = load(&["brust", "count"], &[]).unwrap();
+        assert_eq!(config.count.concurrency.get(), 1);
+        assert_eq!(config.source("count.concurrency"), Some(&Source::Default));
+
+        let config = load(
+            &["brust", "count", "-j", "4"],
+            &[("BRUST_COUNT_CONCURRENCY", "2")],
+        )
+        .unwrap();
+        assert_eq!(config.count.concurrency.get(), 4);
+        assert_eq!(
+            config.source("count.concurrency"),
+            Some(&Source::Flag("--concurrency"))
+        );
+
+        let result = load(&["brust", "count"], &[("BRUST_COUNT_CONCURRENCY", "0")]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn count_duration_and_rate_merge_across_layers() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_config(&dir, "[count]\nduration = \"1h30m\"\nrate = \"5/s\"\n");
+        let path_str = path.to_str().unwrap();
+
+        let config = load(
+            &["brust", "--config", path_str, "count", "--rate", "3/10s"],
+            &[("BRUST_COUNT_DURATION", "90s")],
+        )
+        .unwrap();
+        assert_eq!(config.count.duration, Some(Duration::from_secs(90)));
+        assert_eq!(
+            config.source("count.duration"),
+            Some(&Source::Env("BRUST_COUNT_DURATION"))
+        );
+        assert_eq!(config.count.rate.unwrap().to_string(), "3/10s");
+        assert_eq!(config.source("count.rate"), Some(&Source::Flag("--rate")));
+        let out = config.render();
+        assert!(out.contains("duration = \"1m30s\" # env"), "{out}");
+
+        let result = load(&["brust", "count"], &[("BRUST_COUNT_RATE", "fast")]);
+        assert!(result.is_err());
+        let result = load(&["brust", "count"], &[("BRUST_COUNT_DURATION", "soon")]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn count_span_links_merge_across_layers() {
+        assert!(!load(&["brust", "count"], &[]).unwrap().count.span_links);
+
+        let config = load(&["brust", "count"], &[("BRUST_COUNT_SPAN_LINKS", "true")]).unwrap();
+        assert!(config.count.span_links);
+        assert!(config.render().contains("span_links = true # env"));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_config(&dir, "[count]\nspan_links = false\n");
+        let path_str = path.to_str().unwrap();
+        let config = load(
+            &["brust", "--config", path_str, "count", "--span-links"],
+            &[],
+        )
+        .unwrap();
+        assert!(config.count.span_links);
+        assert_eq!(
+            config.source("count.span_links"),
+            Some(&Source::Flag("--[no-]span-links"))
+        );
+
+        let config = load(
+            &["brust", "count", "--span-links", "--no-span-links"],
+            &[("BRUST_COUNT_SPAN_LINKS", "true")],
+        )
+        .unwrap();
+        assert!(!config.count.span_links);
+        assert!(
+            config
+                .render()
+                .contains("span_links = false # flag --[no-]span-links")
+        );
+
+        let result = load(&["brust", "count"], &[("BRUST_COUNT_SPAN_LINKS", "yes")]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn http_timeouts_merge_across_layers() {
+        let config = load(&["brust", "fetch"], &[]).unwrap();
+        assert_eq!(config.http.connect_timeout, Some(Duration::from_secs(10)));
+        assert_eq!(config.http.read_timeout, None);
+        assert_eq!(config.http.timeout, Some(Duration::from_secs(30)));
+        let out = config.render();
+        assert!(out.contains("timeout = \"30s\" # default"), "{out}");
+        assert!(out.contains("# read_timeout is unset # default"), "{out}");
+
+        let config = load(
+            &["brust", "fetch", "--timeout", "5s"],
+            &[
+                ("BRUST_HTTP_TIMEOUT", "1m"),
+                ("BRUST_HTTP_READ_TIMEOUT", "2s"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(config.http.timeout, Some(Duration::from_secs(5)));
+        assert_eq!(config.http.read_timeout, Some(Duration::from_secs(2)));
+        assert_eq!(
+            config.source("http.read_timeout"),
+            Some(&Source::Env("BRUST_HTTP_READ_TIMEOUT"))
+        );
+        assert_eq!(
+            config.source("http.timeout"),
+            Some(&Source::Flag("--timeout"))
+        );
+
+        let result = load(&["brust", "fetch"], &[("BRUST_HTTP_CONNECT_TIMEOUT", "5")]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn http_max_size_merges_across_layers() {
+        let config = load(&["brust", "fetch"], &[]).unwrap();
+        assert_eq!(config.http.max_size, None);
+        assert!(
+            config.render().contains("# max_size is unset # default"),
+            "{}",
+            config.render()
+        );
+
+        let config = load(&["brust", "fetch"], &[("BRUST_HTTP_MAX_SIZE", "64KiB")]).unwrap();
+        assert_eq!(config.http.max_size, Some(65_536));
+        assert!(config.render().contains("max_size = \"64KiB\" # env"));
+
+        let config = load(
+            &["brust", "fetch", "--max-size", "1MB"],
+            &[("BRUST_HTTP_MAX_SIZE", "64KiB")],
+        )
+        .unwrap();
+        assert_eq!(config.http.max_size, Some(1_000_000));
+        assert_eq!(
+            config.source("http.max_size"),
+            Some(&Source::Flag("--max-size"))
+        );
+
+        let result = load(&["brust", "fetch"], &[("BRUST_HTTP_MAX_SIZE", "lots")]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn http_retry_policy_merges_across_layers() {
+        let config = load(&["brust", "fetch"], &[]).unwrap();
+        assert_eq!(config.http.retry, retry::Policy::default());
+        assert!(config.render().contains("max_attempts = 1 # default"));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_config(
+            &dir,
+            "[http]\nmax_attempts = 5\nretry_max_delay = \"1m\"\nretry_statuses = [500]\n",
+        );
+        let path_str = path.to_str().unwrap();
+        let config = load(
+            &[
+                "brust",
+                "--config",
+                path_str,
+                "fetch",
+                "--retry-base-delay",
+                "250ms",
+                "--retry-errors",
+                "network,tls",
+            ],
+            &[
+                ("BRUST_HTTP_MAX_ATTEMPTS", "3"),
+                ("BRUST_HTTP_RETRY_JITTER", "0"),
+            ],
+        )
+        .unwrap();
+        let retry = &config.http.retry;
+        assert_eq!(retry.max_attempts.get(), 3);
+        assert_eq!(retry.base_delay, Duration::from_millis(250));
+        assert_eq!(retry.max_delay, Duration::from_mins(1));
+        assert_eq!(retry.jitter, Jitter::NONE);
+        assert_eq!(retry.statuses, [500]);
+        assert_eq!(retry.errors, [RetryableError::Network, RetryableError::Tls]);
+        assert!(!retry.non_idempotent);
+        assert_eq!(
+            config.source("http.max_attempts"),
+            Some(&Source::Env("BRUST_HTTP_MAX_ATTEMPTS"))
+        );
+        assert_eq!(
+            config.source("http.retry_base_delay"),
+            Some(&Source::Flag("--retry-base-delay"))
+        );
+        let out = config.render();
+        assert!(out.contains("retry_max_delay = \"1m\" # file"), "{out}");
+        assert!(out.contains("retry_statuses = [500] # file"), "{out}");
+        assert!(
+            out.contains("retry_non_idempotent = false # default"),
+            "{out}"
+        );
+
+        let config = load(
+            &["brust", "fetch", "--retry-non-idempotent"],
+            &[("BRUST_HTTP_RETRY_NON_IDEMPOTENT", "false")],
+        )
+        .unwrap();
+        assert!(config.http.retry.non_idempotent);
+        assert_eq!(
+            config.source("http.retry_non_idempotent"),
+            Some(&Source::Flag("--[no-]retry-non-idempotent"))
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_config(&dir, "[http]\nretry_non_idempotent = true\n");
+        let path_str = path.to_str().unwrap();
+        let config = load(
+            &[
+                "brust",
+                "--config",
+                path_str,
+                "fetch",
+                "--no-retry-non-idempotent",
+            ],
+            &[("BRUST_HTTP_RETRY_NON_IDEMPOTENT", "true")],
+        )
+        .unwrap();
+        assert!(!config.http.retry.non_idempotent);
+
+        for (var, value) in [
+            ("BRUST_HTTP_MAX_ATTEMPTS", "0"),
+            ("BRUST_HTTP_RETRY_JITTER", "2"),
+            ("BRUST_HTTP_RETRY_STATUSES", "503,oops"),
+            ("BRUST_HTTP_RETRY_ERRORS", "http"),
+            ("BRUST_HTTP_RETRY_NON_IDEMPOTENT", "yes"),
+        ] {
+            let result = load(&["brust", "fetch"], &[(var, value)]);
+            assert!(result.is_err(), "{var}={value}");
+        }
+    }
+
+    #[test]
+    fn count_failures_merge_across_layers() {
+        let config = load(&["brust", "count"], &[]).unwrap();
+        assert_eq!(config.count.error_rate, ErrorRate::NEVER);
+        assert_eq!(config.count.error_kinds, IterationError::ALL);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_config(
+            &dir,
+            "[count]\nerror_rate = 0.5\nerror_kinds = [\"invalid\"]\n",
+        );
+        let path_str = path.to_str().unwrap();
+        let config = load(
+            &[
+                "brust",
+                "--config",
+                path_str,
+                "count",
+                "--error-rate",
+                "0.05",
+            ],
+            &[("BRUST_COUNT_ERROR_KINDS", "timeout,panic_like")],
+        )
+        .unwrap();
+        assert_eq!(config.count.error_rate.to_string(), "0.05");
+        assert_eq!(
+            config.source("count.error_rate"),
+            Some(&Source::Flag("--error-rate"))
+        );
+        assert_eq!(
+            config.count.error_kinds,
+            [IterationError::Timeout, IterationError::PanicLike]
+        );
+        let out = config.render();
+        assert!(
+            out.contains("error_kinds = [\"timeout\", \"panic_like\"] # env"),
+            "{out}"
+        );
+
+        let path = write_config(&dir, "[count]\nerror_rate = 2.0\n");
+        let result = load(&["brust", "--config", path.to_str().unwrap(), "count"], &[]);
+        assert!(result.is_err(), "error rate above 1 must be reported");
+        let result = load(&["brust", "count"], &[("BRUST_COUNT_ERROR_KINDS", "oops")]);
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,346 @@
+//! Typed command failures and the exit codes they map to.
+//!
+//! Every failing command ends in one [`CliError`]; its [`ErrorKind`] picks
+//! the process exit code, so scripts can tell a bad input from a network
+//! outage without parsing messages.
+
+use std::ffi::OsString;
+use std::fmt;
+use std::io::{self, Write};
+use std::process::ExitCode;
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use brust::libs::batch;
+use brust::libs::hello::GreetingError;
+use brust::libs::http::{self, FetchError};
+
+/// Failure category of a command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// Anything not covered below, such as a failed write to stdout.
+    Failure,
+    /// Invalid command line, or a required value that was not given.
+    Usage,
+    /// Invalid greeting input, batch records, URL or configuration file.
+    InvalidInput,
+    /// Connecting to or exchanging data with a server failed.
+    Network,
+    /// TLS handshake or certificate verification failed.
+    Tls,
+    /// Telemetry export could not be set up.
+    #[cfg_attr(not(feature = "otel"), expect(dead_code))]
+    Telemetry,
+    /// Interrupted by `SIGINT` (Ctrl-C) or `SIGTERM`.
+    Cancelled,
+}
+
+impl ErrorKind {
+    /// Process exit code for this kind; `0` is reserved for success.
+    #[must_use]
+    pub const fn exit_code(self) -> u8 {
+        match self {
+            Self::Failure => 1,
+            Self::Usage => 2,
+            Self::InvalidInput => 3,
+            Self::Network => 4,
+            Self::Tls => 5,
+            Self::Telemetry => 6,
+            // 128 + SIGINT, as shells report a process killed by Ctrl-C.
+            Self::Cancelled => 130,
+        }
+    }
+}
+
+/// A failed command: what kind of failure, and a human-readable message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliError {
+    /// Failure category, which selects the exit code.
+    pub kind: ErrorKind,
+    /// Message shown to the user.
+    pub message: String,
+}
+
+impl CliError {
+    /// Create an error of `kind`.
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+
+    /// Exit code for this error.
+    #[must_use]
+    pub fn exit_code(&self) -> ExitCode {
+        ExitCode::from(self.kind.exit_code())
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for CliError {}
+
+impl From<GreetingError> for CliError {
+    fn from(e: GreetingError) -> Self {
+        Self::new(ErrorKind::InvalidInput, e.to_string())
+    }
+}
+
+impl From<FetchError> for CliError {
+    fn from(e: FetchError) -> Self {
+        Self::from(&e)
+    }
+}
+
+impl From<&FetchError> for CliError {
+    fn from(e: &FetchError) -> Self {
+        let kind = match e {
+            FetchError::InvalidUrl(_) => ErrorKind::InvalidInput,
+            FetchError::Client(_)
+            | FetchError::BodyTooLarge(_)
+            | FetchError::Write(_)
+            | FetchError::Panicked => ErrorKind::Failure,
+            FetchError::Tls(_) => ErrorKind::Tls,
+            FetchError::Timeout(_)
+            | FetchError::ConnectionRefused(_)
+            | FetchError::Dns(_)
+            | FetchError::BodyRead(_)
+            | FetchError::Network(_) => ErrorKind::Network,
+            FetchError::Cancelled => ErrorKind::Cancelled,
+        };
+        Self::new(kind, e.to_string())
+    }
+}
+
+impl From<batch::RunError> for CliError {
+    fn from(e: batch::RunError) -> Self {
+        match e {
+            batch::RunError::UnknownFormat => Self::new(
+                ErrorKind::Usage,
+                "cannot infer the batch format; pass --format csv or --format jsonl",
+            ),
+            batch::RunError::Template(e) => Self::new(
+                ErrorKind::InvalidInput,
+                format!("invalid greet.template: {e}"),
+            ),
+            batch::RunError::Open { .. } => Self::new(ErrorKind::InvalidInput, e.to_string()),
+            batch::RunError::Stream(e) => Self::new(ErrorKind::Failure, e.to_string()),
+        }
+    }
+}
+
+impl From<http::SetupError> for CliError {
+    fn from(e: http::SetupError) -> Self {
+        match e {
+            http::SetupError::StdinTwice => Self::new(
+                ErrorKind::Usage,
+                "--url-file - cannot be combined with --data @-; both read stdin",
+            ),
+            http::SetupError::NoUrl => Self::new(
+                ErrorKind::Usage,
+                "no URL given; pass --url or --url-file, or set http.url",
+            ),
+            http::SetupError::SaveMany(urls) => Self::new(
+                ErrorKind::Usage,
+                format!("--save takes a single URL, got {urls}"),
+            ),
+            http::SetupError::UrlFile { .. } | http::SetupError::Body(_) => {
+                Self::new(ErrorKind::InvalidInput, e.to_string())
+            }
+            http::SetupError::SaveCreate { .. } => Self::new(ErrorKind::Failure, e.to_string()),
+        }
+    }
+}
+
+impl From<http::Failures<'_>> for CliError {
+    fn from(failures: http::Failures<'_>) -> Self {
+        Self::new(Self::from(failures.first).kind, failures.to_string())
+    }
+}
+
+impl From<clap::Error> for CliError {
+    fn from(e: clap::Error) -> Self {
+        let rendered = e.render().to_string();
+        let message = rendered
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .trim_start_matches("error: ");
+        Self::new(ErrorKind::Usage, message)
+    }
+}
+
+/// How the final error is reported on stderr (`--error-format`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ErrorFormat {
+    /// A log line.
+    #[default]
+    Text,
+    /// One JSON object on its own line.
+    Json,
+}
+
+impl ErrorFormat {
+    /// Format requested in raw command-line `args`, for errors raised before
+    /// they could be parsed.
+    pub fn from_args(args: impl IntoIterator<Item = OsString>) -> Self {
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let value = if arg == "--error-format" {
+                args.next()
+            } else {
+                arg.to_str()
+                    .and_then(|a| a.strip_prefix("--error-format="))
+                    .map(OsString::from)
+            };
+            if let Some(format) = value.and_then(|v| Self::from_str(v.to_str()?, false).ok()) {
+                return format;
+            }
+        }
+        Self::Text
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorObject<'a> {
+    kind: ErrorKind,
+    exit_code: u8,
+    message: &'a str,
+}
+
+/// Report `error` in `format`: logged for text, written to `out` for JSON.
+///
+/// # Errors
+///
+/// Returns an error if `out` cannot be written.
+pub fn report(error: &CliError, format: ErrorFormat, mut out: impl Write) -> io::Result<()> {
+    match format {
+        ErrorFormat::Text => {
+            tracing::error!(error.kind = ?error.kind, "{error}");
+            Ok(())
+        }
+        ErrorFormat::Json => {
+            let object = ErrorObject {
+                kind: error.kind,
+                exit_code: error.kind.exit_code(),
+                message: &error.message,
+            };
+            serde_json::to_writer(&mut out, &object)?;
+            writeln!(out)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use std::ffi::OsString;
+
+    use super::{CliError, ErrorFormat, ErrorKind, report};
+    use brust::libs::batch;
+    use brust::libs::hello::GreetingError;
+    use brust::libs::http::FetchError;
+
+    fn args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn exit_codes_are_distinct_and_nonzero() {
+        let kinds = [
+            ErrorKind::Failure,
+            ErrorKind::Usage,
+            ErrorKind::InvalidInput,
+            ErrorKind::Network,
+            ErrorKind::Tls,
+            ErrorKind::Telemetry,
+            ErrorKind::Cancelled,
+        ];
+        let codes: Vec<u8> = kinds.iter().map(|k| k.exit_code()).collect();
+        assert_eq!(codes, [1, 2, 3, 4, 5, 6, 130]);
+    }
+
+    #[test]
+    fn greeting_errors_are_invalid_input() {
+        let error = CliError::from(GreetingError::InvalidGender(String::from("x")));
+        assert_eq!(error.kind, ErrorKind::InvalidInput);
+        assert_eq!(error.message, "invalid gender: x");
+    }
+
+    #[test]
+    fn panicked_fetches_are_failures_not_cancellations() {
+        let error = CliError::from(FetchError::Panicked);
+        assert_eq!(error.kind, ErrorKind::Failure);
+        assert_eq!(error.kind.exit_code(), 1);
+        assert_eq!(FetchError::Panicked.error_type(), "panicked");
+    }
+
+    #[test]
+    fn batch_errors_name_the_flag_or_key_to_fix() {
+        let error = CliError::from(batch::RunError::UnknownFormat);
+        assert_eq!(error.kind, ErrorKind::Usage);
+        assert!(
+            error
+                .message
+                .ends_with("pass --format csv or --format jsonl")
+        );
+        let invalid = GreetingError::InvalidTemplate(String::from("unknown"));
+        let error = CliError::from(batch::RunError::Template(invalid));
+        assert_eq!(error.kind, ErrorKind::InvalidInput);
+        assert!(error.message.starts_with("invalid greet.template: "));
+    }
+
+    #[test]
+    fn error_format_is_found_in_raw_args() {
+        let json = ErrorFormat::Json;
+        assert_eq!(
+            ErrorFormat::from_args(args(&["brust", "--error-format", "json"])),
+            json
+        );
+        assert_eq!(
+            ErrorFormat::from_args(args(&["brust", "greet", "--error-format=json"])),
+            json
+        );
+        assert_eq!(
+            ErrorFormat::from_args(args(&["brust", "--error-format", "xml"])),
+            ErrorFormat::Text
+        );
+        assert_eq!(
+            ErrorFormat::from_args(args(&["brust", "--error-format"])),
+            ErrorFormat::Text
+        );
+    }
+
+    #[test]
+    fn json_report_is_one_object_per_line() {
+        let error = CliError::new(ErrorKind::Network, "HTTP request failed");
+        let mut out = Vec::new();
+        report(&error, ErrorFormat::Json, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "{\"kind\":\"network\",\"exit_code\":4,\"message\":\"HTTP request failed\"}\n"
+        );
+    }
+
+    #[test]
+    fn clap_errors_become_usage_errors_without_prefix() {
+        let error = clap::Command::new("brust")
+            .try_get_matches_from(["brust", "--bogus"])
+            .unwrap_err();
+        let error = CliError::from(error);
+        assert_eq!(error.kind, ErrorKind::Usage);
+        assert!(
+            error.message.starts_with("unexpected argument"),
+            "{}",
+            error.message
+        );
+    }
+}
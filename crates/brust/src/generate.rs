@@ -0,0 +1,120 @@
+//! Shell completion scripts and man pages for the `brust` CLI.
+//!
+//! Thin wrappers binding [`brust::libs::generate`] to [`Cli`].
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use brust::libs::generate;
+use clap_complete::Shell;
+
+use crate::cli::Cli;
+
+/// Completion script for `shell`, covering every subcommand and flag.
+#[must_use]
+pub fn completions(shell: Shell) -> String {
+    generate::completions::<Cli>(shell)
+}
+
+/// Write one man page per command into `dir`, creating it if needed:
+/// `brust.1` first, then a page for every subcommand (`brust-greet.1`,
+/// `brust-config-show.1`, ...).
+///
+/// Returns the written paths in page order.
+///
+/// # Errors
+///
+/// Returns an error if `dir` cannot be created or a page cannot be written.
+pub fn write_man_pages(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    generate::write_man_pages::<Cli>(dir)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use clap::{Command, CommandFactory as _, ValueEnum as _};
+    use clap_complete::Shell;
+
+    use super::{completions, write_man_pages};
+    use crate::cli::Cli;
+
+    /// Every command in the tree with the long flags it accepts, including
+    /// propagated global flags.
+    fn long_flags() -> Vec<(String, Vec<String>)> {
+        fn walk(cmd: &Command, out: &mut Vec<(String, Vec<String>)>) {
+            let flags = cmd
+                .get_arguments()
+                .filter(|arg| !arg.is_hide_set())
+                .filter_map(|arg| arg.get_long().map(String::from))
+                .collect();
+            out.push((String::from(cmd.get_name()), flags));
+            for sub in cmd.get_subcommands() {
+                walk(sub, out);
+            }
+        }
+        let mut cmd = Cli::command().disable_help_subcommand(true);
+        cmd.build();
+        let mut out = Vec::new();
+        walk(&cmd, &mut out);
+        out
+    }
+
+    #[test]
+    fn completions_list_every_flag_for_every_shell() {
+        let flags: Vec<String> = long_flags().into_iter().flat_map(|(_, f)| f).collect();
+        for &shell in Shell::value_variants() {
+            let script = completions(shell);
+            for flag in &flags {
+                // fish names long options with `-l NAME`; the others spell `--NAME`.
+                let expected = if shell == Shell::Fish {
+                    format!("-l {flag}")
+                } else {
+                    format!("--{flag}")
+                };
+                assert!(script.contains(&expected), "{shell}: missing {expected}");
+            }
+            for sub in [
+                "greet",
+                "count",
+                "fetch",
+                "batch",
+                "config",
+                "completions",
+                "man",
+            ] {
+                assert!(script.contains(sub), "{shell}: missing {sub}");
+            }
+        }
+    }
+
+    #[test]
+    fn man_pages_cover_every_subcommand_and_flag() {
+        let pages = brust::libs::generate::man_pages::<Cli>();
+        let commands = long_flags();
+        assert_eq!(pages.len(), commands.len());
+        for (page, (name, flags)) in pages.iter().zip(&commands) {
+            let mut roff = Vec::new();
+            page.render(&mut roff).unwrap();
+            let roff = String::from_utf8(roff).unwrap();
+            for flag in flags {
+                let expected = format!("\\-\\-{}", flag.replace('-', "\\-"));
+                assert!(roff.contains(&expected), "{name}: missing --{flag}");
+            }
+        }
+    }
+
+    #[test]
+    fn write_man_pages_names_files_after_subcommands() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = write_man_pages(&dir.path().join("man1")).unwrap();
+        let names: Vec<_> = paths
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names.first().map(String::as_str), Some("brust.1"));
+        assert!(names.iter().any(|n| n == "brust-greet.1"));
+        assert!(names.iter().any(|n| n == "brust-config-show.1"));
+        assert!(paths.iter().all(|p| p.is_file()));
+    }
+}
@@ -0,0 +1,46 @@
+//! Brust - Rust ボイラープレートプロジェクトのライブラリ
+//!
+//! The `brust` binary is a thin CLI over this crate; other crates can greet,
+//! run the count demo, greet batches and fetch URLs with the same metrics
+//! through it, down to [`run_count`], [`run_batch`] and [`run_fetch`].
+//!
+//! Every entry point takes a [`Meters`]. Create it with [`Meters::new`] after
+//! setting up a global `MeterProvider`, or pass [`Meters::noop`] to record
+//! nothing.
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use brust::{Clock as _, Delay, Greeter, Meters, VirtualClock};
+//! use brust::libs::hello::locale::Locale;
+//!
+//! let meters = Meters::noop();
+//! let line = Greeter::new(Locale::En).greet("Alice", &meters).unwrap();
+//! assert_eq!(line, "Hi, Alice, new world!!");
+//!
+//! // Virtual time: ten 1-5 s iterations without waiting.
+//! let clock = VirtualClock::new();
+//! let mut rng = brust::libs::count::rng(Some(1));
+//! let results = brust::run_iterations(10, &Delay::default(), &mut rng, &clock);
+//! assert_eq!(results.len(), 10);
+//! assert!(clock.now() >= Duration::from_secs(10));
+//! ```
+
+/// ライブラリモジュール群
+pub mod libs;
+/// OpenTelemetry instrumentation (metrics, future: tracing, logs)
+pub mod telemetry;
+
+pub use crate::libs::batch::run as run_batch;
+pub use crate::libs::cancellation::{CancelToken, Cancelled};
+pub use crate::libs::count::time::{Clock, SystemClock, VirtualClock};
+pub use crate::libs::count::{
+    Delay, IterationResult, Settings as CountSettings, run as run_count, run_iterations,
+};
+pub use crate::libs::hello::GreetingError;
+pub use crate::libs::hello::greeter::Greeter;
+pub use crate::libs::http::{
+    Client as HttpClient, ClientOptions, FetchError, FetchResult, Save, build_requests,
+    collect_urls, fetch_outcome, fetch_url, run_fetch,
+};
+pub use crate::telemetry::metrics::Meters;
@@ -1,6 +1,14 @@
+/// Batch greeting records in CSV or JSON Lines
+pub mod batch;
+/// Cooperative cancellation of long-running work
+pub mod cancellation;
 /// Iteration counter for metrics demonstration
 pub mod count;
+/// Shell completions and man pages from clap definitions
+pub mod generate;
 /// 挨拶関連モジュール
 pub mod hello;
 /// HTTP client utilities with OTel metrics instrumentation
 pub mod http;
+/// Human-readable durations and rates
+pub mod units;
@@ -0,0 +1,524 @@
+//! Batch greeting records read from and written to CSV or JSON Lines.
+
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use tracing::field::Empty;
+
+use crate::libs::hello::greeter::Greeter;
+use crate::libs::hello::honorific::{Gender, Honorific};
+use crate::libs::hello::locale::Locale;
+use crate::libs::hello::template::Template;
+use crate::telemetry::metrics::Meters;
+
+mod run;
+
+pub use self::run::{RunError, run};
+
+/// Encoding of batch input and output records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Comma-separated values with a header row.
+    Csv,
+    /// One JSON object per line.
+    Jsonl,
+}
+
+impl Format {
+    /// Infer the format from a file extension (`.csv`, `.jsonl`, `.ndjson`).
+    #[must_use]
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "csv" => Some(Self::Csv),
+            "jsonl" | "ndjson" => Some(Self::Jsonl),
+            _ => None,
+        }
+    }
+}
+
+/// One person to greet.
+///
+/// Empty `gender` and `locale` values are treated as unset; unknown columns
+/// or fields are ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct Record {
+    /// Name of the person to greet.
+    pub name: String,
+    /// Built-in honorific choice, as accepted by `--gender`.
+    #[serde(default)]
+    pub gender: Option<String>,
+    /// Greeting locale tag; the run's locale applies when unset.
+    #[serde(default)]
+    pub locale: Option<String>,
+}
+
+/// Why a batch input record could not be read.
+#[derive(Debug)]
+pub enum RecordError {
+    /// The record is malformed; the batch continues with the next one.
+    Invalid(String),
+    /// Reading the input failed; the batch cannot continue.
+    Io(io::Error),
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Invalid(reason) => write!(f, "invalid record: {reason}"),
+            Self::Io(e) => write!(f, "failed to read record: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for RecordError {}
+
+/// Why a batch stopped part-way.
+#[derive(Debug)]
+pub enum StreamError {
+    /// Reading the input failed.
+    Read(io::Error),
+    /// Writing an outcome failed.
+    Write(io::Error),
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Read(e) => write!(f, "failed to read batch input: {e}"),
+            Self::Write(e) => write!(f, "failed to write batch output: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for StreamError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Read(e) | Self::Write(e) => Some(e),
+        }
+    }
+}
+
+/// Result of greeting one record, written back in the input format.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Outcome {
+    /// 1-based position of the record in the input.
+    pub record: u64,
+    /// Name from the record (empty if the record was malformed).
+    pub name: String,
+    /// Gender from the record, as given.
+    pub gender: Option<String>,
+    /// Locale the greeting was rendered in.
+    pub locale: Option<String>,
+    /// Greeting line; unset when the record failed.
+    pub greeting: Option<String>,
+    /// Why the record failed; unset on success.
+    pub error: Option<String>,
+}
+
+/// Stream records from `input` one at a time.
+///
+/// CSV input needs a header row naming the `name`, `gender` and `locale`
+/// columns; blank JSON Lines are skipped.
+pub fn read_records<'a>(
+    input: impl BufRead + 'a,
+    format: Format,
+) -> Box<dyn Iterator<Item = Result<Record, RecordError>> + 'a> {
+    match format {
+        Format::Csv => Box::new(
+            csv::ReaderBuilder::new()
+                .flexible(true)
+                .trim(csv::Trim::All)
+                .from_reader(input)
+                .into_deserialize()
+                .map(|row| row.map_err(csv_error)),
+        ),
+        Format::Jsonl => Box::new(
+            input
+                .lines()
+                .filter(|line| line.as_ref().map_or(true, |l| !l.trim().is_empty()))
+                .map(|line| {
+                    let line = line.map_err(RecordError::Io)?;
+                    serde_json::from_str(&line).map_err(|e| RecordError::Invalid(e.to_string()))
+                }),
+        ),
+    }
+}
+
+/// Split CSV errors into fatal I/O errors and per-record errors.
+fn csv_error(e: csv::Error) -> RecordError {
+    if !e.is_io_error() {
+        return RecordError::Invalid(e.to_string());
+    }
+    match e.into_kind() {
+        csv::ErrorKind::Io(e) => RecordError::Io(e),
+        // NOTEST(unreachable): is_io_error() guarantees ErrorKind::Io
+        kind => RecordError::Invalid(format!("{kind:?}")),
+    }
+}
+
+/// Greet every record of `input`, streaming one outcome per record to `out`
+/// in the same `format`.
+///
+/// Malformed records and invalid genders are reported in the outcome's
+/// `error` field without stopping the batch; records without a supported
+/// `locale` use `default_locale`. Returns the number of records that failed.
+///
+/// # Errors
+///
+/// Returns [`StreamError::Read`] if reading `input` fails and
+/// [`StreamError::Write`] if writing `out` fails, both part-way.
+pub fn greet_all(
+    input: impl BufRead,
+    format: Format,
+    default_locale: Locale,
+    template: Option<&Template>,
+    meters: &Meters,
+    out: impl Write,
+) -> Result<u64, StreamError> {
+    let start = std::time::Instant::now();
+    let mut writer = Writer::new(io::BufWriter::new(out), format);
+
+    let mut failed = 0_u64;
+    for (number, record) in (1_u64..).zip(read_records(input, format)) {
+        let span = tracing::info_span!("greet_record", record = number, error.r#type = Empty);
+        let _guard = span.enter();
+        let record = match record {
+            Err(RecordError::Io(e)) => return Err(StreamError::Read(e)),
+            record => record,
+        };
+        let outcome = greet_record(number, record, default_locale, template, meters);
+        if outcome.error.is_some() {
+            failed = failed.saturating_add(1);
+        }
+        writer.write(&outcome).map_err(StreamError::Write)?;
+    }
+    writer.flush().map_err(StreamError::Write)?;
+
+    meters.record_run_duration(start.elapsed().as_secs_f64(), "batch");
+    Ok(failed)
+}
+
+/// Greet record `number` and count it in the greeting metrics.
+///
+/// Records without a supported `locale` use `default_locale`.
+pub fn greet_record(
+    number: u64,
+    record: Result<Record, RecordError>,
+    default_locale: Locale,
+    template: Option<&Template>,
+    meters: &Meters,
+) -> Outcome {
+    let record = match record {
+        Ok(record) => record,
+        Err(e) => {
+            tracing::Span::current().record("error.type", "invalid_record");
+            meters.record_greeting("invalid", default_locale.as_str());
+            meters.record_greeting_error("invalid_record");
+            return Outcome {
+                record: number,
+                error: Some(e.to_string()),
+                ..Outcome::default()
+            };
+        }
+    };
+
+    let locale = record
+        .locale
+        .as_deref()
+        .and_then(Locale::from_tag)
+        .unwrap_or(default_locale);
+    let gender = record
+        .gender
+        .as_deref()
+        .filter(|gender| !gender.is_empty())
+        .map(str::parse::<Gender>)
+        .transpose();
+    let greeter = Greeter::new(locale)
+        .with_honorific(gender.as_ref().ok().copied().flatten().map(Honorific::from))
+        .with_template(template.cloned());
+    let result = match gender {
+        Ok(_) => greeter.greet(&record.name, meters),
+        Err(e) => {
+            greeter.record(meters, Some(&e));
+            Err(e)
+        }
+    };
+
+    let (greeting, error) = match result {
+        Ok(line) => (Some(line), None),
+        Err(e) => {
+            tracing::Span::current().record("error.type", e.error_type());
+            (None, Some(e.to_string()))
+        }
+    };
+    Outcome {
+        record: number,
+        name: record.name,
+        gender: record.gender,
+        locale: Some(String::from(locale.as_str())),
+        greeting,
+        error,
+    }
+}
+
+/// Writes [`Outcome`]s in a batch [`Format`].
+pub struct Writer<W: Write> {
+    inner: Inner<W>,
+}
+
+enum Inner<W: Write> {
+    Csv(Box<csv::Writer<W>>),
+    Jsonl(W),
+}
+
+impl<W: Write> fmt::Debug for Writer<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let format = match self.inner {
+            Inner::Csv(_) => Format::Csv,
+            Inner::Jsonl(_) => Format::Jsonl,
+        };
+        f.debug_struct("Writer")
+            .field("format", &format)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<W: Write> Writer<W> {
+    /// Create a writer; CSV output starts with a header row.
+    pub fn new(output: W, format: Format) -> Self {
+        let inner = match format {
+            Format::Csv => Inner::Csv(Box::new(csv::Writer::from_writer(output))),
+            Format::Jsonl => Inner::Jsonl(output),
+        };
+        Self { inner }
+    }
+
+    /// Write one outcome.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the output cannot be written.
+    pub fn write(&mut self, outcome: &Outcome) -> io::Result<()> {
+        match &mut self.inner {
+            Inner::Csv(writer) => writer.serialize(outcome).map_err(io::Error::other),
+            Inner::Jsonl(writer) => {
+                serde_json::to_writer(&mut *writer, outcome)?;
+                writer.write_all(b"\n")
+            }
+        }
+    }
+
+    /// Flush buffered output.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the output cannot be flushed.
+    pub fn flush(&mut self) -> io::Result<()> {
+        match &mut self.inner {
+            Inner::Csv(writer) => writer.flush(),
+            Inner::Jsonl(writer) => writer.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    #![allow(clippy::indexing_slicing)]
+
+    use std::path::Path;
+
+    use super::{
+        Format, Outcome, Record, RecordError, StreamError, Writer, greet_all, greet_record,
+        read_records,
+    };
+    use crate::libs::hello::locale::Locale;
+    use crate::telemetry::metrics::Meters;
+
+    fn read(input: &str, format: Format) -> Vec<Result<Record, RecordError>> {
+        read_records(input.as_bytes(), format).collect()
+    }
+
+    #[test]
+    fn format_from_path_uses_extension() {
+        assert_eq!(Format::from_path(Path::new("a.CSV")), Some(Format::Csv));
+        assert_eq!(
+            Format::from_path(Path::new("a.ndjson")),
+            Some(Format::Jsonl)
+        );
+        assert_eq!(Format::from_path(Path::new("a.txt")), None);
+        assert_eq!(Format::from_path(Path::new("-")), None);
+    }
+
+    #[test]
+    fn reads_csv_with_optional_columns() {
+        let records = read(
+            "name,gender,locale\nAlice,woman,\n Bob , ,ja\n",
+            Format::Csv,
+        );
+        let records: Vec<Record> = records.into_iter().map(Result::unwrap).collect();
+        assert_eq!(
+            records,
+            [
+                Record {
+                    name: String::from("Alice"),
+                    gender: Some(String::from("woman")),
+                    locale: None,
+                },
+                Record {
+                    name: String::from("Bob"),
+                    gender: None,
+                    locale: Some(String::from("ja")),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn csv_without_name_column_is_invalid_per_record() {
+        let records = read("gender\nman\nwoman\n", Format::Csv);
+        assert_eq!(records.len(), 2);
+        assert!(
+            records
+                .iter()
+                .all(|r| matches!(r, Err(RecordError::Invalid(_))))
+        );
+    }
+
+    #[test]
+    fn reads_jsonl_skipping_blank_lines_and_reporting_bad_ones() {
+        let records = read(
+            "{\"name\":\"Alice\",\"extra\":1}\n\n{not json}\n{\"name\":\"Bob\",\"gender\":\"man\"}\n",
+            Format::Jsonl,
+        );
+        assert_eq!(records.len(), 3);
+        assert!(matches!(&records[0], Ok(r) if r.name == "Alice"));
+        assert!(matches!(&records[1], Err(RecordError::Invalid(_))));
+        assert!(matches!(&records[2], Ok(r) if r.gender.as_deref() == Some("man")));
+    }
+
+    #[test]
+    fn writes_outcomes_in_both_formats() {
+        let outcomes = [
+            Outcome {
+                record: 1,
+                name: String::from("Alice"),
+                locale: Some(String::from("en")),
+                greeting: Some(String::from("Hi, Alice, new world!!")),
+                ..Outcome::default()
+            },
+            Outcome {
+                record: 2,
+                error: Some(String::from("invalid gender: x")),
+                ..Outcome::default()
+            },
+        ];
+
+        let mut csv = Vec::new();
+        let mut writer = Writer::new(&mut csv, Format::Csv);
+        for outcome in &outcomes {
+            writer.write(outcome).unwrap();
+        }
+        writer.flush().unwrap();
+        drop(writer);
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "record,name,gender,locale,greeting,error\n\
+             1,Alice,,en,\"Hi, Alice, new world!!\",\n\
+             2,,,,,invalid gender: x\n"
+        );
+
+        let mut jsonl = Vec::new();
+        let mut writer = Writer::new(&mut jsonl, Format::Jsonl);
+        writer.write(&outcomes[1]).unwrap();
+        drop(writer);
+        assert_eq!(
+            String::from_utf8(jsonl).unwrap(),
+            "{\"record\":2,\"name\":\"\",\"gender\":null,\"locale\":null,\"greeting\":null,\"error\":\"invalid gender: x\"}\n"
+        );
+    }
+
+    #[test]
+    fn greet_record_reports_errors_per_record() {
+        let meters = Meters::default();
+
+        let record = Record {
+            name: String::from("花子"),
+            gender: Some(String::from("ms")),
+            locale: Some(String::from("ja_JP")),
+        };
+        let outcome = greet_record(1, Ok(record), Locale::En, None, &meters);
+        assert_eq!(
+            outcome.greeting.as_deref(),
+            Some("こんにちは、花子さん、新しい世界!!")
+        );
+        assert_eq!(outcome.locale.as_deref(), Some("ja"));
+        assert_eq!(outcome.error, None);
+
+        let record = Record {
+            name: String::from("Bob"),
+            gender: Some(String::from("other")),
+            locale: None,
+        };
+        let outcome = greet_record(2, Ok(record), Locale::En, None, &meters);
+        assert_eq!(outcome.greeting, None);
+        assert_eq!(outcome.error.as_deref(), Some("invalid gender: other"));
+
+        let malformed = Err(RecordError::Invalid(String::from("missing field `name`")));
+        let outcome = greet_record(3, malformed, Locale::En, None, &meters);
+        assert_eq!(outcome.record, 3);
+        assert_eq!(
+            outcome.error.as_deref(),
+            Some("invalid record: missing field `name`")
+        );
+    }
+
+    #[test]
+    fn greet_all_streams_outcomes_and_counts_failures() {
+        let mut out = Vec::new();
+        let failed = greet_all(
+            "{\"name\":\"Alice\"}\n{\"name\":\"Bob\",\"gender\":\"x\"}\n".as_bytes(),
+            Format::Jsonl,
+            Locale::En,
+            None,
+            &Meters::default(),
+            &mut out,
+        )
+        .unwrap();
+        assert_eq!(failed, 1);
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(out.lines().count(), 2);
+        assert!(
+            out.contains("\"greeting\":\"Hi, Alice, new world!!\""),
+            "{out}"
+        );
+    }
+
+    #[test]
+    fn greet_all_stops_at_a_write_error() {
+        struct Broken;
+        impl std::io::Write for Broken {
+            fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other("disk full"))
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+        let error = greet_all(
+            br#"{"name":"Alice"}"#.as_slice(),
+            Format::Jsonl,
+            Locale::En,
+            None,
+            &Meters::default(),
+            Broken,
+        )
+        .unwrap_err();
+        assert!(matches!(error, StreamError::Write(_)), "{error}");
+        assert_eq!(error.to_string(), "failed to write batch output: disk full");
+    }
+}
@@ -0,0 +1,164 @@
+//! Running a whole batch from an input path, as `brust batch` does.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use super::{Format, StreamError, greet_all};
+use crate::libs::hello::GreetingError;
+use crate::libs::hello::locale::Locale;
+use crate::libs::hello::template::Template;
+use crate::telemetry::metrics::Meters;
+
+/// Why a batch could not run to the end.
+#[derive(Debug)]
+pub enum RunError {
+    /// No format was given and none follows from the input path.
+    UnknownFormat,
+    /// The greeting template is invalid.
+    Template(GreetingError),
+    /// The input file could not be opened.
+    Open {
+        /// Path of the input file.
+        path: PathBuf,
+        /// Why opening it failed.
+        source: io::Error,
+    },
+    /// Reading or writing failed part-way.
+    Stream(StreamError),
+}
+
+impl fmt::Display for RunError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownFormat => write!(f, "cannot infer the batch format"),
+            Self::Template(e) => write!(f, "invalid template: {e}"),
+            Self::Open { path, source } => {
+                write!(
+                    f,
+                    "failed to open batch input: {}: {source}",
+                    path.display()
+                )
+            }
+            Self::Stream(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for RunError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::UnknownFormat => None,
+            Self::Template(e) => Some(e),
+            Self::Open { source, .. } => Some(source),
+            Self::Stream(e) => Some(e),
+        }
+    }
+}
+
+/// Greet every record of the file at `input`, or of stdin when it is unset
+/// or `-`, streaming one outcome per record to `out` with [`greet_all`].
+///
+/// `format` defaults to the one [inferred](Format::from_path) from `input`,
+/// and `template` is parsed before any record is read. Returns the number of
+/// records that failed.
+///
+/// # Errors
+///
+/// Returns a [`RunError`] if the format cannot be inferred, the template is
+/// invalid, the input cannot be opened, or reading or writing fails
+/// part-way.
+pub fn run(
+    input: Option<&Path>,
+    format: Option<Format>,
+    locale: Locale,
+    template: Option<&str>,
+    meters: &Meters,
+    out: impl Write,
+) -> Result<u64, RunError> {
+    let format = format
+        .or_else(|| input.and_then(Format::from_path))
+        .ok_or(RunError::UnknownFormat)?;
+    let template = template
+        .map(Template::parse)
+        .transpose()
+        .map_err(RunError::Template)?;
+
+    let reader: Box<dyn BufRead> = match input.filter(|p| *p != Path::new("-")) {
+        Some(path) => Box::new(BufReader::new(File::open(path).map_err(|source| {
+            RunError::Open {
+                path: path.to_owned(),
+                source,
+            }
+        })?)),
+        None => Box::new(io::stdin().lock()),
+    };
+    greet_all(reader, format, locale, template.as_ref(), meters, out).map_err(RunError::Stream)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use std::path::Path;
+
+    use super::{RunError, run};
+    use crate::libs::batch::Format;
+    use crate::libs::hello::locale::Locale;
+    use crate::telemetry::metrics::Meters;
+
+    fn run_on(
+        input: &Path,
+        format: Option<Format>,
+        template: Option<&str>,
+    ) -> (Result<u64, RunError>, String) {
+        let mut out = Vec::new();
+        let result = run(
+            Some(input),
+            format,
+            Locale::En,
+            template,
+            &Meters::noop(),
+            &mut out,
+        );
+        (result, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn run_infers_the_format_from_the_input_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("people.jsonl");
+        std::fs::write(
+            &path,
+            "{\"name\":\"Alice\"}\n{\"name\":\"Bob\",\"gender\":\"x\"}\n",
+        )
+        .unwrap();
+
+        let (result, out) = run_on(&path, None, None);
+        assert_eq!(result.unwrap(), 1);
+        assert!(out.starts_with("{\"record\":1,\"name\":\"Alice\""), "{out}");
+    }
+
+    #[test]
+    fn run_reports_what_kept_it_from_starting() {
+        let dir = tempfile::tempdir().unwrap();
+        let (result, _) = run_on(&dir.path().join("people.txt"), None, None);
+        assert!(matches!(result, Err(RunError::UnknownFormat)));
+
+        let (result, _) = run_on(&dir.path().join("people.csv"), None, Some("Hi {nmae}"));
+        assert!(matches!(result, Err(RunError::Template(_))));
+
+        let missing = dir.path().join("missing.csv");
+        let (result, out) = run_on(&missing, Some(Format::Csv), None);
+        let error = result.unwrap_err();
+        assert!(
+            error.to_string().starts_with(&format!(
+                "failed to open batch input: {}: ",
+                missing.display()
+            )),
+            "{error}"
+        );
+        assert!(out.is_empty());
+    }
+}
@@ -0,0 +1,123 @@
+//! Cooperative cancellation of long-running work.
+//!
+//! A [`CancelToken`] is shared between whoever decides to stop (such as a
+//! signal handler) and the work itself, which checks it between steps and
+//! wakes early from sleeps when it fires.
+
+use std::fmt;
+use std::sync::{Arc, Condvar, Mutex, PoisonError};
+use std::time::Duration;
+
+/// Shared flag that stops work once set; clones share the same flag.
+///
+/// A new token is never cancelled until [`cancel`](Self::cancel) is called.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    state: Arc<(Mutex<bool>, Condvar)>,
+}
+
+impl CancelToken {
+    /// Token that has not been cancelled.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel the work and wake every [`wait_timeout`](Self::wait_timeout).
+    pub fn cancel(&self) {
+        let (cancelled, woken) = &*self.state;
+        *cancelled.lock().unwrap_or_else(PoisonError::into_inner) = true;
+        woken.notify_all();
+    }
+
+    /// Whether [`cancel`](Self::cancel) has been called.
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        *self.state.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Block for `timeout` or until cancelled, whichever comes first.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Cancelled`] if the token is or becomes cancelled before
+    /// `timeout` has passed.
+    pub fn wait_timeout(&self, timeout: Duration) -> Result<(), Cancelled> {
+        let (cancelled, woken) = &*self.state;
+        let cancelled = *woken
+            .wait_timeout_while(
+                cancelled.lock().unwrap_or_else(PoisonError::into_inner),
+                timeout,
+                |cancelled| !*cancelled,
+            )
+            .unwrap_or_else(PoisonError::into_inner)
+            .0;
+        if cancelled { Err(Cancelled) } else { Ok(()) }
+    }
+
+    /// Fail with [`Cancelled`] if the token has been cancelled.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Cancelled`] once [`cancel`](Self::cancel) has been called.
+    pub fn check(&self) -> Result<(), Cancelled> {
+        if self.is_cancelled() {
+            Err(Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Error returned when work stops because its [`CancelToken`] fired.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn new_token_waits_out_the_timeout() {
+        let token = CancelToken::new();
+        assert!(!token.is_cancelled());
+        assert_eq!(token.check(), Ok(()));
+        assert_eq!(token.wait_timeout(Duration::from_millis(5)), Ok(()));
+        assert_eq!(token.wait_timeout(Duration::ZERO), Ok(()));
+    }
+
+    #[test]
+    fn cancelled_token_returns_immediately() {
+        let token = CancelToken::new();
+        let shared = token.clone();
+        shared.cancel();
+        assert!(token.is_cancelled());
+        assert_eq!(token.check(), Err(Cancelled));
+        assert_eq!(token.wait_timeout(Duration::MAX), Err(Cancelled));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn cancel_wakes_a_waiting_thread() {
+        let token = CancelToken::new();
+        let waiter = {
+            let token = token.clone();
+            thread::spawn(move || token.wait_timeout(Duration::from_mins(1)))
+        };
+        thread::sleep(Duration::from_millis(10));
+        token.cancel();
+        assert_eq!(waiter.join().unwrap(), Err(Cancelled));
+    }
+}
@@ -1,34 +1,89 @@
 //! Iteration runner for metrics demonstration.
+//!
+//! Each iteration sleeps for a delay drawn from a [`Delay`] distribution with
+//! millisecond resolution. Pass a seeded RNG (see [`rng`]) for reproducible
+//! runs, and a [`VirtualClock`](time::VirtualClock) to skip the sleeping.
+//! [`Runner`] spreads iterations over a pool of worker threads, can run
+//! until a deadline or start iterations on a fixed-rate schedule, and can
+//! fail a share of iterations on purpose to exercise error telemetry.
+//! [`run`](crate::libs::count::run) drives a whole demo run from its
+//! [`Settings`](crate::libs::count::Settings).
+
+pub mod stats;
+pub mod time;
+
+mod delay;
+mod failure;
+mod runner;
+mod settings;
+mod worker;
 
-use std::thread;
 use std::time::Duration;
 
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng as _};
+
+pub use self::delay::{Delay, DelayError};
+pub use self::failure::{ErrorRate, FailureError, IterationError};
+pub use self::runner::Runner;
+pub use self::settings::{Settings, run};
+use self::time::Clock;
+use crate::libs::units::Rate;
+use crate::telemetry::metrics::Meters;
+
 /// Result of a single iteration.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct IterationResult {
-    /// Duration of the random delay in seconds.
-    pub duration_secs: u64,
+    /// Delay slept by this iteration, in whole milliseconds.
+    pub delay: Duration,
+    /// How long after its scheduled slot the iteration started; always zero
+    /// without a [rate](Runner::with_rate).
+    pub lag: Duration,
+    /// Injected failure, if the iteration [failed](Runner::with_failures).
+    pub error: Option<IterationError>,
+}
+
+impl IterationResult {
+    /// Delay in seconds, for metrics and output.
+    #[must_use]
+    pub const fn delay_secs(&self) -> f64 {
+        self.delay.as_secs_f64()
+    }
+
+    /// Scheduling lag in seconds, for metrics and output.
+    #[must_use]
+    pub const fn lag_secs(&self) -> f64 {
+        self.lag.as_secs_f64()
+    }
+}
+
+/// Number of `results` that started so late under `rate` that the next slot
+/// was already due, i.e. slots the schedule missed.
+#[must_use]
+pub fn missed_slots(results: &[IterationResult], rate: &Rate) -> usize {
+    let interval = rate.interval();
+    results.iter().filter(|r| r.lag >= interval).count()
 }
 
-/// Run `count` iterations, each sleeping for a random 1-5 seconds.
+/// Random number generator for [`run_iterations`]: seeded from `seed` for
+/// reproducible delays, otherwise from the operating system.
+#[must_use]
+pub fn rng(seed: Option<u64>) -> StdRng {
+    seed.map_or_else(rand::make_rng, StdRng::seed_from_u64)
+}
+
+/// Run `count` iterations one after another, each sleeping on `clock` for a
+/// delay drawn from `delay`, without recording metrics.
 ///
 /// Logs progress via `tracing::info!` on each iteration.
 /// Returns a `Vec<IterationResult>` recording each iteration's delay.
-pub fn run_iterations(count: u32) -> Vec<IterationResult> {
-    #[allow(clippy::as_conversions)] // u32 -> usize is always safe (usize >= 32 bits)
-    let mut results = Vec::with_capacity(count as usize);
-
-    for i in 1..=count {
-        let secs = rand::random_range(1..=5);
-        tracing::info!(iteration = i, delay_secs = secs, "starting iteration");
-        thread::sleep(Duration::from_secs(secs));
-        tracing::info!(iteration = i, delay_secs = secs, "finished iteration");
-        results.push(IterationResult {
-            duration_secs: secs,
-        });
-    }
-
-    results
+pub fn run_iterations<R: Rng + Send>(
+    count: u32,
+    delay: &Delay,
+    rng: &mut R,
+    clock: &(impl Clock + Sync),
+) -> Vec<IterationResult> {
+    Runner::new(*delay).run(Some(count), rng, clock, &Meters::noop())
 }
 
 #[cfg(test)]
@@ -36,30 +91,58 @@ mod tests {
     #![allow(clippy::unwrap_used)]
     #![allow(clippy::indexing_slicing)]
 
+    use super::time::VirtualClock;
     use super::*;
 
     #[test]
     fn test_run_iterations_returns_correct_count() {
-        let results = run_iterations(2);
+        let clock = VirtualClock::new();
+        let results = run_iterations(2, &Delay::default(), &mut rng(None), &clock);
         assert_eq!(results.len(), 2);
-        assert!((1..=5).contains(&results[0].duration_secs));
-        assert!((1..=5).contains(&results[1].duration_secs));
+        let range = Duration::from_secs(1)..=Duration::from_secs(5);
+        assert!(range.contains(&results[0].delay));
+        assert!(range.contains(&results[1].delay));
     }
 
     #[test]
     fn test_run_iterations_duration_range() {
-        let results = run_iterations(1);
+        let results = run_iterations(1, &Delay::default(), &mut rng(None), &VirtualClock::new());
         assert_eq!(results.len(), 1);
         assert!(
-            (1..=5).contains(&results[0].duration_secs),
-            "duration_secs should be between 1 and 5, got {}", // NOTEST(unreachable): assertion format arg; only reached when test fails
-            results[0].duration_secs
+            (1.0..=5.0).contains(&results[0].delay_secs()),
+            "delay should be between 1 and 5 s, got {:?}", // NOTEST(unreachable): assertion format arg; only reached when test fails
+            results[0].delay
         );
     }
 
     #[test]
     fn test_run_iterations_zero() {
-        let results = run_iterations(0);
+        let clock = VirtualClock::new();
+        let results = run_iterations(0, &Delay::default(), &mut rng(None), &clock);
         assert!(results.is_empty());
+        assert_eq!(clock.now(), Duration::ZERO);
+    }
+
+    #[test]
+    fn virtual_clock_runs_many_iterations_instantly() {
+        let clock = VirtualClock::new();
+        let results = run_iterations(5_000, &Delay::default(), &mut rng(Some(3)), &clock);
+        assert_eq!(results.len(), 5_000);
+        let total: Duration = results.iter().map(|r| r.delay).sum();
+        assert_eq!(clock.now(), total);
+        assert!(results.iter().all(|r| r.delay >= Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn seeded_rng_reproduces_delays() {
+        let delay = Delay::Normal {
+            mean: 100,
+            stddev: 30,
+        };
+        let draw = || {
+            let mut rng = rng(Some(42));
+            (0..5).map(|_| delay.sample(&mut rng)).collect::<Vec<_>>()
+        };
+        assert_eq!(draw(), draw());
     }
 }
@@ -0,0 +1,276 @@
+//! Distributions of per-iteration delays.
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use rand::{Rng, RngExt as _};
+use serde::Deserialize;
+
+/// Distribution of per-iteration delays. All parameters are milliseconds.
+///
+/// Parsed from `fixed:MS`, `uniform:MIN,MAX`, `exponential:MEAN`,
+/// `normal:MEAN,STDDEV` or `lognormal:MEAN,STDDEV`; [`Display`](fmt::Display)
+/// writes the same form back. Samples below zero are clamped to zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum Delay {
+    /// Always the same delay.
+    Fixed(u64),
+    /// Uniform between `min` and `max`, inclusive.
+    Uniform {
+        /// Shortest delay.
+        min: u64,
+        /// Longest delay.
+        max: u64,
+    },
+    /// Exponential with the given mean.
+    Exponential {
+        /// Mean delay.
+        mean: u64,
+    },
+    /// Normal (Gaussian) with the given mean and standard deviation.
+    Normal {
+        /// Mean delay.
+        mean: u64,
+        /// Standard deviation.
+        stddev: u64,
+    },
+    /// Log-normal whose samples have the given mean and standard deviation.
+    LogNormal {
+        /// Mean delay.
+        mean: u64,
+        /// Standard deviation.
+        stddev: u64,
+    },
+}
+
+impl Default for Delay {
+    /// Uniform between 1 and 5 seconds.
+    fn default() -> Self {
+        Self::Uniform {
+            min: 1_000,
+            max: 5_000,
+        }
+    }
+}
+
+impl Delay {
+    /// Distribution name, used as the `brust.delay.distribution` attribute.
+    #[must_use]
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::Fixed(_) => "fixed",
+            Self::Uniform { .. } => "uniform",
+            Self::Exponential { .. } => "exponential",
+            Self::Normal { .. } => "normal",
+            Self::LogNormal { .. } => "lognormal",
+        }
+    }
+
+    /// Draw one delay from the distribution, rounded to whole milliseconds.
+    pub fn sample(&self, rng: &mut impl Rng) -> Duration {
+        #[allow(clippy::cast_precision_loss, clippy::as_conversions)]
+        // millisecond parameters are far below f64's exact integer range
+        let ms = |value: u64| value as f64;
+        let millis = match *self {
+            Self::Fixed(delay) => return Duration::from_millis(delay),
+            Self::Uniform { min, max } => {
+                return Duration::from_millis(rng.random_range(min..=max));
+            }
+            Self::Exponential { mean } => -ms(mean) * (1.0 - rng.random::<f64>()).ln(),
+            Self::Normal { mean, stddev } => ms(stddev).mul_add(standard_normal(rng), ms(mean)),
+            Self::LogNormal { mean, stddev } => {
+                if mean == 0 {
+                    return Duration::ZERO;
+                }
+                // Parameters of the underlying normal that give the requested
+                // mean and standard deviation.
+                let variance = (ms(stddev) / ms(mean)).powi(2).ln_1p();
+                let mu = ms(mean).ln() - variance / 2.0;
+                variance.sqrt().mul_add(standard_normal(rng), mu).exp()
+            }
+        };
+        from_millis_f64(millis)
+    }
+}
+
+impl fmt::Display for Delay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = self.name();
+        match *self {
+            Self::Fixed(delay) => write!(f, "{name}:{delay}"),
+            Self::Exponential { mean } => write!(f, "{name}:{mean}"),
+            Self::Uniform { min: a, max: b }
+            | Self::Normal { mean: a, stddev: b }
+            | Self::LogNormal { mean: a, stddev: b } => write!(f, "{name}:{a},{b}"),
+        }
+    }
+}
+
+/// Error returned when a delay distribution cannot be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DelayError(String);
+
+impl fmt::Display for DelayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid delay distribution: {}", self.0)
+    }
+}
+
+impl std::error::Error for DelayError {}
+
+impl FromStr for Delay {
+    type Err = DelayError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| DelayError(format!("{s:?} ({reason})"));
+        let (name, params) = s
+            .split_once(':')
+            .ok_or_else(|| invalid("expected NAME:PARAMS, e.g. fixed:1000 or uniform:1000,5000"))?;
+        let params = params
+            .split(',')
+            .map(|p| p.trim().parse::<u64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| invalid("parameters must be whole milliseconds"))?;
+        let delay = match (name.trim(), params.as_slice()) {
+            ("fixed", &[delay]) => Self::Fixed(delay),
+            ("uniform", &[min, max]) if min <= max => Self::Uniform { min, max },
+            ("uniform", &[_, _]) => return Err(invalid("MIN must not exceed MAX")),
+            ("exponential", &[mean]) => Self::Exponential { mean },
+            ("normal", &[mean, stddev]) => Self::Normal { mean, stddev },
+            ("lognormal", &[mean, stddev]) => Self::LogNormal { mean, stddev },
+            ("fixed" | "exponential", _) => return Err(invalid("expected one parameter")),
+            ("uniform" | "normal" | "lognormal", _) => {
+                return Err(invalid("expected two parameters"));
+            }
+            _ => {
+                return Err(invalid(
+                    "expected fixed, uniform, exponential, normal or lognormal",
+                ));
+            }
+        };
+        Ok(delay)
+    }
+}
+
+impl TryFrom<String> for Delay {
+    type Error = DelayError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// Standard normal sample (Box-Muller transform).
+fn standard_normal(rng: &mut impl Rng) -> f64 {
+    // 1 - [0, 1) is in (0, 1], so the logarithm is finite.
+    let radius = (-2.0 * (1.0 - rng.random::<f64>()).ln()).sqrt();
+    radius * (std::f64::consts::TAU * rng.random::<f64>()).cos()
+}
+
+/// Round a sample in milliseconds to a whole-millisecond `Duration`,
+/// clamping negative values to zero.
+fn from_millis_f64(millis: f64) -> Duration {
+    let exact = Duration::try_from_secs_f64(millis.max(0.0) / 1_000.0).unwrap_or(Duration::MAX);
+    let rounded = exact.as_micros().saturating_add(500) / 1_000;
+    Duration::from_millis(u64::try_from(rounded).unwrap_or(u64::MAX))
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    #![allow(clippy::indexing_slicing)]
+
+    use super::*;
+    use crate::libs::count::rng;
+
+    /// Mean of `n` samples in milliseconds.
+    fn sample_mean(delay: &Delay, n: u32) -> f64 {
+        let mut rng = rng(Some(7));
+        let total: Duration = (0..n).map(|_| delay.sample(&mut rng)).sum();
+        total.as_secs_f64() * 1_000.0 / f64::from(n)
+    }
+
+    #[test]
+    fn delays_round_trip_through_strings() {
+        for spec in [
+            "fixed:250",
+            "uniform:1000,5000",
+            "exponential:2000",
+            "normal:3000,500",
+            "lognormal:2000,1000",
+        ] {
+            let delay: Delay = spec.parse().unwrap();
+            assert_eq!(delay.to_string(), spec);
+            assert!(spec.starts_with(delay.name()));
+        }
+        assert_eq!(Delay::default().to_string(), "uniform:1000,5000");
+    }
+
+    #[test]
+    fn invalid_delays_are_rejected() {
+        for spec in [
+            "",
+            "fixed",
+            "fixed:1.5",
+            "fixed:1,2",
+            "uniform:10",
+            "uniform:5,1",
+            "normal:-1,2",
+            "poisson:3",
+        ] {
+            let error = spec.parse::<Delay>().unwrap_err();
+            assert!(
+                error
+                    .to_string()
+                    .starts_with("invalid delay distribution: "),
+                "{error}"
+            );
+        }
+    }
+
+    #[test]
+    fn samples_have_millisecond_resolution_and_no_negatives() {
+        let delay = Delay::Normal {
+            mean: 5,
+            stddev: 50,
+        };
+        let mut rng = rng(Some(1));
+        for _ in 0..200 {
+            let sample = delay.sample(&mut rng);
+            assert_eq!(sample.subsec_nanos() % 1_000_000, 0, "{sample:?}");
+        }
+        assert_eq!(
+            Delay::Fixed(1_234).sample(&mut rng),
+            Duration::from_millis(1_234)
+        );
+    }
+
+    #[test]
+    fn sample_means_match_parameters() {
+        let within = |delay: Delay, expected: f64| {
+            let mean = sample_mean(&delay, 20_000);
+            assert!(
+                (mean - expected).abs() < expected * 0.05,
+                "{delay}: mean {mean} ms" // NOTEST(unreachable): assertion format arg; only reached when test fails
+            );
+        };
+        within(Delay::Uniform { min: 100, max: 300 }, 200.0);
+        within(Delay::Exponential { mean: 400 }, 400.0);
+        within(
+            Delay::Normal {
+                mean: 1_000,
+                stddev: 100,
+            },
+            1_000.0,
+        );
+        within(
+            Delay::LogNormal {
+                mean: 800,
+                stddev: 400,
+            },
+            800.0,
+        );
+    }
+}
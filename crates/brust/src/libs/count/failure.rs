@@ -0,0 +1,212 @@
+//! Failures injected into iterations on purpose: their kinds and the rate
+//! they happen at.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+/// Failure injected into an iteration, reported as its `error.type`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(try_from = "String")]
+pub enum IterationError {
+    /// The iteration took too long.
+    Timeout,
+    /// The iteration crashed the way a panic would, without unwinding.
+    PanicLike,
+    /// The iteration produced an invalid result.
+    Invalid,
+}
+
+impl IterationError {
+    /// Every kind, in declaration order.
+    pub const ALL: [Self; 3] = [Self::Timeout, Self::PanicLike, Self::Invalid];
+
+    /// `error.type` attribute value.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Timeout => "timeout",
+            Self::PanicLike => "panic_like",
+            Self::Invalid => "invalid",
+        }
+    }
+}
+
+impl fmt::Display for IterationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Timeout => "iteration timed out",
+            Self::PanicLike => "iteration crashed",
+            Self::Invalid => "iteration produced an invalid result",
+        })
+    }
+}
+
+impl std::error::Error for IterationError {}
+
+impl FromStr for IterationError {
+    type Err = FailureError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == s.trim())
+            .ok_or_else(|| {
+                FailureError(format!(
+                    "unknown error kind {s:?} (expected timeout, panic_like or invalid)"
+                ))
+            })
+    }
+}
+
+impl TryFrom<String> for IterationError {
+    type Error = FailureError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// Probability that an iteration fails, between 0 and 1 inclusive.
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd, Deserialize)]
+#[serde(try_from = "f64")]
+pub struct ErrorRate(f64);
+
+// Never NaN: every constructor checks the range.
+impl Eq for ErrorRate {}
+
+impl ErrorRate {
+    /// No iteration fails.
+    pub const NEVER: Self = Self(0.0);
+
+    /// Probability as a number between 0 and 1.
+    #[must_use]
+    pub const fn get(self) -> f64 {
+        self.0
+    }
+}
+
+impl TryFrom<f64> for ErrorRate {
+    type Error = FailureError;
+
+    fn try_from(rate: f64) -> Result<Self, Self::Error> {
+        if (0.0..=1.0).contains(&rate) {
+            Ok(Self(rate))
+        } else {
+            Err(FailureError(format!(
+                "error rate {rate} is not between 0 and 1"
+            )))
+        }
+    }
+}
+
+impl FromStr for ErrorRate {
+    type Err = FailureError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.trim()
+            .parse::<f64>()
+            .map_err(|_| FailureError(format!("invalid error rate {s:?} (expected e.g. 0.05)")))
+            .and_then(Self::try_from)
+    }
+}
+
+impl fmt::Display for ErrorRate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Error returned when an error rate or kind cannot be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailureError(String);
+
+impl fmt::Display for FailureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for FailureError {}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    #![allow(clippy::indexing_slicing)]
+
+    use super::*;
+    use crate::libs::count::time::VirtualClock;
+    use crate::libs::count::{Delay, IterationResult, Runner, rng};
+    use crate::telemetry::metrics::Meters;
+
+    #[test]
+    fn failures_follow_the_error_rate_and_kinds() {
+        let run = |rate: f64, kinds: &[IterationError]| {
+            Runner::new(Delay::Fixed(1))
+                .with_failures(ErrorRate::try_from(rate).unwrap(), kinds.to_vec())
+                .run(
+                    Some(1_000),
+                    &mut rng(Some(4)),
+                    &VirtualClock::new(),
+                    &Meters::noop(),
+                )
+        };
+        let failed =
+            |results: &[IterationResult]| results.iter().filter(|r| r.error.is_some()).count();
+
+        assert_eq!(failed(&run(0.0, &IterationError::ALL)), 0);
+        assert_eq!(failed(&run(1.0, &[])), 0, "no kinds means no failures");
+        let all = run(1.0, &[IterationError::Invalid]);
+        assert!(all.iter().all(|r| r.error == Some(IterationError::Invalid)));
+
+        let some = run(0.1, &IterationError::ALL);
+        assert!(
+            (60..=140).contains(&failed(&some)),
+            "{} failures",
+            failed(&some)
+        );
+        for kind in IterationError::ALL {
+            assert!(some.iter().any(|r| r.error == Some(kind)), "{kind:?}");
+        }
+        assert_eq!(some, run(0.1, &IterationError::ALL), "seeded runs repeat");
+    }
+
+    #[test]
+    fn failures_do_not_change_seeded_delays() {
+        let delays = |runner: Runner| {
+            runner
+                .run(
+                    Some(20),
+                    &mut rng(Some(9)),
+                    &VirtualClock::new(),
+                    &Meters::noop(),
+                )
+                .into_iter()
+                .map(|r| r.delay)
+                .collect::<Vec<_>>()
+        };
+        let delay = Delay::Uniform { min: 1, max: 100 };
+        assert_eq!(
+            delays(Runner::new(delay)),
+            delays(Runner::new(delay).with_failures(ErrorRate::NEVER, vec![]))
+        );
+    }
+
+    #[test]
+    fn error_rates_and_kinds_parse() {
+        assert!(("0.05".parse::<ErrorRate>().unwrap().get() - 0.05).abs() < f64::EPSILON);
+        assert_eq!("1".parse::<ErrorRate>().unwrap().to_string(), "1");
+        for invalid in ["-0.1", "1.5", "NaN", "often"] {
+            assert!(invalid.parse::<ErrorRate>().is_err(), "{invalid}");
+        }
+        for kind in IterationError::ALL {
+            assert_eq!(kind.as_str().parse::<IterationError>(), Ok(kind));
+        }
+        let error = "segfault".parse::<IterationError>().unwrap_err();
+        assert!(
+            error.to_string().starts_with("unknown error kind"),
+            "{error}"
+        );
+    }
+}
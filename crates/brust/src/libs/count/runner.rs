@@ -0,0 +1,384 @@
+//! The iteration [`Runner`] and its settings.
+
+use std::num::NonZeroU32;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use rand::Rng;
+
+use super::time::Clock;
+use super::worker::LastSpan;
+use super::{Delay, ErrorRate, IterationError, IterationResult};
+use crate::libs::cancellation::{CancelToken, Cancelled};
+use crate::libs::units::Rate;
+use crate::telemetry::metrics::Meters;
+
+/// Iteration runner: a delay distribution, the number of iterations that
+/// may sleep at once and when to stop starting new ones.
+///
+/// Iterations are handed out in order to `concurrency` workers, each inside
+/// its own `count_worker` span. Delays are drawn in iteration order, so a
+/// seeded RNG gives the same delays at any concurrency.
+///
+/// By default each worker starts its next iteration as soon as the previous
+/// one finishes (closed loop). With a [rate](Self::with_rate), iteration `n`
+/// is scheduled `n - 1` intervals after the start instead (open loop); when
+/// every worker is still busy at a slot, that iteration starts late and its
+/// [lag](IterationResult::lag) is recorded rather than silently dropped.
+///
+/// With a [duration](Self::with_duration), no iteration starts (or, with a
+/// rate, is scheduled) at or after the deadline; those already running
+/// finish. With [failures](Self::with_failures), each iteration fails with
+/// the given probability after sleeping its delay. Once its [`CancelToken`]
+/// fires, workers wake from their sleeps and stop taking iterations; the run
+/// returns the iterations that completed.
+///
+/// Every iteration runs in its own `count_iteration` span under its worker's
+/// span. With [span links](Self::with_span_links), each iteration span also
+/// follows from the one started before it, so trace backends can show the
+/// sequence across workers.
+#[derive(Debug, Clone)]
+pub struct Runner {
+    delay: Delay,
+    concurrency: NonZeroU32,
+    duration: Option<Duration>,
+    rate: Option<Rate>,
+    error_rate: ErrorRate,
+    error_kinds: Vec<IterationError>,
+    span_links: bool,
+    cancel: CancelToken,
+}
+
+impl Runner {
+    /// Sequential runner drawing delays from `delay`.
+    #[must_use]
+    pub fn new(delay: Delay) -> Self {
+        Self {
+            delay,
+            concurrency: NonZeroU32::MIN,
+            duration: None,
+            rate: None,
+            error_rate: ErrorRate::NEVER,
+            error_kinds: IterationError::ALL.to_vec(),
+            span_links: false,
+            cancel: CancelToken::new(),
+        }
+    }
+
+    /// Set the number of workers.
+    #[must_use]
+    pub const fn with_concurrency(mut self, concurrency: NonZeroU32) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+
+    /// Stop starting iterations once `duration` has passed.
+    #[must_use]
+    pub const fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    /// Start iterations on a fixed schedule of `rate` instead of back to back.
+    #[must_use]
+    pub const fn with_rate(mut self, rate: Rate) -> Self {
+        self.rate = Some(rate);
+        self
+    }
+
+    /// Fail each iteration with probability `rate`, with a kind drawn
+    /// uniformly from `kinds`; no iteration fails when `kinds` is empty.
+    #[must_use]
+    pub fn with_failures(mut self, rate: ErrorRate, kinds: Vec<IterationError>) -> Self {
+        self.error_rate = rate;
+        self.error_kinds = kinds;
+        self
+    }
+
+    /// Link each iteration span to the iteration span started before it.
+    #[must_use]
+    pub const fn with_span_links(mut self, links: bool) -> Self {
+        self.span_links = links;
+        self
+    }
+
+    /// Stop the run when `cancel` fires.
+    #[must_use]
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Delay distribution.
+    #[must_use]
+    pub const fn delay(&self) -> &Delay {
+        &self.delay
+    }
+
+    /// Number of workers.
+    #[must_use]
+    pub const fn concurrency(&self) -> NonZeroU32 {
+        self.concurrency
+    }
+
+    /// How long iterations keep starting, if limited.
+    #[must_use]
+    pub const fn duration(&self) -> Option<Duration> {
+        self.duration
+    }
+
+    /// Schedule iterations start on, if any.
+    #[must_use]
+    pub const fn rate(&self) -> Option<&Rate> {
+        self.rate.as_ref()
+    }
+
+    /// Probability that an iteration fails.
+    #[must_use]
+    pub const fn error_rate(&self) -> ErrorRate {
+        self.error_rate
+    }
+
+    /// Kinds failing iterations are drawn from.
+    #[must_use]
+    pub fn error_kinds(&self) -> &[IterationError] {
+        &self.error_kinds
+    }
+
+    /// Whether iteration spans link to the one started before them.
+    #[must_use]
+    pub const fn span_links(&self) -> bool {
+        self.span_links
+    }
+
+    /// Token that stops the run.
+    #[must_use]
+    pub const fn cancel(&self) -> &CancelToken {
+        &self.cancel
+    }
+
+    /// Run up to `count` iterations, or until the duration passes or the
+    /// run is cancelled when `count` is `None`, sleeping on `clock`. Returns
+    /// the results of the iterations that completed in iteration order.
+    ///
+    /// Each iteration is counted in `brust.iteration.in_flight` while it
+    /// sleeps and then recorded in `brust.iteration.count` and
+    /// `brust.iteration.duration`; in rate mode its start lag is recorded in
+    /// `brust.iteration.lag`. The first worker runs on the calling thread,
+    /// the others on scoped threads. [`VirtualClock`](super::time::VirtualClock)
+    /// advances by every sleep, so with several workers it reports the
+    /// summed sleeps rather than the wall-clock time.
+    pub fn run<R: Rng + Send>(
+        &self,
+        count: Option<u32>,
+        rng: &mut R,
+        clock: &(impl Clock + Sync),
+        meters: &Meters,
+    ) -> Vec<IterationResult> {
+        let start = clock.now();
+        let queue = Mutex::new((Some(1_u32), rng));
+        let last = LastSpan::default();
+        let last = self.span_links.then_some(&last);
+        let parent = tracing::Span::current();
+        let worker = |worker: u32| {
+            let span = tracing::info_span!(parent: &parent, "count_worker", worker);
+            let _entered = span.enter();
+            let mut done = Vec::new();
+            while let Some(slot) = self.claim(&queue, count, start, clock) {
+                let iteration = slot.iteration;
+                match self.iterate(slot, clock, meters, last) {
+                    Ok(result) => done.push((iteration, result)),
+                    Err(Cancelled) => break,
+                }
+            }
+            done
+        };
+
+        let workers = self.concurrency.get().min(count.unwrap_or(u32::MAX));
+        let mut done = thread::scope(|scope| {
+            let others: Vec<_> = (2..=workers)
+                .map(|id| scope.spawn(move || worker(id)))
+                .collect();
+            let mut done = worker(1);
+            for handle in others {
+                done.extend(
+                    handle
+                        .join()
+                        .unwrap_or_else(|panic| std::panic::resume_unwind(panic)),
+                );
+            }
+            done
+        });
+        done.sort_unstable_by_key(|&(iteration, _)| iteration);
+        done.into_iter().map(|(_, result)| result).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    #![allow(clippy::indexing_slicing)]
+
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+    use crate::libs::count::time::{self, VirtualClock};
+    use crate::libs::count::{missed_slots, rng, run_iterations};
+
+    /// Wall clock that tracks how many sleeps overlap.
+    #[derive(Default)]
+    struct OverlapClock {
+        sleeping: AtomicU32,
+        peak: AtomicU32,
+    }
+
+    impl Clock for OverlapClock {
+        fn now(&self) -> Duration {
+            Duration::ZERO
+        }
+
+        fn sleep(&self, duration: Duration) {
+            let now = self
+                .sleeping
+                .fetch_add(1, Ordering::SeqCst)
+                .saturating_add(1);
+            self.peak.fetch_max(now, Ordering::SeqCst);
+            thread::sleep(duration);
+            self.sleeping.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn concurrent_runs_keep_iteration_order_and_seeded_delays() {
+        let delay = Delay::Uniform { min: 1, max: 1_000 };
+        let sequential = run_iterations(50, &delay, &mut rng(Some(5)), &VirtualClock::new());
+        let concurrent = Runner::new(delay)
+            .with_concurrency(NonZeroU32::new(8).unwrap())
+            .run(
+                Some(50),
+                &mut rng(Some(5)),
+                &VirtualClock::new(),
+                &Meters::noop(),
+            );
+        assert_eq!(concurrent, sequential);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn concurrency_limits_overlapping_iterations() {
+        let clock = OverlapClock::default();
+        let results = Runner::new(Delay::Fixed(20))
+            .with_concurrency(NonZeroU32::new(3).unwrap())
+            .run(Some(9), &mut rng(Some(1)), &clock, &Meters::noop());
+        assert_eq!(results.len(), 9);
+        let peak = clock.peak.load(Ordering::SeqCst);
+        assert!((2..=3).contains(&peak), "peak concurrency {peak}");
+    }
+
+    #[test]
+    fn more_workers_than_iterations() {
+        let runner = Runner::new(Delay::Fixed(1)).with_concurrency(NonZeroU32::MAX);
+        let clock = VirtualClock::new();
+        let results = runner.run(Some(2), &mut rng(None), &clock, &Meters::noop());
+        assert_eq!(results.len(), 2);
+        assert_eq!(clock.now(), Duration::from_millis(2));
+        assert!(
+            runner
+                .run(Some(0), &mut rng(None), &clock, &Meters::noop())
+                .is_empty()
+        );
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn cancelled_run_keeps_completed_iterations() {
+        let cancel = CancelToken::new();
+        let clock = time::SystemClock::new();
+        let runner = Runner::new(Delay::Fixed(60_000))
+            .with_concurrency(NonZeroU32::new(2).unwrap())
+            .with_cancel(cancel.clone());
+        let results = thread::scope(|scope| {
+            let run = scope.spawn(|| runner.run(Some(10), &mut rng(None), &clock, &Meters::noop()));
+            thread::sleep(Duration::from_millis(20));
+            cancel.cancel();
+            run.join().unwrap()
+        });
+        assert!(results.is_empty());
+        assert!(clock.now() < Duration::from_mins(1));
+
+        let clock = VirtualClock::new();
+        let runner = Runner::new(Delay::Fixed(10)).with_cancel(cancel);
+        assert!(
+            runner
+                .run(Some(3), &mut rng(None), &clock, &Meters::noop())
+                .is_empty()
+        );
+        assert_eq!(clock.now(), Duration::ZERO);
+    }
+
+    #[test]
+    fn duration_stops_starting_iterations_at_the_deadline() {
+        let clock = VirtualClock::new();
+        let results = Runner::new(Delay::Fixed(100))
+            .with_duration(Duration::from_secs(1))
+            .run(None, &mut rng(None), &clock, &Meters::noop());
+        assert_eq!(results.len(), 10);
+        assert_eq!(clock.now(), Duration::from_secs(1));
+
+        let results = Runner::new(Delay::Fixed(100))
+            .with_duration(Duration::from_secs(1))
+            .run(
+                Some(3),
+                &mut rng(None),
+                &VirtualClock::new(),
+                &Meters::noop(),
+            );
+        assert_eq!(results.len(), 3, "the count still limits the run");
+    }
+
+    #[test]
+    fn rate_schedules_iterations_on_fixed_slots() {
+        let rate: Rate = "4/s".parse().unwrap();
+        let clock = VirtualClock::new();
+        let results = Runner::new(Delay::Fixed(0))
+            .with_rate(rate)
+            .with_duration(Duration::from_secs(1))
+            .run(None, &mut rng(None), &clock, &Meters::noop());
+        assert_eq!(results.len(), 4);
+        assert!(results.iter().all(|r| r.lag.is_zero()));
+        assert_eq!(clock.now(), Duration::from_millis(750));
+        assert_eq!(missed_slots(&results, &rate), 0);
+    }
+
+    #[test]
+    fn slow_iterations_lag_behind_the_rate_and_miss_slots() {
+        let rate: Rate = "10/s".parse().unwrap();
+        let results = Runner::new(Delay::Fixed(250)).with_rate(rate).run(
+            Some(4),
+            &mut rng(None),
+            &VirtualClock::new(),
+            &Meters::noop(),
+        );
+        let lags: Vec<_> = results.iter().map(|r| r.lag.as_millis()).collect();
+        assert_eq!(lags, [0, 150, 300, 450]);
+        assert_eq!(missed_slots(&results, &rate), 3);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn workers_absorb_slow_iterations_at_a_rate() {
+        let rate: Rate = "50/s".parse().unwrap();
+        let results = Runner::new(Delay::Fixed(30))
+            .with_rate(rate)
+            .with_concurrency(NonZeroU32::new(4).unwrap())
+            .run(
+                Some(6),
+                &mut rng(None),
+                &time::SystemClock::new(),
+                &Meters::noop(),
+            );
+        assert_eq!(results.len(), 6);
+        assert_eq!(missed_slots(&results, &rate), 0, "{results:?}");
+    }
+}
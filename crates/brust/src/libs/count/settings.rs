@@ -0,0 +1,257 @@
+//! Settings of a whole count demo run and the entry point that runs it.
+
+use std::fmt;
+use std::num::NonZeroU32;
+use std::time::Duration;
+
+use super::time::Clock;
+use super::{Delay, ErrorRate, IterationError, IterationResult, Runner, rng};
+use crate::libs::cancellation::CancelToken;
+use crate::libs::units::Rate;
+use crate::telemetry::metrics::Meters;
+
+/// Count demo settings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Settings {
+    /// Number of iterations to run.
+    pub iterations: Option<u32>,
+    /// Per-iteration delay distribution.
+    pub delay: Delay,
+    /// RNG seed; delays are unpredictable when unset.
+    pub seed: Option<u64>,
+    /// Number of iterations that may run at once.
+    pub concurrency: NonZeroU32,
+    /// How long iterations keep starting; unlimited when unset.
+    pub duration: Option<Duration>,
+    /// Fixed schedule iterations start on; back to back when unset.
+    pub rate: Option<Rate>,
+    /// Probability that an iteration fails on purpose.
+    pub error_rate: ErrorRate,
+    /// Kinds failing iterations are drawn from.
+    pub error_kinds: Vec<IterationError>,
+    /// Whether each iteration span links to the one started before it.
+    pub span_links: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            iterations: None,
+            delay: Delay::default(),
+            seed: None,
+            concurrency: NonZeroU32::MIN,
+            duration: None,
+            rate: None,
+            error_rate: ErrorRate::NEVER,
+            error_kinds: IterationError::ALL.to_vec(),
+            span_links: false,
+        }
+    }
+}
+
+impl Settings {
+    /// Whether a run stops on its own, after a number of iterations or a
+    /// duration.
+    #[must_use]
+    pub const fn is_limited(&self) -> bool {
+        self.iterations.is_some() || self.duration.is_some()
+    }
+
+    /// [`Runner`] for these settings that stops early when `cancel` fires.
+    #[must_use]
+    pub fn runner(&self, cancel: &CancelToken) -> Runner {
+        let mut runner = Runner::new(self.delay)
+            .with_concurrency(self.concurrency)
+            .with_failures(self.error_rate, self.error_kinds.clone())
+            .with_span_links(self.span_links)
+            .with_cancel(cancel.clone());
+        if let Some(duration) = self.duration {
+            runner = runner.with_duration(duration);
+        }
+        if let Some(rate) = self.rate {
+            runner = runner.with_rate(rate);
+        }
+        runner
+    }
+}
+
+/// Run the count demo with `settings` and record `OTel` metrics.
+///
+/// Delays are drawn from `settings.delay`, seeded by `settings.seed` when
+/// set, and slept on `clock`. Passes one line per iteration to `line` and
+/// returns the results in iteration order with the wall-clock time of the
+/// run. Iterations stop starting when `cancel` fires.
+#[cfg_attr(
+    feature = "otel",
+    tracing::instrument(
+        name = "run_count",
+        skip_all,
+        fields(
+            count = ?settings.iterations,
+            delay = %settings.delay,
+            seed = ?settings.seed,
+            concurrency = settings.concurrency
+        )
+    )
+)]
+pub fn run(
+    settings: &Settings,
+    clock: &(impl Clock + Sync),
+    cancel: &CancelToken,
+    meters: &Meters,
+    mut line: impl FnMut(fmt::Arguments<'_>),
+) -> (Vec<IterationResult>, Duration) {
+    let start = clock.now();
+    let mut rng = rng(settings.seed);
+    let results = settings
+        .runner(cancel)
+        .run(settings.iterations, &mut rng, clock, meters);
+
+    for (iteration, result) in (1_u32..).zip(&results) {
+        let lag = if settings.rate.is_some() {
+            format!(" (lag {:.3} s)", result.lag_secs())
+        } else {
+            String::new()
+        };
+        let failed = result
+            .error
+            .map(|error| format!(" failed ({})", error.as_str()))
+            .unwrap_or_default();
+        line(format_args!(
+            "iteration {iteration}: {:.3} s{lag}{failed}",
+            result.delay_secs()
+        ));
+    }
+    let wall = clock.now().saturating_sub(start);
+    meters.record_run_duration(wall.as_secs_f64(), "count");
+    (results, wall)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use std::time::Duration;
+
+    use super::{Settings, run};
+    use crate::libs::cancellation::CancelToken;
+    use crate::libs::count::stats::Summary;
+    use crate::libs::count::time::{Clock as _, VirtualClock};
+    use crate::libs::count::{Delay, IterationError};
+    use crate::telemetry::metrics::Meters;
+
+    /// Run `settings` on `clock` and return the results with every line.
+    fn count(settings: &Settings, clock: &VirtualClock) -> (usize, Duration, Vec<String>) {
+        let mut lines = Vec::new();
+        let (results, wall) = run(
+            settings,
+            clock,
+            &CancelToken::new(),
+            &Meters::noop(),
+            |line| {
+                lines.push(line.to_string());
+            },
+        );
+        (results.len(), wall, lines)
+    }
+
+    #[test]
+    fn run_writes_one_line_per_iteration() {
+        let settings = Settings {
+            iterations: Some(2),
+            delay: Delay::Fixed(5),
+            ..Settings::default()
+        };
+        let clock = VirtualClock::new();
+        let (count, wall, lines) = count(&settings, &clock);
+        assert_eq!(count, 2);
+        assert_eq!(lines, ["iteration 1: 0.005 s", "iteration 2: 0.005 s"]);
+        assert_eq!(wall, Duration::from_millis(10));
+        assert_eq!(clock.now(), Duration::from_millis(10));
+    }
+
+    #[test]
+    fn run_reports_lag_at_a_rate() {
+        let settings = Settings {
+            delay: Delay::Fixed(150),
+            duration: Some(Duration::from_millis(300)),
+            rate: Some("10/s".parse().unwrap()),
+            ..Settings::default()
+        };
+        let (_, wall, lines) = count(&settings, &VirtualClock::new());
+        assert_eq!(
+            lines,
+            [
+                "iteration 1: 0.150 s (lag 0.000 s)",
+                "iteration 2: 0.150 s (lag 0.050 s)",
+                "iteration 3: 0.150 s (lag 0.100 s)",
+            ]
+        );
+        assert_eq!(wall, Duration::from_millis(450));
+    }
+
+    #[test]
+    fn run_reports_injected_failures() {
+        let settings = Settings {
+            iterations: Some(2),
+            delay: Delay::Fixed(5),
+            error_rate: "1".parse().unwrap(),
+            error_kinds: vec![IterationError::PanicLike],
+            ..Settings::default()
+        };
+        let (_, _, lines) = count(&settings, &VirtualClock::new());
+        assert_eq!(
+            lines,
+            [
+                "iteration 1: 0.005 s failed (panic_like)",
+                "iteration 2: 0.005 s failed (panic_like)",
+            ]
+        );
+    }
+
+    #[test]
+    fn run_is_limited_by_iterations_or_duration() {
+        assert!(!Settings::default().is_limited());
+        let settings = Settings {
+            duration: Some(Duration::from_mins(10)),
+            ..Settings::default()
+        };
+        assert!(settings.is_limited());
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        let (results, _) = run(
+            &settings,
+            &VirtualClock::new(),
+            &cancel,
+            &Meters::noop(),
+            |_| {},
+        );
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn run_default_delays_on_virtual_clock() {
+        let settings = Settings {
+            iterations: Some(1_000),
+            seed: Some(11),
+            ..Settings::default()
+        };
+        let clock = VirtualClock::new();
+        let (results, wall) = run(
+            &settings,
+            &clock,
+            &CancelToken::new(),
+            &Meters::new(),
+            |_| {},
+        );
+        let summary = Summary::new(results.iter().map(|r| r.delay));
+        assert_eq!(clock.now(), summary.total);
+        assert_eq!(wall, summary.total);
+        assert!(summary.total >= Duration::from_secs(1_000));
+        let (p50, p99) = (summary.p50.unwrap(), summary.p99.unwrap());
+        assert!(
+            (Duration::from_secs(1)..=p99).contains(&p50) && p99 <= Duration::from_secs(5),
+            "p50 {p50:?}, p99 {p99:?}"
+        );
+    }
+}
@@ -0,0 +1,132 @@
+//! Summary statistics over iteration delays.
+//!
+//! Percentiles use the nearest-rank method, so every reported value is one of
+//! the samples; the standard deviation is the population one.
+
+use std::time::Duration;
+
+/// Aggregates of a set of durations; every statistic but `count` and `total`
+/// is `None` when there are no samples.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Summary {
+    /// Number of samples.
+    pub count: usize,
+    /// Sum of all samples.
+    pub total: Duration,
+    /// Shortest sample.
+    pub min: Option<Duration>,
+    /// Longest sample.
+    pub max: Option<Duration>,
+    /// Arithmetic mean.
+    pub mean: Option<Duration>,
+    /// Population standard deviation.
+    pub stddev: Option<Duration>,
+    /// Median.
+    pub p50: Option<Duration>,
+    /// 90th percentile.
+    pub p90: Option<Duration>,
+    /// 99th percentile.
+    pub p99: Option<Duration>,
+}
+
+impl Summary {
+    /// Summarize `samples`, in any order.
+    #[must_use]
+    pub fn new(samples: impl IntoIterator<Item = Duration>) -> Self {
+        let mut sorted: Vec<Duration> = samples.into_iter().collect();
+        sorted.sort_unstable();
+        let count = sorted.len();
+        let total = sorted
+            .iter()
+            .copied()
+            .fold(Duration::ZERO, Duration::saturating_add);
+        let mean = u32::try_from(count).ok().and_then(|n| total.checked_div(n));
+        let stddev = mean.map(|mean| {
+            let mean = mean.as_secs_f64();
+            let squares: f64 = sorted
+                .iter()
+                .map(|sample| (sample.as_secs_f64() - mean).powi(2))
+                .sum();
+            #[allow(clippy::cast_precision_loss, clippy::as_conversions)]
+            // sample counts are far below f64's exact integer range
+            let variance = squares / count as f64;
+            Duration::try_from_secs_f64(variance.sqrt()).unwrap_or(Duration::MAX)
+        });
+        Self {
+            count,
+            total,
+            min: sorted.first().copied(),
+            max: sorted.last().copied(),
+            mean,
+            stddev,
+            p50: percentile(&sorted, 50),
+            p90: percentile(&sorted, 90),
+            p99: percentile(&sorted, 99),
+        }
+    }
+}
+
+/// Nearest-rank `percent`ile of `sorted`, which must be in ascending order;
+/// `None` when it is empty.
+#[must_use]
+pub fn percentile(sorted: &[Duration], percent: u8) -> Option<Duration> {
+    let rank = sorted
+        .len()
+        .saturating_mul(usize::from(percent.min(100)))
+        .div_ceil(100)
+        .max(1);
+    sorted.get(rank.saturating_sub(1)).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn millis(values: &[u64]) -> Vec<Duration> {
+        values.iter().copied().map(Duration::from_millis).collect()
+    }
+
+    #[test]
+    fn empty_samples_have_no_statistics() {
+        let summary = Summary::new([]);
+        assert_eq!(summary, Summary::default());
+        assert_eq!(percentile(&[], 50), None);
+    }
+
+    #[test]
+    fn summary_of_unordered_samples() {
+        let summary = Summary::new(millis(&[400, 200, 800, 600]));
+        assert_eq!(summary.count, 4);
+        assert_eq!(summary.total, Duration::from_secs(2));
+        assert_eq!(summary.min, Some(Duration::from_millis(200)));
+        assert_eq!(summary.max, Some(Duration::from_millis(800)));
+        assert_eq!(summary.mean, Some(Duration::from_millis(500)));
+        // sqrt((300² + 100² + 100² + 300²) / 4) = sqrt(50000) ms
+        let stddev = summary.stddev.map(|d| d.as_secs_f64() * 1_000.0);
+        assert!(stddev.is_some_and(|s| (s - 50_000_f64.sqrt()).abs() < 1e-6));
+        assert_eq!(summary.p50, Some(Duration::from_millis(400)));
+        assert_eq!(summary.p90, Some(Duration::from_millis(800)));
+        assert_eq!(summary.p99, Some(Duration::from_millis(800)));
+    }
+
+    #[test]
+    fn nearest_rank_percentiles() {
+        let sorted = millis(&(1..=100).collect::<Vec<_>>());
+        assert_eq!(percentile(&sorted, 0), Some(Duration::from_millis(1)));
+        assert_eq!(percentile(&sorted, 50), Some(Duration::from_millis(50)));
+        assert_eq!(percentile(&sorted, 90), Some(Duration::from_millis(90)));
+        assert_eq!(percentile(&sorted, 99), Some(Duration::from_millis(99)));
+        assert_eq!(percentile(&sorted, 100), Some(Duration::from_millis(100)));
+        assert_eq!(
+            percentile(&millis(&[7]), 99),
+            Some(Duration::from_millis(7))
+        );
+    }
+
+    #[test]
+    fn identical_samples_have_no_spread() {
+        let summary = Summary::new(millis(&[5, 5, 5]));
+        assert_eq!(summary.stddev, Some(Duration::ZERO));
+        assert_eq!(summary.p99, Some(Duration::from_millis(5)));
+    }
+}
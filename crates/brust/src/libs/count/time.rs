@@ -0,0 +1,177 @@
+//! Time source for the count runner.
+//!
+//! [`run_iterations`](super::run_iterations) reads the time and sleeps through
+//! a [`Clock`], so callers can swap the wall clock for [`VirtualClock`] and
+//! run any number of iterations without waiting.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::libs::cancellation::{CancelToken, Cancelled};
+
+/// Monotonic time source that can also sleep.
+pub trait Clock {
+    /// Time elapsed since the clock was created.
+    fn now(&self) -> Duration;
+
+    /// Block until `duration` has passed on this clock.
+    fn sleep(&self, duration: Duration);
+
+    /// Sleep like [`sleep`](Self::sleep), but stop early once `cancel` fires.
+    ///
+    /// The default checks `cancel` only before and after sleeping; clocks
+    /// that really wait should wake as soon as it fires.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Cancelled`] if `cancel` fired before or during the sleep.
+    fn sleep_unless_cancelled(
+        &self,
+        duration: Duration,
+        cancel: &CancelToken,
+    ) -> Result<(), Cancelled> {
+        cancel.check()?;
+        self.sleep(duration);
+        cancel.check()
+    }
+}
+
+/// Wall clock: [`Instant`] for the time and [`thread::sleep`] to wait, or a
+/// [`CancelToken`] wait when the sleep can be cancelled.
+#[derive(Debug, Clone, Copy)]
+pub struct SystemClock {
+    origin: Instant,
+}
+
+impl SystemClock {
+    /// Clock whose [`now`](Clock::now) counts from this call.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            origin: Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.origin.elapsed()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+
+    fn sleep_unless_cancelled(
+        &self,
+        duration: Duration,
+        cancel: &CancelToken,
+    ) -> Result<(), Cancelled> {
+        cancel.wait_timeout(duration)
+    }
+}
+
+/// Virtual clock: time starts at zero and only moves when something sleeps,
+/// which returns immediately after advancing it.
+///
+/// Time is kept in whole nanoseconds and saturates at about 584 years.
+#[derive(Debug, Default)]
+pub struct VirtualClock {
+    nanos: AtomicU64,
+}
+
+impl VirtualClock {
+    /// Clock at time zero.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            nanos: AtomicU64::new(0),
+        }
+    }
+
+    /// Move the clock forward by `duration` without sleeping.
+    pub fn advance(&self, duration: Duration) {
+        let step = u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+        // The closure always returns Some, so the update cannot fail.
+        let _ = self
+            .nanos
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |nanos| {
+                Some(nanos.saturating_add(step))
+            });
+    }
+}
+
+impl Clock for VirtualClock {
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::SeqCst))
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.advance(duration);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn virtual_clock_advances_only_when_sleeping() {
+        let clock = VirtualClock::new();
+        assert_eq!(clock.now(), Duration::ZERO);
+        clock.sleep(Duration::from_secs(3));
+        clock.advance(Duration::from_millis(250));
+        assert_eq!(clock.now(), Duration::from_millis(3_250));
+    }
+
+    #[test]
+    fn virtual_clock_saturates() {
+        let clock = VirtualClock::new();
+        clock.sleep(Duration::MAX);
+        clock.sleep(Duration::from_secs(1));
+        assert_eq!(clock.now(), Duration::from_nanos(u64::MAX));
+    }
+
+    #[test]
+    fn virtual_clock_does_not_advance_once_cancelled() {
+        let clock = VirtualClock::new();
+        let cancel = CancelToken::new();
+        assert_eq!(
+            clock.sleep_unless_cancelled(Duration::from_secs(1), &cancel),
+            Ok(())
+        );
+        cancel.cancel();
+        assert_eq!(
+            clock.sleep_unless_cancelled(Duration::from_secs(1), &cancel),
+            Err(Cancelled)
+        );
+        assert_eq!(clock.now(), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn system_clock_wakes_when_cancelled() {
+        let clock = SystemClock::new();
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        assert_eq!(
+            clock.sleep_unless_cancelled(Duration::from_mins(1), &cancel),
+            Err(Cancelled)
+        );
+        assert!(clock.now() < Duration::from_mins(1));
+    }
+
+    #[test]
+    fn system_clock_is_monotonic() {
+        let clock = SystemClock::new();
+        let before = clock.now();
+        clock.sleep(Duration::from_millis(2));
+        assert!(clock.now() >= before + Duration::from_millis(2));
+    }
+}
@@ -0,0 +1,303 @@
+//! What each worker of a [`Runner`] does: claim the next iteration, wait
+//! for its slot, sleep its delay and record it.
+
+use std::fmt;
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+
+use rand::Rng;
+use rand::RngExt as _;
+use rand::seq::IndexedRandom as _;
+use tracing::field::Empty;
+
+use super::time::Clock;
+use super::{ErrorRate, IterationError, IterationResult, Runner};
+use crate::libs::cancellation::Cancelled;
+use crate::telemetry::metrics::Meters;
+
+/// Span of the iteration that started last, kept open so the next one can
+/// link to it.
+pub(super) type LastSpan = Mutex<Option<tracing::Span>>;
+
+/// Next iteration number (`None` once exhausted) and the RNG that draws delays.
+pub(super) type Queue<'a, R> = Mutex<(Option<u32>, &'a mut R)>;
+
+/// An iteration handed to a worker.
+#[derive(Clone, Copy)]
+pub(super) struct Slot {
+    pub(super) iteration: u32,
+    delay: Duration,
+    /// Clock time the iteration should start at, in rate mode.
+    scheduled: Option<Duration>,
+    /// Failure to report once the delay is slept.
+    failure: Option<IterationError>,
+}
+
+impl Runner {
+    /// Take the next iteration from `queue`, schedule it and draw its delay,
+    /// or `None` once all `count` iterations are taken, the deadline has
+    /// passed or the run is cancelled.
+    pub(super) fn claim<R: Rng>(
+        &self,
+        queue: &Queue<'_, R>,
+        count: Option<u32>,
+        start: Duration,
+        clock: &impl Clock,
+    ) -> Option<Slot> {
+        let mut queue = queue.lock().unwrap_or_else(PoisonError::into_inner);
+        let (next, rng) = &mut *queue;
+        let iteration = (*next).filter(|&n| count.is_none_or(|count| n <= count))?;
+        let scheduled = self.rate().map(|rate| {
+            let slots = rate.interval().saturating_mul(iteration.saturating_sub(1));
+            start.saturating_add(slots)
+        });
+        let starts = scheduled.unwrap_or_else(|| clock.now());
+        let open = self
+            .duration()
+            .is_none_or(|duration| starts < start.saturating_add(duration));
+        let claimed = (open && !self.cancel().is_cancelled()).then(|| {
+            *next = iteration.checked_add(1);
+            let delay = self.delay().sample(rng);
+            // Only draw when failures are on, so seeded delays stay the same.
+            let fails =
+                self.error_rate() > ErrorRate::NEVER && rng.random_bool(self.error_rate().get());
+            Slot {
+                iteration,
+                delay,
+                scheduled,
+                failure: fails
+                    .then(|| self.error_kinds().choose(rng).copied())
+                    .flatten(),
+            }
+        });
+        drop(queue);
+        claimed
+    }
+
+    /// Wait for the slot's scheduled start, then sleep for its delay in a
+    /// `count_iteration` span and record it; a cancelled iteration is logged
+    /// but not recorded. With `last`, the span follows from the previous one.
+    ///
+    /// A failed or cancelled iteration sets the span's status to `ERROR` with
+    /// its `error.type`; a failed one is also recorded in
+    /// `brust.iteration.errors`. Successful iterations leave the status unset.
+    pub(super) fn iterate(
+        &self,
+        slot: Slot,
+        clock: &impl Clock,
+        meters: &Meters,
+        last: Option<&LastSpan>,
+    ) -> Result<IterationResult, Cancelled> {
+        let Slot {
+            iteration,
+            delay,
+            scheduled,
+            failure,
+        } = slot;
+        let mut lag = Duration::ZERO;
+        if let Some(scheduled) = scheduled {
+            clock.sleep_unless_cancelled(scheduled.saturating_sub(clock.now()), self.cancel())?;
+            lag = clock.now().saturating_sub(scheduled);
+            meters.record_iteration_lag(lag.as_secs_f64());
+        }
+        let span = tracing::info_span!(
+            "count_iteration",
+            iteration.index = iteration,
+            iteration.delay_s = delay.as_secs_f64(),
+            otel.status_code = Empty,
+            otel.status_message = Empty,
+            error.r#type = Empty,
+        );
+        if let Some(last) = last {
+            follow_last(last, &span);
+        }
+        let _entered = span.enter();
+        let delay_ms = delay.as_millis();
+        meters.in_flight_add(1);
+        tracing::info!(iteration, delay_ms, "starting iteration");
+        let slept = clock.sleep_unless_cancelled(delay, self.cancel());
+        meters.in_flight_add(-1);
+        if slept.is_err() {
+            tracing::warn!(iteration, delay_ms, "iteration cancelled");
+            mark_error(&span, "cancelled", &Cancelled);
+            return Err(Cancelled);
+        }
+        meters.record_iteration(delay.as_secs_f64(), self.delay().name());
+        if let Some(error) = failure {
+            let error_type = error.as_str();
+            tracing::warn!(
+                iteration,
+                delay_ms,
+                error.r#type = error_type,
+                "iteration failed: {error}"
+            );
+            mark_error(&span, error_type, &error);
+            meters.record_iteration_error(error_type);
+        } else {
+            tracing::info!(iteration, delay_ms, "finished iteration");
+        }
+        Ok(IterationResult {
+            delay,
+            lag,
+            error: failure,
+        })
+    }
+}
+
+/// Make `span` follow from the span in `last`, if any, and put it there in
+/// its place.
+fn follow_last(last: &LastSpan, span: &tracing::Span) {
+    let mut last = last.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(previous) = last.replace(span.clone()) {
+        span.follows_from(&previous);
+    }
+}
+
+/// Set `span`'s status to `ERROR` with `error_type` and `error`'s message.
+fn mark_error(span: &tracing::Span, error_type: &str, error: &dyn fmt::Display) {
+    span.record("otel.status_code", "ERROR");
+    span.record("otel.status_message", error.to_string().as_str());
+    span.record("error.type", error_type);
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    #![allow(clippy::indexing_slicing)]
+
+    use tracing::subscriber::with_default;
+    use tracing_mock::{expect, subscriber};
+
+    use crate::libs::count::time::VirtualClock;
+    use crate::libs::count::{Delay, ErrorRate, IterationError, Runner, rng, run_iterations};
+    use crate::telemetry::metrics::Meters;
+
+    #[test]
+    fn failed_iteration_marks_its_span() {
+        let worker = expect::span().named("count_worker");
+        let iteration = expect::span().named("count_iteration");
+        let (subscriber, handle) = subscriber::mock()
+            .new_span(worker.clone())
+            .enter(worker.clone())
+            .new_span(
+                iteration.clone().with_fields(
+                    expect::field("iteration.index")
+                        .with_value(&1_u32)
+                        .and(expect::field("iteration.delay_s").with_value(&0.0)),
+                ),
+            )
+            .enter(iteration.clone())
+            .event(expect::event().with_fields(expect::msg("starting iteration")))
+            .event(
+                expect::event()
+                    .at_level(tracing::Level::WARN)
+                    .with_fields(expect::field("error.type").with_value(&"timeout")),
+            )
+            .record(
+                iteration.clone(),
+                expect::field("otel.status_code").with_value(&"ERROR"),
+            )
+            .record(
+                iteration.clone(),
+                expect::field("otel.status_message").with_value(&"iteration timed out"),
+            )
+            .record(
+                iteration.clone(),
+                expect::field("error.type").with_value(&"timeout"),
+            )
+            .exit(iteration.clone())
+            .drop_span(iteration)
+            .exit(worker.clone())
+            .drop_span(worker)
+            .only()
+            .run_with_handle();
+
+        with_default(subscriber, || {
+            let results = Runner::new(Delay::Fixed(0))
+                .with_failures(
+                    ErrorRate::try_from(1.0).unwrap(),
+                    vec![IterationError::Timeout],
+                )
+                .run(
+                    Some(1),
+                    &mut rng(None),
+                    &VirtualClock::new(),
+                    &Meters::noop(),
+                );
+            assert_eq!(results[0].error, Some(IterationError::Timeout));
+        });
+
+        handle.assert_finished();
+    }
+
+    #[test]
+    fn iteration_spans_follow_the_previous_iteration() {
+        let worker = expect::span().named("count_worker");
+        let iteration = expect::span().named("count_iteration");
+        let numbered = |index: u32| {
+            iteration
+                .clone()
+                .with_fields(expect::field("iteration.index").with_value(&index))
+        };
+        let (subscriber, handle) = subscriber::mock()
+            .new_span(worker.clone())
+            .enter(worker.clone())
+            .new_span(numbered(1))
+            .enter(iteration.clone())
+            .event(expect::event().with_fields(expect::msg("starting iteration")))
+            .event(expect::event().with_fields(expect::msg("finished iteration")))
+            .exit(iteration.clone())
+            .new_span(numbered(2))
+            .follows_from(iteration.clone(), iteration.clone())
+            .drop_span(iteration.clone())
+            .enter(iteration.clone())
+            .event(expect::event().with_fields(expect::msg("starting iteration")))
+            .event(expect::event().with_fields(expect::msg("finished iteration")))
+            .exit(iteration.clone())
+            .exit(worker.clone())
+            .drop_span(worker)
+            .drop_span(iteration)
+            .only()
+            .run_with_handle();
+
+        with_default(subscriber, || {
+            Runner::new(Delay::Fixed(0)).with_span_links(true).run(
+                Some(2),
+                &mut rng(None),
+                &VirtualClock::new(),
+                &Meters::noop(),
+            );
+        });
+
+        handle.assert_finished();
+    }
+
+    #[test]
+    fn sequential_worker_runs_in_its_own_span() {
+        let worker = expect::span().named("count_worker");
+        let iteration = expect::span().named("count_iteration");
+        let (subscriber, handle) = subscriber::mock()
+            .new_span(
+                worker
+                    .clone()
+                    .with_fields(expect::field("worker").with_value(&1_u32)),
+            )
+            .enter(worker.clone())
+            .new_span(iteration.clone())
+            .enter(iteration.clone())
+            .event(expect::event().with_fields(expect::msg("starting iteration")))
+            .event(expect::event().with_fields(expect::msg("finished iteration")))
+            .exit(iteration.clone())
+            .drop_span(iteration)
+            .exit(worker.clone())
+            .drop_span(worker)
+            .only()
+            .run_with_handle();
+
+        with_default(subscriber, || {
+            run_iterations(1, &Delay::Fixed(0), &mut rng(None), &VirtualClock::new());
+        });
+
+        handle.assert_finished();
+    }
+}
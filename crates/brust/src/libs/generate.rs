@@ -0,0 +1,129 @@
+//! Shell completion scripts and man pages generated from clap definitions.
+//!
+//! Every function is generic over the [`CommandFactory`] of a binary's CLI so
+//! `brust` and other binaries built on this crate generate their pages the
+//! same way. The binary name is the root command's name.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use clap::{Command, CommandFactory};
+use clap_complete::Shell;
+use clap_mangen::Man;
+
+/// Completion script of `C` for `shell`, covering every subcommand and flag.
+#[must_use]
+pub fn completions<C: CommandFactory>(shell: Shell) -> String {
+    let mut cmd = C::command();
+    let bin_name = cmd.get_name().to_owned();
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut cmd, bin_name, &mut script);
+    String::from_utf8_lossy(&script).into_owned()
+}
+
+/// One man page per command of `C`: the root command first, then a page for
+/// every visible subcommand, depth first (`brust-config(1)`,
+/// `brust-config-show(1)`, ...).
+#[must_use]
+pub fn man_pages<C: CommandFactory>() -> Vec<Man> {
+    let mut cmd = C::command().disable_help_subcommand(true);
+    cmd.build();
+    let mut pages = Vec::new();
+    collect_pages(cmd, &mut pages);
+    pages
+}
+
+fn collect_pages(cmd: Command, pages: &mut Vec<Man>) {
+    let subcommands: Vec<Command> = cmd
+        .get_subcommands()
+        .filter(|sub| !sub.is_hide_set())
+        .cloned()
+        .collect();
+    pages.push(Man::new(cmd));
+    for sub in subcommands {
+        collect_pages(sub, pages);
+    }
+}
+
+/// Write every page of [`man_pages`] into `dir`, creating it if needed.
+///
+/// Returns the written paths in page order.
+///
+/// # Errors
+///
+/// Returns an error if `dir` cannot be created or a page cannot be written.
+pub fn write_man_pages<C: CommandFactory>(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir)?;
+    man_pages::<C>()
+        .iter()
+        .map(|page| page.generate_to(dir))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use clap::{Parser, Subcommand};
+    use clap_complete::Shell;
+
+    use super::{completions, man_pages, write_man_pages};
+
+    #[derive(Parser)]
+    #[command(name = "demo")]
+    struct Demo {
+        #[command(subcommand)]
+        command: DemoCommand,
+    }
+
+    #[derive(Subcommand)]
+    enum DemoCommand {
+        /// Visible leaf
+        Run,
+        /// Nested group
+        #[command(subcommand)]
+        Config(ConfigCommand),
+        /// Hidden from pages
+        #[command(hide = true)]
+        Secret,
+    }
+
+    #[derive(Subcommand)]
+    enum ConfigCommand {
+        /// Print the configuration
+        Show,
+    }
+
+    #[test]
+    fn pages_walk_visible_subcommands_depth_first() {
+        let names: Vec<String> = man_pages::<Demo>()
+            .iter()
+            .map(clap_mangen::Man::get_filename)
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "demo.1",
+                "demo-run.1",
+                "demo-config.1",
+                "demo-config-show.1"
+            ]
+        );
+    }
+
+    #[test]
+    fn write_man_pages_returns_written_paths_in_page_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = write_man_pages::<Demo>(&dir.path().join("man1")).unwrap();
+        assert_eq!(paths.len(), 4);
+        assert!(paths.first().unwrap().ends_with("man1/demo.1"));
+        assert!(paths.iter().all(|p| p.is_file()));
+    }
+
+    #[test]
+    fn completions_use_the_root_command_name() {
+        let script = completions::<Demo>(Shell::Bash);
+        assert!(script.contains("_demo()"), "{script}");
+    }
+}
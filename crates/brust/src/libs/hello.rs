@@ -1,28 +1,59 @@
+/// 設定済みの挨拶生成器
+pub mod greeter;
+/// 敬称モデル
+pub mod honorific;
+/// ロケールとメッセージカタログ
+pub mod locale;
+/// ユーザー定義の挨拶テンプレート
+pub mod template;
+
+use self::honorific::{Gender, Honorific, validate_prefix};
+use self::locale::{Locale, fill};
+use self::template::{Context, Template, TimeOfDay};
+
 /// 挨拶生成時のエラー種別
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum GreetingError {
-    /// 性別が未指定
-    UnknownGender,
     /// 無効な性別が指定された
     InvalidGender(String),
+    /// 無効なユーザー定義の前置敬称が指定された
+    InvalidHonorific(String),
+    /// 挨拶テンプレートの構文が不正（理由を保持）
+    InvalidTemplate(String),
+}
+
+impl GreetingError {
+    /// `error.type` メトリクス属性の値
+    #[must_use]
+    pub const fn error_type(&self) -> &'static str {
+        match self {
+            Self::InvalidGender(_) => "invalid_gender",
+            Self::InvalidHonorific(_) => "invalid_honorific",
+            Self::InvalidTemplate(_) => "invalid_template",
+        }
+    }
 }
 
 impl std::fmt::Display for GreetingError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::UnknownGender => write!(f, "gender not specified"),
             Self::InvalidGender(gender) => write!(f, "invalid gender: {gender}"),
+            Self::InvalidHonorific(prefix) => write!(f, "invalid honorific prefix: {prefix:?}"),
+            Self::InvalidTemplate(reason) => write!(f, "invalid greeting template: {reason}"),
         }
     }
 }
 
 impl std::error::Error for GreetingError {}
 
-/// 性別を考慮した挨拶メッセージを生成
+/// 性別を考慮した挨拶メッセージを英語で生成
+///
+/// 性別を文字列で受け取る互換 API です。型付きの敬称を使う場合は
+/// [`sayhello_in`] を使ってください。
 ///
 /// # Arguments
 /// * `name` - 挨拶対象の名前
-/// * `gender` - 性別（None, Some("man"), Some("woman"), その他）
+/// * `gender` - 性別（None, Some("man"), Some("woman"), Some("neutral"), Some("dr"), Some("prof"), その他）
 ///
 /// # Returns
 /// * `Ok(String)` - 正常な挨拶文字列
@@ -33,7 +64,7 @@ impl std::error::Error for GreetingError {}
 ///
 /// # Examples
 /// ```
-/// use hello::sayhello;
+/// use brust::libs::hello::sayhello;
 ///
 /// // 成功例
 /// let result = sayhello("Alice", Some("woman"));
@@ -44,17 +75,76 @@ impl std::error::Error for GreetingError {}
 /// assert!(matches!(result, Err(_)));
 /// ```
 pub fn sayhello(name: &str, gender: Option<&str>) -> Result<String, GreetingError> {
-    match gender {
-        Some("man") => Ok(format!("Hi, Mr. {name}")),
-        Some("woman") => Ok(format!("Hi, Ms. {name}")),
-        None => Ok(format!("Hi, {name}")),
-        Some(invalid) => Err(GreetingError::InvalidGender(String::from(invalid))),
+    let honorific = gender
+        .map(str::parse::<Gender>)
+        .transpose()?
+        .map(Honorific::from);
+    sayhello_in(Locale::En, name, honorific.as_ref())
+}
+
+/// 指定ロケールのメッセージカタログで挨拶メッセージを生成
+///
+/// 敬称の位置はロケールごとに異なります（`en`: "Mr. {name}", `ja`: "{name}さん"）。
+///
+/// # Errors
+/// ユーザー定義の前置敬称が不正な場合に `GreetingError::InvalidHonorific` を返します。
+pub fn sayhello_in(
+    locale: Locale,
+    name: &str,
+    honorific: Option<&Honorific>,
+) -> Result<String, GreetingError> {
+    let addressee = addressee(locale, name, honorific)?;
+    Ok(fill(
+        locale.catalog().greeting,
+        &[("addressee", &addressee)],
+    ))
+}
+
+/// ユーザー定義テンプレートで挨拶メッセージを生成
+///
+/// テンプレートの出力がそのまま挨拶行になり、カタログの `announcement` は
+/// 適用されません。
+///
+/// # Errors
+/// ユーザー定義の前置敬称が不正な場合に `GreetingError::InvalidHonorific` を返します。
+pub fn sayhello_template(
+    template: &Template,
+    locale: Locale,
+    name: &str,
+    honorific: Option<&Honorific>,
+    time_of_day: TimeOfDay,
+) -> Result<String, GreetingError> {
+    let addressee = addressee(locale, name, honorific)?;
+    Ok(template.render(&Context {
+        name,
+        title: honorific.map_or("", |h| h.title(locale)),
+        addressee: &addressee,
+        locale,
+        time_of_day,
+    }))
+}
+
+/// 敬称を検証して宛名を組み立てる
+fn addressee(
+    locale: Locale,
+    name: &str,
+    honorific: Option<&Honorific>,
+) -> Result<String, GreetingError> {
+    match honorific {
+        None => Ok(String::from(name)),
+        Some(honorific) => {
+            if let Honorific::Custom(prefix) = honorific {
+                validate_prefix(prefix)?;
+            }
+            Ok(honorific.address(locale, name))
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{GreetingError, sayhello};
+    use super::template::{Template, TimeOfDay};
+    use super::{GreetingError, Honorific, Locale, sayhello, sayhello_in, sayhello_template};
 
     #[test]
     fn test_sayhello_with_gender_man() {
@@ -86,15 +176,116 @@ mod tests {
         assert_eq!(result, GreetingError::InvalidGender(String::new()));
     }
 
+    #[test]
+    fn test_sayhello_in_ja_places_honorific_after_name() {
+        let result = sayhello_in(Locale::Ja, "太郎", Some(&Honorific::Mr)).unwrap();
+        assert_eq!(result, "こんにちは、太郎さん");
+        let result = sayhello_in(Locale::Ja, "花子", Some(&Honorific::Ms)).unwrap();
+        assert_eq!(result, "こんにちは、花子さん");
+        let result = sayhello_in(Locale::Ja, "山田", Some(&Honorific::Prof)).unwrap();
+        assert_eq!(result, "こんにちは、山田先生");
+    }
+
+    #[test]
+    fn test_sayhello_in_ja_without_gender() {
+        let result = sayhello_in(Locale::Ja, "Bob", None).unwrap();
+        assert_eq!(result, "こんにちは、Bob");
+    }
+
+    #[test]
+    fn test_sayhello_in_titles_and_neutral() {
+        let greet = |h| sayhello_in(Locale::En, "Sam", Some(&h)).unwrap();
+        assert_eq!(greet(Honorific::Mx), "Hi, Mx. Sam");
+        assert_eq!(greet(Honorific::Dr), "Hi, Dr. Sam");
+        assert_eq!(greet(Honorific::Prof), "Hi, Prof. Sam");
+    }
+
+    #[test]
+    fn test_sayhello_in_custom_prefix() {
+        let custom = Honorific::Custom(String::from("Sir"));
+        let result = sayhello_in(Locale::En, "Lancelot", Some(&custom)).unwrap();
+        assert_eq!(result, "Hi, Sir Lancelot");
+    }
+
+    #[test]
+    fn test_sayhello_in_invalid_custom_prefix() {
+        let custom = Honorific::Custom(String::from(" "));
+        let result = sayhello_in(Locale::Ja, "Bob", Some(&custom)).unwrap_err();
+        assert_eq!(result, GreetingError::InvalidHonorific(String::from(" ")));
+        assert_eq!(result.error_type(), "invalid_honorific");
+    }
+
+    #[test]
+    fn test_sayhello_in_name_with_braces_is_verbatim() {
+        let result = sayhello_in(Locale::En, "{addressee}", Some(&Honorific::Mr)).unwrap();
+        assert_eq!(result, "Hi, Mr. {addressee}");
+    }
+
+    #[test]
+    fn test_sayhello_template_fills_placeholders() {
+        let template = Template::parse("Good {time_of_day}, {title} {name}! [{locale}]").unwrap();
+        let result = sayhello_template(
+            &template,
+            Locale::En,
+            "Bob",
+            Some(&Honorific::Dr),
+            TimeOfDay::Morning,
+        )
+        .unwrap();
+        assert_eq!(result, "Good morning, Dr. Bob! [en]");
+    }
+
+    #[test]
+    fn test_sayhello_template_without_honorific_drops_title_space() {
+        let template = Template::parse("Good day, {title} {name}!").unwrap();
+        let result =
+            sayhello_template(&template, Locale::En, "Bob", None, TimeOfDay::Night).unwrap();
+        assert_eq!(result, "Good day, Bob!");
+    }
+
+    #[test]
+    fn test_sayhello_template_ja_addressee() {
+        let template = Template::parse("{time_of_day}です、{addressee}").unwrap();
+        let result = sayhello_template(
+            &template,
+            Locale::Ja,
+            "山田",
+            Some(&Honorific::Prof),
+            TimeOfDay::Evening,
+        )
+        .unwrap();
+        assert_eq!(result, "夕方です、山田先生");
+    }
+
+    #[test]
+    fn test_sayhello_template_validates_custom_prefix() {
+        let template = Template::parse("{addressee}").unwrap();
+        let custom = Honorific::Custom(String::new());
+        let result = sayhello_template(
+            &template,
+            Locale::En,
+            "Bob",
+            Some(&custom),
+            TimeOfDay::Night,
+        );
+        assert_eq!(result, Err(GreetingError::InvalidHonorific(String::new())));
+    }
+
     #[test]
     fn test_greeting_error_display() {
         assert_eq!(
-            GreetingError::UnknownGender.to_string(),
-            "gender not specified"
+            GreetingError::InvalidHonorific(String::new()).to_string(),
+            "invalid honorific prefix: \"\""
         );
         assert_eq!(
             GreetingError::InvalidGender(String::from("test")).to_string(),
             "invalid gender: test"
         );
+        let error = GreetingError::InvalidTemplate(String::from("unknown placeholder `{x}`"));
+        assert_eq!(
+            error.to_string(),
+            "invalid greeting template: unknown placeholder `{x}`"
+        );
+        assert_eq!(error.error_type(), "invalid_template");
     }
 }
@@ -0,0 +1,157 @@
+//! ロケール・敬称・テンプレートをまとめた挨拶の組み立て
+
+use super::honorific::Honorific;
+use super::locale::{Locale, fill};
+use super::template::{Template, TimeOfDay};
+use super::{GreetingError, sayhello_in, sayhello_template};
+use crate::telemetry::metrics::Meters;
+
+/// 設定済みの挨拶生成器
+///
+/// ロケール・敬称・テンプレートを一度設定し、名前ごとに挨拶行を生成します。
+///
+/// # Examples
+/// ```
+/// use brust::{Greeter, Meters};
+/// use brust::libs::hello::honorific::Honorific;
+/// use brust::libs::hello::locale::Locale;
+///
+/// let greeter = Greeter::new(Locale::Ja).with_honorific(Some(Honorific::Mr));
+/// let line = greeter.greet("太郎", &Meters::noop()).unwrap();
+/// assert_eq!(line, "こんにちは、太郎さん、新しい世界!!");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Greeter {
+    locale: Locale,
+    honorific: Option<Honorific>,
+    template: Option<Template>,
+}
+
+impl Greeter {
+    /// `locale` のメッセージカタログで敬称なしに挨拶する生成器を作成
+    #[must_use]
+    pub const fn new(locale: Locale) -> Self {
+        Self {
+            locale,
+            honorific: None,
+            template: None,
+        }
+    }
+
+    /// 敬称を設定（None なら敬称なし）
+    #[must_use]
+    pub fn with_honorific(mut self, honorific: Option<Honorific>) -> Self {
+        self.honorific = honorific;
+        self
+    }
+
+    /// 挨拶行のテンプレートを設定（None ならカタログの文言）
+    #[must_use]
+    pub fn with_template(mut self, template: Option<Template>) -> Self {
+        self.template = template;
+        self
+    }
+
+    /// 挨拶に使うロケール
+    #[must_use]
+    pub const fn locale(&self) -> Locale {
+        self.locale
+    }
+
+    /// 設定された敬称
+    #[must_use]
+    pub const fn honorific(&self) -> Option<&Honorific> {
+        self.honorific.as_ref()
+    }
+
+    /// 現在時刻で挨拶行を生成し、`brust.greeting.count` などに記録
+    ///
+    /// # Errors
+    /// ユーザー定義の前置敬称が不正な場合に `GreetingError::InvalidHonorific` を返します。
+    pub fn greet(&self, name: &str, meters: &Meters) -> Result<String, GreetingError> {
+        let result = self.line_at(name, TimeOfDay::now());
+        self.record(meters, result.as_ref().err());
+        result
+    }
+
+    /// 時間帯を指定して挨拶行を生成（メトリクスは記録しない）
+    ///
+    /// テンプレートがなければカタログの `announcement` で挨拶を包みます。
+    ///
+    /// # Errors
+    /// ユーザー定義の前置敬称が不正な場合に `GreetingError::InvalidHonorific` を返します。
+    pub fn line_at(&self, name: &str, time_of_day: TimeOfDay) -> Result<String, GreetingError> {
+        let honorific = self.honorific.as_ref();
+        self.template.as_ref().map_or_else(
+            || {
+                sayhello_in(self.locale, name, honorific).map(|greeting| {
+                    fill(
+                        self.locale.catalog().announcement,
+                        &[("greeting", greeting.as_str())],
+                    )
+                })
+            },
+            |template| sayhello_template(template, self.locale, name, honorific, time_of_day),
+        )
+    }
+
+    /// 失敗時の挨拶行：敬称もテンプレートも使わずカタログの文言で挨拶
+    #[must_use]
+    pub fn fallback(&self, name: &str) -> String {
+        let catalog = self.locale.catalog();
+        let greeting = fill(catalog.greeting, &[("addressee", name)]);
+        fill(catalog.announcement, &[("greeting", greeting.as_str())])
+    }
+
+    /// 挨拶 1 回分を `brust.greeting.count` に、失敗なら `brust.greeting.errors`
+    /// にも記録
+    ///
+    /// 生成器の外で起きた失敗（テンプレートや性別の解析エラー）の記録にも使えます。
+    pub fn record(&self, meters: &Meters, error: Option<&GreetingError>) {
+        let locale = self.locale.as_str();
+        if let Some(e) = error {
+            meters.record_greeting("invalid", locale);
+            meters.record_greeting_error(e.error_type());
+        } else {
+            meters.record_greeting(
+                self.honorific
+                    .as_ref()
+                    .map_or("none", Honorific::metric_value),
+                locale,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::{Greeter, GreetingError, Honorific, Locale, Template, TimeOfDay};
+    use crate::telemetry::metrics::Meters;
+
+    #[test]
+    fn greeter_defaults_to_english_without_honorific() {
+        let line = Greeter::default().greet("Bob", &Meters::noop()).unwrap();
+        assert_eq!(line, "Hi, Bob, new world!!");
+    }
+
+    #[test]
+    fn greeter_applies_honorific_and_template() {
+        let template = Template::parse("Good {time_of_day}, {title} {name}!").unwrap();
+        let greeter = Greeter::new(Locale::En)
+            .with_honorific(Some(Honorific::Dr))
+            .with_template(Some(template));
+        let line = greeter.line_at("Who", TimeOfDay::Evening).unwrap();
+        assert_eq!(line, "Good evening, Dr. Who!");
+    }
+
+    #[test]
+    fn greeter_rejects_invalid_custom_honorific() {
+        let greeter =
+            Greeter::new(Locale::Ja).with_honorific(Some(Honorific::Custom(String::from(" "))));
+        let error = greeter.greet("花子", &Meters::noop()).unwrap_err();
+        assert_eq!(error, GreetingError::InvalidHonorific(String::from(" ")));
+        assert_eq!(greeter.fallback("花子"), "こんにちは、花子、新しい世界!!");
+    }
+}
@@ -0,0 +1,200 @@
+//! 敬称モデル
+//!
+//! [`Gender`] は CLI や設定ファイルで選べる組み込みの選択肢、[`Honorific`] は
+//! それにユーザー定義の前置敬称を加えた、挨拶生成で使う敬称です。
+
+use std::str::FromStr;
+
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use super::GreetingError;
+use super::locale::Locale;
+
+/// ユーザー定義の前置敬称の最大文字数
+pub const MAX_CUSTOM_PREFIX_CHARS: usize = 32;
+
+/// 挨拶に使う性別・肩書き（`--gender` の選択肢）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Gender {
+    /// 男性（Mr.）
+    #[value(alias = "mr")]
+    #[serde(alias = "mr")]
+    Man,
+    /// 女性（Ms.）
+    #[value(alias = "ms")]
+    #[serde(alias = "ms")]
+    Woman,
+    /// ジェンダーニュートラル（Mx.）
+    #[value(alias = "mx")]
+    #[serde(alias = "mx")]
+    Neutral,
+    /// 博士・医師（Dr.）
+    Dr,
+    /// 教授（Prof.）
+    Prof,
+}
+
+impl Gender {
+    /// CLI・設定ファイル・メトリクス属性で使う名前
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Man => "man",
+            Self::Woman => "woman",
+            Self::Neutral => "neutral",
+            Self::Dr => "dr",
+            Self::Prof => "prof",
+        }
+    }
+}
+
+impl FromStr for Gender {
+    type Err = GreetingError;
+
+    /// 大文字小文字を区別せずに名前またはエイリアス（`mr` など）を解析
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(s, true)
+            .map_err(|_| GreetingError::InvalidGender(String::from(s)))
+    }
+}
+
+/// 挨拶で名前に付ける敬称
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Honorific {
+    /// Mr.
+    Mr,
+    /// Ms.
+    Ms,
+    /// Mx.
+    Mx,
+    /// Dr.
+    Dr,
+    /// Prof.
+    Prof,
+    /// ユーザー定義の前置敬称（例: "Sir"）
+    Custom(String),
+}
+
+impl From<Gender> for Honorific {
+    fn from(gender: Gender) -> Self {
+        match gender {
+            Gender::Man => Self::Mr,
+            Gender::Woman => Self::Ms,
+            Gender::Neutral => Self::Mx,
+            Gender::Dr => Self::Dr,
+            Gender::Prof => Self::Prof,
+        }
+    }
+}
+
+impl Honorific {
+    /// `brust.gender` メトリクス属性の値
+    ///
+    /// 前置敬称の文字列は記録せず `"custom"` にまとめ、低カーディナリティを保ちます。
+    #[must_use]
+    pub const fn metric_value(&self) -> &'static str {
+        match self {
+            Self::Mr => Gender::Man.as_str(),
+            Self::Ms => Gender::Woman.as_str(),
+            Self::Mx => Gender::Neutral.as_str(),
+            Self::Dr => Gender::Dr.as_str(),
+            Self::Prof => Gender::Prof.as_str(),
+            Self::Custom(_) => "custom",
+        }
+    }
+
+    /// 名前を除いた敬称の文字列（例: `"Mr."`, `"さん"`, ユーザー定義の前置敬称）
+    #[must_use]
+    pub fn title(&self, locale: Locale) -> &str {
+        let catalog = locale.catalog();
+        match self {
+            Self::Mr => catalog.mr,
+            Self::Ms => catalog.ms,
+            Self::Mx => catalog.mx,
+            Self::Dr => catalog.dr,
+            Self::Prof => catalog.prof,
+            Self::Custom(prefix) => prefix,
+        }
+    }
+
+    /// 敬称付きの宛名（例: `"Mr. John"`, `"太郎さん"`）
+    #[must_use]
+    pub fn address(&self, locale: Locale, name: &str) -> String {
+        let catalog = locale.catalog();
+        let layout = match self {
+            Self::Custom(_) => catalog.custom,
+            _ => catalog.addressee,
+        };
+        super::locale::fill(layout, &[("title", self.title(locale)), ("name", name)])
+    }
+}
+
+/// ユーザー定義の前置敬称を検証
+///
+/// # Errors
+/// 空白のみ、制御文字を含む、または [`MAX_CUSTOM_PREFIX_CHARS`] 文字を超える場合に
+/// `GreetingError::InvalidHonorific` を返します。
+pub fn validate_prefix(prefix: &str) -> Result<(), GreetingError> {
+    let valid = !prefix.trim().is_empty()
+        && !prefix.chars().any(char::is_control)
+        && prefix.chars().count() <= MAX_CUSTOM_PREFIX_CHARS;
+    if valid {
+        Ok(())
+    } else {
+        Err(GreetingError::InvalidHonorific(String::from(prefix)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Gender, GreetingError, Honorific, Locale, validate_prefix};
+
+    #[test]
+    fn gender_parses_names_and_aliases_case_insensitively() {
+        assert_eq!("man".parse::<Gender>(), Ok(Gender::Man));
+        assert_eq!("Ms".parse::<Gender>(), Ok(Gender::Woman));
+        assert_eq!("mx".parse::<Gender>(), Ok(Gender::Neutral));
+        assert_eq!("PROF".parse::<Gender>(), Ok(Gender::Prof));
+    }
+
+    #[test]
+    fn gender_rejects_unknown_and_empty() {
+        assert_eq!(
+            "other".parse::<Gender>(),
+            Err(GreetingError::InvalidGender(String::from("other")))
+        );
+        assert_eq!(
+            "".parse::<Gender>(),
+            Err(GreetingError::InvalidGender(String::new()))
+        );
+    }
+
+    #[test]
+    fn metric_values_are_low_cardinality() {
+        assert_eq!(Honorific::from(Gender::Dr).metric_value(), "dr");
+        assert_eq!(
+            Honorific::Custom(String::from("Sir")).metric_value(),
+            "custom"
+        );
+    }
+
+    #[test]
+    fn title_and_address_follow_locale() {
+        assert_eq!(Honorific::Dr.title(Locale::En), "Dr.");
+        assert_eq!(Honorific::Dr.title(Locale::Ja), "先生");
+        assert_eq!(Honorific::Mx.address(Locale::Ja, "太郎"), "太郎さん");
+        let custom = Honorific::Custom(String::from("Dame"));
+        assert_eq!(custom.title(Locale::Ja), "Dame");
+        assert_eq!(custom.address(Locale::Ja, "Judi"), "Dame Judi");
+    }
+
+    #[test]
+    fn validate_prefix_rules() {
+        assert!(validate_prefix("Sir").is_ok());
+        assert!(validate_prefix("  ").is_err());
+        assert!(validate_prefix("Dame\n").is_err());
+        assert!(validate_prefix(&"x".repeat(33)).is_err());
+    }
+}
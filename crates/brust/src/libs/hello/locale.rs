@@ -0,0 +1,207 @@
+//! 挨拶のロケールとメッセージカタログ
+
+/// [`Locale::resolve`] が参照する環境変数（優先度の高い順）
+pub const LOCALE_ENV_VARS: [&str; 3] = ["LC_ALL", "LC_MESSAGES", "LANG"];
+
+/// 対応している挨拶のロケール
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Locale {
+    /// 英語（`C` と `POSIX` ロケールも含む）
+    #[default]
+    En,
+    /// 日本語
+    Ja,
+}
+
+/// 1 つのロケールのメッセージテンプレート
+///
+/// プレースホルダは `{key}` の形で書き、[`fill`] で埋めます。
+#[derive(Debug)]
+pub struct Catalog {
+    /// 挨拶全体（`{addressee}` は敬称を付けた宛名）
+    pub greeting: &'static str,
+    /// 組み込みの敬称 `{title}` を付けた宛名（`{name}` は敬称なしの名前）
+    pub addressee: &'static str,
+    /// ユーザー定義の前置敬称 `{title}` を付けた宛名
+    pub custom: &'static str,
+    /// `Mr.` に当たる敬称
+    pub mr: &'static str,
+    /// `Ms.` に当たる敬称
+    pub ms: &'static str,
+    /// ジェンダーニュートラルな `Mx.` に当たる敬称
+    pub mx: &'static str,
+    /// `Dr.` に当たる敬称
+    pub dr: &'static str,
+    /// `Prof.` に当たる敬称
+    pub prof: &'static str,
+    /// 朝・昼・夕方・夜を表す語（この順）
+    pub times_of_day: [&'static str; 4],
+    /// CLI が `{greeting}` を埋めて出力する行
+    pub announcement: &'static str,
+}
+
+const EN: Catalog = Catalog {
+    greeting: "Hi, {addressee}",
+    addressee: "{title} {name}",
+    custom: "{title} {name}",
+    mr: "Mr.",
+    ms: "Ms.",
+    mx: "Mx.",
+    dr: "Dr.",
+    prof: "Prof.",
+    times_of_day: ["morning", "afternoon", "evening", "night"],
+    announcement: "{greeting}, new world!!",
+};
+
+const JA: Catalog = Catalog {
+    greeting: "こんにちは、{addressee}",
+    addressee: "{name}{title}",
+    custom: "{title} {name}",
+    mr: "さん",
+    ms: "さん",
+    mx: "さん",
+    dr: "先生",
+    prof: "先生",
+    times_of_day: ["朝", "昼", "夕方", "夜"],
+    announcement: "{greeting}、新しい世界!!",
+};
+
+impl Locale {
+    /// メトリクス属性で使う言語コード（`"en"`, `"ja"`）
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::En => "en",
+            Self::Ja => "ja",
+        }
+    }
+
+    /// このロケールのメッセージカタログ
+    #[must_use]
+    pub const fn catalog(self) -> &'static Catalog {
+        match self {
+            Self::En => &EN,
+            Self::Ja => &JA,
+        }
+    }
+
+    /// BCP 47 または POSIX 形式のロケールタグを言語部分で解析
+    ///
+    /// `ja`、`ja-JP`、`ja_JP.UTF-8` はいずれも [`Locale::Ja`]、`C` と `POSIX` は
+    /// [`Locale::En`] になります。対応していない言語なら `None` を返します。
+    #[must_use]
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let language = tag
+            .split(['-', '_', '.', '@'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match language.as_str() {
+            "en" | "c" | "posix" => Some(Self::En),
+            "ja" => Some(Self::Ja),
+            _ => None,
+        }
+    }
+
+    /// `explicit`（`--locale` など）、続いて `env` で引いた `LC_ALL`、
+    /// `LC_MESSAGES`、`LANG` の順にロケールを選択
+    ///
+    /// 最初に対応している候補を使います。空の値や対応していない値は読み飛ばし、
+    /// どれも当てはまらなければ英語になります。
+    #[must_use]
+    pub fn resolve(explicit: Option<&str>, env: impl Fn(&str) -> Option<String>) -> Self {
+        explicit
+            .map(str::to_owned)
+            .into_iter()
+            .chain(LOCALE_ENV_VARS.iter().filter_map(|&var| env(var)))
+            .filter(|tag| !tag.is_empty())
+            .find_map(|tag| Self::from_tag(&tag))
+            .unwrap_or_default()
+    }
+}
+
+/// `template` 中の各 `{key}` を `values` の値で置換
+///
+/// 値はそのまま挿入し、値の中のプレースホルダは展開しません。
+#[must_use]
+pub fn fill(template: &str, values: &[(&str, &str)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let (before, tail) = rest.split_at(start);
+        out.push_str(before);
+        let value = tail.find('}').and_then(|end| {
+            let key = tail.get(1..end)?;
+            let (_, value) = values.iter().find(|&&(k, _)| k == key)?;
+            Some((*value, end))
+        });
+        if let Some((value, end)) = value {
+            out.push_str(value);
+            rest = tail
+                .get(end..)
+                .and_then(|t| t.strip_prefix('}'))
+                .unwrap_or_default();
+        } else {
+            out.push('{');
+            rest = tail.strip_prefix('{').unwrap_or_default();
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{Locale, fill};
+
+    fn env_of(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|&(k, v)| (k.to_owned(), v.to_owned()))
+            .collect();
+        move |var| vars.get(var).cloned()
+    }
+
+    #[test]
+    fn from_tag_accepts_bcp47_and_posix_forms() {
+        assert_eq!(Locale::from_tag("ja"), Some(Locale::Ja));
+        assert_eq!(Locale::from_tag("ja-JP"), Some(Locale::Ja));
+        assert_eq!(Locale::from_tag("ja_JP.UTF-8"), Some(Locale::Ja));
+        assert_eq!(Locale::from_tag("EN_us"), Some(Locale::En));
+        assert_eq!(Locale::from_tag("C.UTF-8"), Some(Locale::En));
+        assert_eq!(Locale::from_tag("fr_FR"), None);
+    }
+
+    #[test]
+    fn resolve_prefers_explicit_over_env() {
+        let env = env_of(&[("LANG", "ja_JP.UTF-8")]);
+        assert_eq!(Locale::resolve(Some("en"), env), Locale::En);
+    }
+
+    #[test]
+    fn resolve_follows_posix_precedence() {
+        let env = env_of(&[("LC_ALL", "ja_JP.UTF-8"), ("LANG", "en_US.UTF-8")]);
+        assert_eq!(Locale::resolve(None, env), Locale::Ja);
+        let env = env_of(&[("LC_MESSAGES", "ja_JP.UTF-8"), ("LANG", "en_US.UTF-8")]);
+        assert_eq!(Locale::resolve(None, env), Locale::Ja);
+    }
+
+    #[test]
+    fn resolve_falls_through_unsupported_and_empty() {
+        let env = env_of(&[("LC_ALL", ""), ("LC_MESSAGES", "fr_FR"), ("LANG", "ja_JP")]);
+        assert_eq!(Locale::resolve(Some("de"), env), Locale::Ja);
+        assert_eq!(Locale::resolve(None, env_of(&[])), Locale::En);
+    }
+
+    #[test]
+    fn fill_replaces_known_keys_only() {
+        assert_eq!(
+            fill("{a}-{b}-{c}", &[("a", "1"), ("b", "{a}")]),
+            "1-{a}-{c}"
+        );
+        assert_eq!(fill("no placeholders", &[]), "no placeholders");
+        assert_eq!(fill("dangling {", &[]), "dangling {");
+    }
+}
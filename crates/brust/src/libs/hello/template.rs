@@ -0,0 +1,278 @@
+//! ユーザー定義の挨拶テンプレート
+//!
+//! `"Good {time_of_day}, {title} {name}!"` のような名前付きプレースホルダを持つ
+//! テンプレートを解析・展開します。`{{` と `}}` はそれぞれ `{` と `}` を表します。
+
+use jiff::Timestamp;
+use jiff::tz::TimeZone;
+
+use super::GreetingError;
+use super::locale::Locale;
+
+/// テンプレートで使えるプレースホルダ名
+pub const PLACEHOLDERS: [&str; 5] = ["name", "title", "addressee", "locale", "time_of_day"];
+
+/// 時間帯（`{time_of_day}` の値）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TimeOfDay {
+    /// 5 時から 11 時
+    Morning,
+    /// 12 時から 16 時
+    Afternoon,
+    /// 17 時から 20 時
+    Evening,
+    /// 21 時から 4 時
+    Night,
+}
+
+impl TimeOfDay {
+    /// 0〜23 時の時刻から時間帯を判定
+    #[must_use]
+    pub const fn from_hour(hour: u8) -> Self {
+        match hour {
+            5..=11 => Self::Morning,
+            12..=16 => Self::Afternoon,
+            17..=20 => Self::Evening,
+            _ => Self::Night,
+        }
+    }
+
+    /// 現在の時間帯
+    ///
+    /// システムのタイムゾーン（`TZ` 環境変数、なければ `/etc/localtime`）の時刻で
+    /// 判定します。タイムゾーンが分からない場合は UTC になります。
+    #[must_use]
+    pub fn now() -> Self {
+        Self::at(Timestamp::now(), &TimeZone::system())
+    }
+
+    /// 指定した時刻をタイムゾーン `zone` で見たときの時間帯
+    #[must_use]
+    pub fn at(time: Timestamp, zone: &TimeZone) -> Self {
+        let hour = time.to_zoned(zone.clone()).hour();
+        Self::from_hour(u8::try_from(hour).unwrap_or_default())
+    }
+
+    /// ロケールのメッセージカタログでの表記（例: `"morning"`, `"朝"`）
+    #[must_use]
+    pub const fn word(self, locale: Locale) -> &'static str {
+        let [morning, afternoon, evening, night] = locale.catalog().times_of_day;
+        match self {
+            Self::Morning => morning,
+            Self::Afternoon => afternoon,
+            Self::Evening => evening,
+            Self::Night => night,
+        }
+    }
+}
+
+/// テンプレート展開に使う値
+#[derive(Debug, Clone, Copy)]
+pub struct Context<'a> {
+    /// 敬称なしの名前
+    pub name: &'a str,
+    /// 名前を除いた敬称（敬称なしなら空文字列）
+    pub title: &'a str,
+    /// ロケールに従って敬称を付けた宛名
+    pub addressee: &'a str,
+    /// 挨拶のロケール
+    pub locale: Locale,
+    /// 時間帯
+    pub time_of_day: TimeOfDay,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Placeholder {
+    Name,
+    Title,
+    Addressee,
+    Locale,
+    TimeOfDay,
+}
+
+impl Placeholder {
+    fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "name" => Some(Self::Name),
+            "title" => Some(Self::Title),
+            "addressee" => Some(Self::Addressee),
+            "locale" => Some(Self::Locale),
+            "time_of_day" => Some(Self::TimeOfDay),
+            _ => None,
+        }
+    }
+
+    const fn value<'a>(self, context: &Context<'a>) -> &'a str {
+        match self {
+            Self::Name => context.name,
+            Self::Title => context.title,
+            Self::Addressee => context.addressee,
+            Self::Locale => context.locale.as_str(),
+            Self::TimeOfDay => context.time_of_day.word(context.locale),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+/// 解析済みの挨拶テンプレート
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+impl Template {
+    /// テンプレート文字列を解析
+    ///
+    /// # Errors
+    /// 空のテンプレート、[`PLACEHOLDERS`] にないプレースホルダ、閉じていない `{`、
+    /// 対応しない `}` に対して `GreetingError::InvalidTemplate` を返します。
+    pub fn parse(text: &str) -> Result<Self, GreetingError> {
+        let invalid = |reason: String| GreetingError::InvalidTemplate(reason);
+        if text.trim().is_empty() {
+            return Err(invalid(String::from("template is empty")));
+        }
+
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut rest = text;
+        while let Some(start) = rest.find(['{', '}']) {
+            let (before, tail) = rest.split_at(start);
+            literal.push_str(before);
+            if let Some(after) = tail.strip_prefix("{{") {
+                literal.push('{');
+                rest = after;
+            } else if let Some(after) = tail.strip_prefix("}}") {
+                literal.push('}');
+                rest = after;
+            } else if let Some(after) = tail.strip_prefix('{') {
+                let (key, after) = after
+                    .split_once('}')
+                    .ok_or_else(|| invalid(String::from("unclosed `{`")))?;
+                let placeholder = Placeholder::from_key(key).ok_or_else(|| {
+                    invalid(format!(
+                        "unknown placeholder `{{{key}}}` (expected one of: {})",
+                        PLACEHOLDERS.join(", ")
+                    ))
+                })?;
+                if !literal.is_empty() {
+                    parts.push(Part::Literal(std::mem::take(&mut literal)));
+                }
+                parts.push(Part::Placeholder(placeholder));
+                rest = after;
+            } else {
+                return Err(invalid(String::from("unmatched `}`")));
+            }
+        }
+        literal.push_str(rest);
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+
+        Ok(Self { parts })
+    }
+
+    /// プレースホルダを `context` の値で展開
+    ///
+    /// 値が空のプレースホルダ（敬称なしの `{title}` など）の直後の空白は 1 つ
+    /// 詰めるため、`"{title} {name}"` は敬称なしでも `"Bob"` になります。
+    #[must_use]
+    pub fn render(&self, context: &Context<'_>) -> String {
+        let mut out = String::new();
+        let mut skip_space = false;
+        for part in &self.parts {
+            match part {
+                Part::Literal(text) => {
+                    let text = if skip_space && out.chars().last().is_none_or(char::is_whitespace) {
+                        text.strip_prefix(' ').unwrap_or(text)
+                    } else {
+                        text
+                    };
+                    out.push_str(text);
+                    skip_space = false;
+                }
+                Part::Placeholder(placeholder) => {
+                    let value = placeholder.value(context);
+                    out.push_str(value);
+                    skip_space = value.is_empty();
+                }
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use jiff::Timestamp;
+    use jiff::tz::{self, TimeZone};
+
+    use super::{Context, GreetingError, Locale, Template, TimeOfDay};
+
+    fn context(title: &str) -> Context<'_> {
+        Context {
+            name: "Bob",
+            title,
+            addressee: "Mr. Bob",
+            locale: Locale::En,
+            time_of_day: TimeOfDay::Afternoon,
+        }
+    }
+
+    #[test]
+    fn renders_every_placeholder_and_escapes() {
+        let template =
+            Template::parse("{{{name}}} {title} {addressee} {locale} {time_of_day}").unwrap();
+        assert_eq!(
+            template.render(&context("Mr.")),
+            "{Bob} Mr. Mr. Bob en afternoon"
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_placeholder() {
+        let error = Template::parse("Hello {nmae}").unwrap_err();
+        assert!(
+            matches!(&error, GreetingError::InvalidTemplate(reason) if reason.contains("`{nmae}`")),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn rejects_unbalanced_braces_and_empty() {
+        assert!(Template::parse("Hello {name").is_err());
+        assert!(Template::parse("Hello name}").is_err());
+        assert!(Template::parse("  ").is_err());
+    }
+
+    #[test]
+    fn empty_title_collapses_following_space_only() {
+        let template = Template::parse("Hi {title} {name}, {title}!").unwrap();
+        assert_eq!(template.render(&context("")), "Hi Bob, !");
+    }
+
+    #[test]
+    fn time_of_day_boundaries() {
+        assert_eq!(TimeOfDay::from_hour(4), TimeOfDay::Night);
+        assert_eq!(TimeOfDay::from_hour(5), TimeOfDay::Morning);
+        assert_eq!(TimeOfDay::from_hour(12), TimeOfDay::Afternoon);
+        assert_eq!(TimeOfDay::from_hour(17), TimeOfDay::Evening);
+        assert_eq!(TimeOfDay::from_hour(21), TimeOfDay::Night);
+        assert_eq!(TimeOfDay::Morning.word(Locale::Ja), "朝");
+    }
+
+    #[test]
+    fn time_of_day_uses_the_given_zone() {
+        // 10:00 UTC は日本時間では 19:00
+        let time: Timestamp = "2026-01-15T10:00:00Z".parse().unwrap();
+        assert_eq!(TimeOfDay::at(time, &TimeZone::UTC), TimeOfDay::Morning);
+        assert_eq!(
+            TimeOfDay::at(time, &TimeZone::fixed(tz::offset(9))),
+            TimeOfDay::Evening
+        );
+    }
+}
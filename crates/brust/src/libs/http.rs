@@ -3,76 +3,225 @@
 //! Demonstrates OTel HTTP client semantic conventions:
 //! `http.client.request.duration` with `http.request.method`,
 //! `http.response.status_code`, `server.address`, and `url.scheme` attributes.
+//! A [`Request`] carries the method, headers and body to send. With the
+//! `otel` feature, the current span's context is injected into every request
+//! through the global propagator (W3C `traceparent` once the tracer is set
+//! up), so servers can continue the trace. Failed requests are sent again as
+//! the [`retry::Policy`] in [`ClientOptions`] allows, and are recorded with
+//! the `error.type` of [`FetchError::error_type`] as well. A [`Client`]
+//! keeps its connections open between requests; the free functions build a
+//! new one for every call. Response bodies are streamed in chunks, either
+//! discarded or written out by [`Client::fetch_to`], and their sizes
+//! recorded in `http.client.response.body.size`.
+//! [`run_fetch`](crate::libs::http::run_fetch) and the functions around it
+//! fetch a whole list of URLs as `brust fetch` does.
 
-use std::time::Instant;
+pub mod retry;
 
-use anyhow::Context as _;
+mod attempt;
+mod client;
+mod error;
+mod propagation;
+mod request;
+mod run;
 
+use std::time::Duration;
+
+pub use self::client::Client;
+pub use self::error::FetchError;
+pub use self::request::{Header, HeaderError, Request, read_body, read_url_file};
+pub use self::run::{
+    Failures, Save, SetupError, build_requests, collect_urls, fetch_outcome, run_fetch,
+};
+use crate::libs::cancellation::CancelToken;
 use crate::telemetry::metrics::Meters;
 
+/// Default limit on establishing a connection.
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Default limit on a whole attempt, from connecting to reading the body.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Settings applied when building the HTTP client.
+#[derive(Debug, Clone)]
+pub struct ClientOptions {
+    /// `User-Agent` header value; none is sent when unset.
+    pub user_agent: Option<String>,
+    /// Limit on establishing a connection, TLS handshake included.
+    pub connect_timeout: Option<Duration>,
+    /// Limit on each read from the connection; none by default.
+    pub read_timeout: Option<Duration>,
+    /// Limit on a whole attempt, from connecting to reading the body.
+    pub timeout: Option<Duration>,
+    /// When failed requests are sent again; never by default.
+    pub retry: retry::Policy,
+    /// Limit on the size of a response body in bytes; none by default.
+    pub max_body_size: Option<u64>,
+}
+
+impl Default for ClientOptions {
+    fn default() -> Self {
+        Self {
+            user_agent: None,
+            connect_timeout: Some(DEFAULT_CONNECT_TIMEOUT),
+            read_timeout: None,
+            timeout: Some(DEFAULT_TIMEOUT),
+            retry: retry::Policy::default(),
+            max_body_size: None,
+        }
+    }
+}
+
+/// Outcome of a completed HTTP request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FetchResult {
+    /// HTTP response status code.
+    pub status: u16,
+    /// Round-trip time in seconds of the last attempt, including the
+    /// response body download.
+    pub duration_s: f64,
+    /// `server.address`: host from the URL.
+    pub host: String,
+    /// `url.scheme`: URL scheme.
+    pub scheme: String,
+    /// Requests sent, including retries.
+    pub attempts: u32,
+    /// Whether the last attempt went over a connection kept open by an
+    /// earlier request.
+    pub reused_connection: bool,
+    /// Size in bytes of the response body of the last attempt.
+    pub body_size: u64,
+}
+
+/// Outcome of each request of [`Client::fetch_all`] that was sent.
+pub type Fetched = Vec<(Request, Result<FetchResult, FetchError>)>;
+
 /// Perform an HTTP GET request to `url` and record `OTel` client metrics.
 ///
-/// Records `http.client.request.duration` with `OTel` HTTP semantic convention
-/// attributes. The duration covers the full round-trip including response body
-/// download.
+/// Shorthand for [`fetch`] with [`Request::get`].
 ///
 /// # Errors
 ///
-/// Returns an error if the URL is invalid, the TCP connection fails, the server
-/// returns a network-level error, or the response body cannot be read.
-#[cfg_attr(
-    feature = "otel",
-    tracing::instrument(
-        skip(meters),
-        fields(otel.kind = ?opentelemetry::trace::SpanKind::Client)
-    )
-)]
-pub fn fetch_url(url: &str, meters: &Meters) -> anyhow::Result<()> {
-    let parsed = reqwest::Url::parse(url).context("invalid URL")?;
-    let host = parsed.host_str().unwrap_or("unknown").to_owned();
-    let scheme = parsed.scheme().to_owned();
-
-    let client = reqwest::blocking::Client::new();
-
-    let start = Instant::now();
-    let response = client.get(url).send().context("HTTP request failed")?;
-    let status = response.status().as_u16();
-    let _ = response.bytes().context("failed to read response body")?;
-    let duration_s = start.elapsed().as_secs_f64();
-
-    meters.record_http_request(duration_s, "GET", status, &host, &scheme);
-
-    tracing::info!(
-        http.request.method = "GET",
-        http.response.status_code = status,
-        server.address = %host,
-        url.scheme = %scheme,
-        duration_s,
-        "HTTP GET completed",
-    );
-
-    Ok(())
+/// Returns the same errors as [`fetch`].
+pub fn fetch_url(
+    url: &str,
+    options: &ClientOptions,
+    meters: &Meters,
+) -> Result<FetchResult, FetchError> {
+    fetch(&Request::get(url), options, meters)
+}
+
+/// [`fetch_url`], giving up as soon as `cancel` fires.
+///
+/// # Errors
+///
+/// Returns the same errors as [`fetch_cancellable`].
+pub fn fetch_url_cancellable(
+    url: &str,
+    options: &ClientOptions,
+    meters: &Meters,
+    cancel: &CancelToken,
+) -> Result<FetchResult, FetchError> {
+    fetch_cancellable(&Request::get(url), options, meters, cancel)
+}
+
+/// [`Client::fetch`] with a client built from `options` for this request
+/// alone.
+///
+/// # Errors
+///
+/// Returns [`FetchError::Client`] if the client cannot be built, and
+/// otherwise the same errors as [`Client::fetch`].
+pub fn fetch(
+    request: &Request,
+    options: &ClientOptions,
+    meters: &Meters,
+) -> Result<FetchResult, FetchError> {
+    Client::new(options.clone())?.fetch(request, meters)
+}
+
+/// [`Client::fetch_cancellable`] with a client built from `options` for
+/// this request alone.
+///
+/// # Errors
+///
+/// Returns [`FetchError::Client`] if the client cannot be built, and
+/// otherwise the same errors as [`Client::fetch_cancellable`].
+pub fn fetch_cancellable(
+    request: &Request,
+    options: &ClientOptions,
+    meters: &Meters,
+    cancel: &CancelToken,
+) -> Result<FetchResult, FetchError> {
+    Client::new(options.clone())?.fetch_cancellable(request, meters, cancel)
 }
 
 #[cfg(test)]
 mod tests {
+    use std::thread;
+    use std::time::Instant;
+
     use super::*;
-    use crate::telemetry::metrics::Meters;
 
     #[test]
     fn fetch_url_rejects_invalid_url() {
         let meters = Meters::default();
-        let result = fetch_url("not-a-url", &meters);
-        assert!(result.is_err(), "expected error for invalid URL");
+        let result = fetch_url("not-a-url", &ClientOptions::default(), &meters);
+        assert!(
+            matches!(result, Err(FetchError::InvalidUrl(_))),
+            "expected error for invalid URL"
+        );
     }
 
     #[test]
     fn fetch_url_rejects_empty_url() {
         let meters = Meters::default();
-        let result = fetch_url("", &meters);
+        let result = fetch_url("", &ClientOptions::default(), &meters);
         assert!(result.is_err(), "expected error for empty URL");
     }
 
+    #[test]
+    fn cancellable_fetch_checks_url_then_token() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        let options = ClientOptions::default();
+        let meters = Meters::noop();
+        assert!(matches!(
+            fetch_url_cancellable("not-a-url", &options, &meters, &cancel),
+            Err(FetchError::InvalidUrl(_))
+        ));
+        let result = fetch_url_cancellable("http://127.0.0.1:1/", &options, &meters, &cancel);
+        assert!(matches!(result, Err(FetchError::Cancelled)));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)] // reqwest blocking -> setsockopt SO_KEEPALIVE unsupported under Miri
+    fn cancel_abandons_a_hanging_fetch() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+
+        // Connections are queued by the OS but never answered.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind failed");
+        let url = format!(
+            "http://{}/",
+            listener.local_addr().expect("local_addr failed")
+        );
+        let cancel = CancelToken::new();
+        let start = Instant::now();
+        let result = thread::scope(|scope| {
+            let fetch = scope.spawn(|| {
+                fetch_url_cancellable(&url, &ClientOptions::default(), &Meters::noop(), &cancel)
+            });
+            thread::sleep(Duration::from_millis(100));
+            cancel.cancel();
+            fetch.join().expect("fetch panicked")
+        });
+        assert!(matches!(result, Err(FetchError::Cancelled)));
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(FetchError::Cancelled.to_string(), "HTTP request cancelled");
+    }
+
     #[tokio::test]
     #[cfg_attr(miri, ignore)] // reqwest blocking -> setsockopt SO_KEEPALIVE unsupported under Miri
     async fn fetch_url_success_records_metrics() {
@@ -92,29 +241,55 @@ mod tests {
 
         let url = format!("http://127.0.0.1:{}/", addr.port());
         let meters = Meters::default();
-        let result = tokio::task::spawn_blocking(move || fetch_url(&url, &meters))
-            .await
-            .expect("spawn_blocking panicked");
-        assert!(result.is_ok(), "expected Ok for successful GET: {result:?}");
+        let result = tokio::task::spawn_blocking(move || {
+            fetch_url(&url, &ClientOptions::default(), &meters)
+        })
+        .await
+        .expect("spawn_blocking panicked");
+        let result = result.expect("expected Ok for successful GET");
+        assert_eq!(result.status, 200);
+        assert_eq!(result.host, "127.0.0.1");
+        assert_eq!(result.scheme, "http");
     }
 
     #[tokio::test]
     #[cfg_attr(miri, ignore)] // reqwest blocking -> setsockopt SO_KEEPALIVE unsupported under Miri
-    async fn fetch_url_connection_refused_returns_error() {
+    async fn fetch_url_sends_configured_user_agent() {
+        use axum::{Router, http::HeaderMap, routing::get};
+        use std::sync::{Arc, Mutex};
+
         let _ = rustls::crypto::ring::default_provider().install_default();
 
-        // Bind and immediately drop to get a port that is not listening.
+        let seen = Arc::new(Mutex::new(None));
+        let seen_in_handler = Arc::clone(&seen);
+        let app = Router::new().route(
+            "/",
+            get(move |headers: HeaderMap| async move {
+                *seen_in_handler.lock().unwrap() = headers
+                    .get("user-agent")
+                    .and_then(|v| v.to_str().ok())
+                    .map(ToOwned::to_owned);
+                "ok"
+            }),
+        );
         let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
             .await
             .expect("bind failed");
-        let port = listener.local_addr().expect("local_addr").port();
-        drop(listener);
+        let port = listener.local_addr().expect("local_addr failed").port();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.expect("server error"); // NOTEST(unreachable): test server panic path; unreachable in passing tests
+        });
 
         let url = format!("http://127.0.0.1:{port}/");
+        let options = ClientOptions {
+            user_agent: Some(String::from("brust-test/1.0")),
+            ..ClientOptions::default()
+        };
         let meters = Meters::default();
-        let result = tokio::task::spawn_blocking(move || fetch_url(&url, &meters))
+        let result = tokio::task::spawn_blocking(move || fetch_url(&url, &options, &meters))
             .await
             .expect("spawn_blocking panicked");
-        assert!(result.is_err(), "expected error for connection refused");
+        assert!(result.is_ok(), "expected Ok: {result:?}");
+        assert_eq!(seen.lock().unwrap().as_deref(), Some("brust-test/1.0"));
     }
 }
@@ -0,0 +1,407 @@
+//! Attempts at one request: retries, the client span of each attempt, and
+//! the metrics and log line it is recorded with.
+
+use std::cell::Cell;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use reqwest::Method;
+use reqwest::header::HeaderMap;
+use tracing::field::Empty;
+
+use super::propagation::trace_headers;
+use super::{ClientOptions, FetchError, FetchResult, Request};
+use crate::libs::cancellation::CancelToken;
+use crate::telemetry::conventions::attribute::HTTP_CONNECTION_REUSED;
+use crate::telemetry::metrics::{HttpClientAttributes, Meters};
+
+/// Run attempts with `attempt` until one succeeds, fails for good or runs
+/// out of retries under `options.retry`, waiting in between unless `cancel`
+/// fires.
+///
+/// `attempt` is called inside the attempt's span with its number (1 for the
+/// first) and trace context headers. Failures are not retried once
+/// `written` is set, that is once part of a body was saved.
+pub(super) fn fetch_with_retries(
+    request: &Request,
+    options: &ClientOptions,
+    meters: &Meters,
+    cancel: &CancelToken,
+    written: &Cell<bool>,
+    mut attempt: impl FnMut(u32, &HeaderMap) -> Result<Sent, FetchError>,
+) -> Result<FetchResult, FetchError> {
+    let (host, scheme) = url_parts(request.url())?;
+    cancel.check().map_err(|_| FetchError::Cancelled)?;
+
+    let method = request.method();
+    let policy = &options.retry;
+    let mut rng = rand::rng();
+    let mut attempts = 0_u32;
+    loop {
+        let span = attempt_span(request, &host, &scheme, attempts);
+        attempts = attempts.saturating_add(1);
+        let start = Instant::now();
+        let result = span.in_scope(|| attempt(attempts, &trace_headers()));
+        let attributes = |status, reused_connection| HttpClientAttributes {
+            method: method.as_str(),
+            host: &host,
+            scheme: &scheme,
+            status,
+            reused_connection,
+        };
+        let (retryable, retry_after) = match result {
+            Ok(ref sent) => {
+                span.record("http.response.status_code", i64::from(sent.status));
+                span.record(HTTP_CONNECTION_REUSED, sent.reused_connection);
+                span.record("http.response.body.size", sent.body_size);
+                let error_type = (sent.status >= 400).then(|| sent.status.to_string());
+                if let Some(ref error_type) = error_type {
+                    mark_failed(&span, error_type, &sent.status);
+                }
+                let attributes = attributes(Some(sent.status), Some(sent.reused_connection));
+                meters.record_http_request(sent.duration_s, &attributes, error_type.as_deref());
+                meters.record_http_response_body_size(sent.body_size, &attributes);
+                (policy.retries_status(sent.status), sent.retry_after)
+            }
+            Err(ref e) => {
+                mark_failed(&span, e.error_type(), e);
+                if e.was_sent() {
+                    meters.record_http_request(
+                        start.elapsed().as_secs_f64(),
+                        &attributes(None, None),
+                        Some(e.error_type()),
+                    );
+                }
+                (policy.retries_error(e) && !written.get(), None)
+            }
+        };
+        drop(span);
+
+        let wait = (retryable && policy.retries_method(method))
+            .then(|| policy.wait(attempts, retry_after, &mut rng))
+            .flatten();
+        let Some(wait) = wait else {
+            return result.map(|sent| completed(method, &sent, attempts, host, scheme));
+        };
+        let reason = match result {
+            Ok(ref sent) => sent.status.to_string(),
+            Err(ref e) => e.to_string(),
+        };
+        tracing::warn!(
+            url = request.url(),
+            attempt = attempts,
+            "HTTP {method} failed ({reason}); retrying in {:.3} s",
+            wait.as_secs_f64(),
+        );
+        if cancel.wait_timeout(wait).is_err() {
+            tracing::warn!(url = request.url(), "HTTP {method} cancelled");
+            return Err(FetchError::Cancelled);
+        }
+    }
+}
+
+/// Client span for one attempt at `request`; `resends` attempts came before.
+///
+/// Named after the method as HTTP semantic conventions ask, with
+/// `http.request.resend_count` set on every attempt but the first.
+/// `brust.http.connection.reused` is set once a response arrives, and
+/// `http.response.body.size` once its body is read.
+fn attempt_span(request: &Request, host: &str, scheme: &str, resends: u32) -> tracing::Span {
+    let span = tracing::info_span!(
+        "http_request",
+        otel.name = %request.method(),
+        otel.kind = "client",
+        http.request.method = %request.method(),
+        url.full = request.url(),
+        server.address = host,
+        url.scheme = scheme,
+        http.request.resend_count = Empty,
+        http.response.status_code = Empty,
+        { HTTP_CONNECTION_REUSED } = Empty,
+        http.response.body.size = Empty,
+        error.r#type = Empty,
+        otel.status_code = Empty,
+        otel.status_message = Empty,
+    );
+    if resends > 0 {
+        // As `i64`: `OTel` has no unsigned attributes.
+        span.record("http.request.resend_count", i64::from(resends));
+    }
+    span
+}
+
+/// Mark `span` as failed with `error_type` and the `error` message.
+fn mark_failed(span: &tracing::Span, error_type: &str, error: &dyn fmt::Display) {
+    span.record("otel.status_code", "ERROR");
+    span.record("otel.status_message", error.to_string());
+    span.record("error.type", error_type);
+}
+
+/// `server.address` and `url.scheme` of `url`.
+fn url_parts(url: &str) -> Result<(String, String), FetchError> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| FetchError::InvalidUrl(e.to_string()))?;
+    Ok((
+        parsed.host_str().unwrap_or("unknown").to_owned(),
+        parsed.scheme().to_owned(),
+    ))
+}
+
+/// Response to one attempt.
+#[derive(Debug)]
+pub(super) struct Sent {
+    /// HTTP response status code.
+    pub(super) status: u16,
+    /// Round-trip time in seconds.
+    pub(super) duration_s: f64,
+    /// Wait asked for by a `Retry-After` header.
+    pub(super) retry_after: Option<Duration>,
+    /// Whether no new connection was opened for the request.
+    pub(super) reused_connection: bool,
+    /// Size of the response body in bytes.
+    pub(super) body_size: u64,
+}
+
+/// Log a completed request.
+fn completed(
+    method: &Method,
+    sent: &Sent,
+    attempts: u32,
+    host: String,
+    scheme: String,
+) -> FetchResult {
+    tracing::info!(
+        http.request.method = %method,
+        http.response.status_code = sent.status,
+        server.address = %host,
+        url.scheme = %scheme,
+        duration_s = sent.duration_s,
+        attempts,
+        { HTTP_CONNECTION_REUSED } = sent.reused_connection,
+        http.response.body.size = sent.body_size,
+        "HTTP {method} completed",
+    );
+
+    FetchResult {
+        status: sent.status,
+        duration_s: sent.duration_s,
+        host,
+        scheme,
+        attempts,
+        reused_connection: sent.reused_connection,
+        body_size: sent.body_size,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use reqwest::Method;
+    use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+
+    use crate::libs::cancellation::CancelToken;
+    use crate::libs::http::{
+        Client, ClientOptions, FetchError, Request, fetch, fetch_url, fetch_url_cancellable, retry,
+    };
+    use crate::telemetry::metrics::Meters;
+
+    /// Serve `/` on a random port, answering the first `failures` requests
+    /// with `503` and `Retry-After: retry_after` if given, then `200`.
+    /// Returns the port and the number of requests seen so far.
+    async fn start_flaky_server(
+        failures: u32,
+        retry_after: Option<&'static str>,
+    ) -> (u16, std::sync::Arc<std::sync::atomic::AtomicU32>) {
+        use axum::{Router, http::StatusCode, response::IntoResponse as _, routing::any};
+        use std::sync::{Arc, atomic::AtomicU32, atomic::Ordering};
+
+        let _ = rustls::crypto::ring::default_provider().install_default();
+
+        let seen = Arc::new(AtomicU32::new(0));
+        let seen_in_handler = Arc::clone(&seen);
+        let app = Router::new().route(
+            "/",
+            any(move || async move {
+                if seen_in_handler.fetch_add(1, Ordering::SeqCst) < failures {
+                    let mut headers = HeaderMap::new();
+                    if let Some(value) = retry_after {
+                        headers.insert(RETRY_AFTER, HeaderValue::from_static(value));
+                    }
+                    (StatusCode::SERVICE_UNAVAILABLE, headers, "busy").into_response()
+                } else {
+                    "ok".into_response()
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind failed");
+        let port = listener.local_addr().expect("local_addr failed").port();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.expect("server error"); // NOTEST(unreachable): test server panic path; unreachable in passing tests
+        });
+        (port, seen)
+    }
+
+    /// Up to `max_attempts` attempts, 10 ms apart at most, without jitter.
+    fn retrying(max_attempts: u32) -> ClientOptions {
+        ClientOptions {
+            retry: retry::Policy {
+                max_attempts: std::num::NonZeroU32::new(max_attempts).expect("non-zero"),
+                base_delay: Duration::from_millis(10),
+                max_delay: Duration::from_millis(10),
+                jitter: retry::Jitter::NONE,
+                ..retry::Policy::default()
+            },
+            ..ClientOptions::default()
+        }
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)] // reqwest blocking -> setsockopt SO_KEEPALIVE unsupported under Miri
+    async fn fetch_retries_a_flaky_server() {
+        use std::sync::atomic::Ordering;
+
+        let (port, seen) = start_flaky_server(2, None).await;
+        let url = format!("http://127.0.0.1:{port}/");
+        let result = tokio::task::spawn_blocking(move || {
+            let once = fetch_url(&url, &ClientOptions::default(), &Meters::noop());
+            let retried =
+                fetch_url_cancellable(&url, &retrying(3), &Meters::noop(), &CancelToken::new());
+            (once, retried)
+        })
+        .await
+        .expect("spawn_blocking panicked");
+        let once = result.0.expect("a 503 is a response");
+        assert_eq!((once.status, once.attempts), (503, 1));
+        let retried = result.1.expect("expected Ok after retries");
+        assert_eq!((retried.status, retried.attempts), (200, 2));
+        assert_eq!(seen.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)] // reqwest blocking -> setsockopt SO_KEEPALIVE unsupported under Miri
+    async fn fetch_resends_a_post_only_when_told_to() {
+        use std::sync::atomic::Ordering;
+
+        let post_to = |port: u16| Request::new(Method::POST, format!("http://127.0.0.1:{port}/"));
+        let (port, seen) = start_flaky_server(1, None).await;
+        let request = post_to(port);
+        let once =
+            tokio::task::spawn_blocking(move || fetch(&request, &retrying(3), &Meters::noop()))
+                .await
+                .expect("spawn_blocking panicked")
+                .expect("a 503 is a response");
+        assert_eq!((once.status, once.attempts), (503, 1));
+        assert_eq!(seen.load(Ordering::SeqCst), 1);
+
+        let (port, seen) = start_flaky_server(1, None).await;
+        let request = post_to(port);
+        let retried = tokio::task::spawn_blocking(move || {
+            let mut options = retrying(3);
+            options.retry.non_idempotent = true;
+            fetch(&request, &options, &Meters::noop())
+        })
+        .await
+        .expect("spawn_blocking panicked")
+        .expect("expected Ok after the retry");
+        assert_eq!((retried.status, retried.attempts), (200, 2));
+        assert_eq!(seen.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)] // reqwest blocking -> setsockopt SO_KEEPALIVE unsupported under Miri
+    async fn fetch_gives_up_after_the_last_attempt() {
+        let (port, seen) = start_flaky_server(u32::MAX, None).await;
+        let url = format!("http://127.0.0.1:{port}/");
+        let result =
+            tokio::task::spawn_blocking(move || fetch_url(&url, &retrying(3), &Meters::noop()))
+                .await
+                .expect("spawn_blocking panicked")
+                .expect("a 503 is a response");
+        assert_eq!((result.status, result.attempts), (503, 3));
+        assert_eq!(seen.load(std::sync::atomic::Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)] // reqwest blocking -> setsockopt SO_KEEPALIVE unsupported under Miri
+    async fn fetch_honours_retry_after_up_to_the_max_delay() {
+        // Asking for longer than the max delay returns the response.
+        let (port, _) = start_flaky_server(1, Some("1")).await;
+        let url = format!("http://127.0.0.1:{port}/");
+        let result =
+            tokio::task::spawn_blocking(move || fetch_url(&url, &retrying(3), &Meters::noop()))
+                .await
+                .expect("spawn_blocking panicked")
+                .expect("a 503 is a response");
+        assert_eq!((result.status, result.attempts), (503, 1));
+
+        // Otherwise the retry waits at least as long as asked.
+        let (port, _) = start_flaky_server(1, Some("1")).await;
+        let url = format!("http://127.0.0.1:{port}/");
+        let (result, elapsed) = tokio::task::spawn_blocking(move || {
+            let mut options = retrying(3);
+            options.retry.max_delay = Duration::from_secs(2);
+            let start = Instant::now();
+            (fetch_url(&url, &options, &Meters::noop()), start.elapsed())
+        })
+        .await
+        .expect("spawn_blocking panicked");
+        let result = result.expect("expected Ok after the retry");
+        assert_eq!((result.status, result.attempts), (200, 2));
+        assert!(elapsed >= Duration::from_secs(1), "{elapsed:?}");
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)] // reqwest blocking -> setsockopt SO_KEEPALIVE unsupported under Miri
+    fn cancel_stops_waiting_for_a_retry() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind failed");
+        let url = format!(
+            "http://{}/",
+            listener.local_addr().expect("local_addr failed")
+        );
+        drop(listener);
+        let mut options = retrying(2);
+        options.retry.base_delay = Duration::from_mins(1);
+        options.retry.max_delay = Duration::from_mins(1);
+        let cancel = CancelToken::new();
+        let start = Instant::now();
+        let result = thread::scope(|scope| {
+            let fetch =
+                scope.spawn(|| fetch_url_cancellable(&url, &options, &Meters::noop(), &cancel));
+            thread::sleep(Duration::from_millis(200));
+            cancel.cancel();
+            fetch.join().expect("fetch panicked")
+        });
+        assert!(matches!(result, Err(FetchError::Cancelled)), "{result:?}");
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)] // reqwest blocking -> setsockopt SO_KEEPALIVE unsupported under Miri
+    async fn fetch_to_saves_the_body_of_the_last_attempt() {
+        let (port, _) = start_flaky_server(1, None).await;
+        let url = format!("http://127.0.0.1:{port}/");
+        let (result, saved) = tokio::task::spawn_blocking(move || {
+            let client = Client::new(retrying(2)).expect("client");
+            let mut saved = Vec::new();
+            let result = client.fetch_to(
+                &Request::get(url),
+                &Meters::noop(),
+                &CancelToken::new(),
+                &mut saved,
+            );
+            (result, saved)
+        })
+        .await
+        .expect("spawn_blocking panicked");
+        let result = result.expect("expected Ok after a retry");
+        assert_eq!((result.status, result.attempts), (200, 2));
+        assert_eq!(result.body_size, 2);
+        // The body of the 503 that was retried is left out.
+        assert_eq!(saved, b"ok");
+    }
+}
@@ -0,0 +1,458 @@
+//! The pooled HTTP [`Client`]: sending attempts, streaming response bodies
+//! and counting the connections it opens.
+
+use std::cell::Cell;
+use std::io::{self, Read as _, Write};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::task::{Context, Poll};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use reqwest::header::{CONTENT_TYPE, HeaderMap, RETRY_AFTER};
+
+use super::attempt::{Sent, fetch_with_retries};
+use super::{ClientOptions, FetchError, FetchResult, Fetched, Request, retry};
+use crate::libs::cancellation::CancelToken;
+use crate::telemetry::metrics::Meters;
+
+/// How often a cancellable fetch checks its [`CancelToken`].
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Size of the chunks response bodies are read in.
+const BODY_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Body chunks a background attempt reads ahead of the sink they go to.
+const CHUNKS_IN_FLIGHT: usize = 4;
+
+/// How often progress reading a response body is logged.
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// An HTTP client built once from [`ClientOptions`] and shared by every
+/// request sent through it, so keep-alive connections are reused.
+///
+/// Cloning is cheap: clones share the connection pool.
+#[derive(Debug, Clone)]
+pub struct Client {
+    inner: reqwest::blocking::Client,
+    options: ClientOptions,
+    /// Connections opened so far, counted by [`CountConnections`].
+    opened: Arc<AtomicU64>,
+}
+
+impl Client {
+    /// Build the client from `options`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FetchError::Client`] if the client cannot be built, e.g.
+    /// when no TLS backend is available.
+    pub fn new(options: ClientOptions) -> Result<Self, FetchError> {
+        let opened = Arc::new(AtomicU64::new(0));
+        let mut builder =
+            reqwest::Client::builder().connector_layer(CountConnections(Arc::clone(&opened)));
+        if let Some(connect_timeout) = options.connect_timeout {
+            builder = builder.connect_timeout(connect_timeout);
+        }
+        if let Some(read_timeout) = options.read_timeout {
+            builder = builder.read_timeout(read_timeout);
+        }
+        // The blocking builder has no read timeout, so start from the async one.
+        let mut builder = reqwest::blocking::ClientBuilder::from(builder).timeout(options.timeout);
+        if let Some(ref user_agent) = options.user_agent {
+            builder = builder.user_agent(user_agent);
+        }
+        let inner = builder.build().map_err(FetchError::Client)?;
+        Ok(Self {
+            inner,
+            options,
+            opened,
+        })
+    }
+
+    /// Options the client was built from.
+    #[must_use]
+    pub const fn options(&self) -> &ClientOptions {
+        &self.options
+    }
+
+    /// Connections opened so far, including attempts that failed to
+    /// connect.
+    #[must_use]
+    pub fn connections_opened(&self) -> u64 {
+        self.opened.load(Ordering::Relaxed)
+    }
+
+    /// Send `request`, read the response body and record `OTel` client
+    /// metrics.
+    ///
+    /// Records `http.client.request.duration` with `OTel` HTTP semantic
+    /// convention attributes, including the request's method, for every
+    /// attempt. The duration covers the full round-trip including response
+    /// body download. Failed attempts are retried as the client's
+    /// [`retry::Policy`] allows; each attempt gets its own client span,
+    /// which tells whether its connection was reused.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FetchError::InvalidUrl`] if the URL is invalid, and the
+    /// [`FetchError`] of the failure otherwise, such as
+    /// [`FetchError::Timeout`] or [`FetchError::Tls`]. With retries, the error
+    /// is the one of the last attempt.
+    #[cfg_attr(
+        feature = "otel",
+        tracing::instrument(
+            name = "fetch_url",
+            skip_all,
+            fields(http.request.method = %request.method(), url = request.url())
+        )
+    )]
+    pub fn fetch(&self, request: &Request, meters: &Meters) -> Result<FetchResult, FetchError> {
+        let cancel = CancelToken::new();
+        let written = Cell::new(false);
+        fetch_with_retries(
+            request,
+            &self.options,
+            meters,
+            &cancel,
+            &written,
+            |attempt, trace| self.send(request, trace, attempt, None, &cancel),
+        )
+    }
+
+    /// [`fetch`](Self::fetch), giving up as soon as `cancel` fires.
+    ///
+    /// Each attempt runs on a background thread; when cancelled, it is
+    /// abandoned and nothing is recorded for it. A wait between attempts also
+    /// ends early.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FetchError::Cancelled`] if `cancel` fires before the
+    /// response body is read, and otherwise the same errors as
+    /// [`fetch`](Self::fetch).
+    #[cfg_attr(
+        feature = "otel",
+        tracing::instrument(
+            name = "fetch_url",
+            skip_all,
+            fields(http.request.method = %request.method(), url = request.url())
+        )
+    )]
+    pub fn fetch_cancellable(
+        &self,
+        request: &Request,
+        meters: &Meters,
+        cancel: &CancelToken,
+    ) -> Result<FetchResult, FetchError> {
+        let written = Cell::new(false);
+        fetch_with_retries(
+            request,
+            &self.options,
+            meters,
+            cancel,
+            &written,
+            |attempt, trace| self.send_cancellable(request, trace, attempt, None, &written, cancel),
+        )
+    }
+
+    /// [`fetch_cancellable`](Self::fetch_cancellable), writing the response
+    /// body to `sink` as it arrives.
+    ///
+    /// The body is read on the attempt's background thread and written on
+    /// the calling one, a few chunks at a time, so `sink` need not be
+    /// [`Send`]. Bodies of responses the retry policy sends the request
+    /// again for are discarded. Once part of a body has been written, a
+    /// failure is not retried, so `sink` never gets the start of a body
+    /// twice.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FetchError::Write`] if writing to `sink` fails, and
+    /// otherwise the same errors as
+    /// [`fetch_cancellable`](Self::fetch_cancellable).
+    #[cfg_attr(
+        feature = "otel",
+        tracing::instrument(
+            name = "fetch_url",
+            skip_all,
+            fields(http.request.method = %request.method(), url = request.url())
+        )
+    )]
+    pub fn fetch_to(
+        &self,
+        request: &Request,
+        meters: &Meters,
+        cancel: &CancelToken,
+        sink: &mut dyn Write,
+    ) -> Result<FetchResult, FetchError> {
+        let written = Cell::new(false);
+        fetch_with_retries(
+            request,
+            &self.options,
+            meters,
+            cancel,
+            &written,
+            |attempt, trace| {
+                self.send_cancellable(request, trace, attempt, Some(&mut *sink), &written, cancel)
+            },
+        )
+    }
+
+    /// Fetch `requests` one after another, as
+    /// [`fetch_cancellable`](Self::fetch_cancellable), or as
+    /// [`fetch_to`](Self::fetch_to) when given a `sink`, and record the
+    /// run's end-to-end latency.
+    ///
+    /// `on_response` is called with each completed request. A failure does
+    /// not stop the run; when there are several requests, each one is logged
+    /// as a warning. Stops after a cancelled request, so later ones are left
+    /// out of the result.
+    pub fn fetch_all(
+        &self,
+        requests: &[Request],
+        meters: &Meters,
+        cancel: &CancelToken,
+        mut sink: Option<&mut dyn Write>,
+        mut on_response: impl FnMut(&Request, &FetchResult),
+    ) -> Fetched {
+        let start = Instant::now();
+        let mut fetched = Vec::with_capacity(requests.len());
+        for request in requests {
+            let result = sink.as_deref_mut().map_or_else(
+                || self.fetch_cancellable(request, meters, cancel),
+                |sink| self.fetch_to(request, meters, cancel, sink),
+            );
+            match result {
+                Ok(ref response) => on_response(request, response),
+                Err(ref e) if requests.len() > 1 => tracing::warn!(url = request.url(), "{e}"),
+                Err(_) => {}
+            }
+            let cancelled = matches!(result, Err(FetchError::Cancelled));
+            fetched.push((request.clone(), result));
+            if cancelled {
+                break;
+            }
+        }
+        meters.record_run_duration(start.elapsed().as_secs_f64(), "http");
+        fetched
+    }
+
+    /// Send `request` with the `trace` context headers as attempt `attempt`
+    /// (1 for the first) and read the response body, passing each chunk to
+    /// `save` if given, until `cancel` fires.
+    ///
+    /// Headers set on the request take precedence over `trace`. Whether the
+    /// connection was reused is only exact when no other request is sent
+    /// through the client at the same time.
+    fn send(
+        &self,
+        request: &Request,
+        trace: &HeaderMap,
+        attempt: u32,
+        save: Option<SaveChunk<'_>>,
+        cancel: &CancelToken,
+    ) -> Result<Sent, FetchError> {
+        // Extending replaces every trace header the request also sets.
+        let mut headers = trace.clone();
+        headers.extend(request.headers().clone());
+        let mut builder = self
+            .inner
+            .request(request.method().clone(), request.url())
+            .headers(headers);
+        if let Some(content_type) = request.content_type() {
+            builder = builder.header(CONTENT_TYPE, content_type);
+        }
+        if let Some(body) = request.body() {
+            builder = builder.body(body.to_vec());
+        }
+
+        let opened = self.connections_opened();
+        let start = Instant::now();
+        let mut response = builder.send().map_err(FetchError::from_request)?;
+        let reused_connection = self.connections_opened() == opened;
+        let status = response.status().as_u16();
+        let retry_after = response
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| retry::parse_retry_after(value, SystemTime::now()));
+        // A body that is about to be fetched again is not worth saving.
+        let policy = &self.options.retry;
+        let retried = policy.retries_method(request.method())
+            && policy.retries_status(status)
+            && policy.allows(attempt, retry_after);
+        let mut discard = |_: &[u8]| Ok(());
+        let on_chunk = match save {
+            Some(save) if !retried => save,
+            _ => &mut discard,
+        };
+        let body_size = self.read_body(request, &mut response, on_chunk, cancel)?;
+        Ok(Sent {
+            status,
+            duration_s: start.elapsed().as_secs_f64(),
+            retry_after,
+            reused_connection,
+            body_size,
+        })
+    }
+
+    /// Read the body of `response` to `request` in chunks, passing each to
+    /// `on_chunk`, and return its size.
+    ///
+    /// Progress is logged every [`PROGRESS_INTERVAL`]. Reading stops with
+    /// [`FetchError::BodyTooLarge`] as soon as the body is known to exceed
+    /// the size limit, before the chunk going over it is passed on.
+    fn read_body(
+        &self,
+        request: &Request,
+        response: &mut reqwest::blocking::Response,
+        on_chunk: SaveChunk<'_>,
+        cancel: &CancelToken,
+    ) -> Result<u64, FetchError> {
+        let limit = self.options.max_body_size;
+        let total = response.content_length();
+        if let Some(limit) = limit.filter(|&limit| total.is_some_and(|total| total > limit)) {
+            return Err(FetchError::BodyTooLarge(limit));
+        }
+        let method = request.method();
+        let mut buffer = vec![0; BODY_CHUNK_SIZE];
+        let mut size = 0_u64;
+        let mut reported = Instant::now();
+        loop {
+            cancel.check().map_err(|_| FetchError::Cancelled)?;
+            let read = match response.read(&mut buffer) {
+                Ok(0) => return Ok(size),
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(FetchError::from_body(e)),
+            };
+            size = size.saturating_add(u64::try_from(read).unwrap_or(u64::MAX));
+            if let Some(limit) = limit.filter(|&limit| size > limit) {
+                return Err(FetchError::BodyTooLarge(limit));
+            }
+            on_chunk(buffer.get(..read).unwrap_or_default())?;
+            if reported.elapsed() >= PROGRESS_INTERVAL {
+                reported = Instant::now();
+                let of_total = total
+                    .map(|total| format!(" of {total}"))
+                    .unwrap_or_default();
+                tracing::info!(
+                    url = request.url(),
+                    "HTTP {method} body: {size}{of_total} bytes"
+                );
+            }
+        }
+    }
+
+    /// [`send`](Self::send) on a background thread, abandoned as soon as
+    /// `cancel` fires.
+    ///
+    /// With a `sink`, the body comes back in chunks that are written to it
+    /// here, and `written` is set once one is.
+    fn send_cancellable(
+        &self,
+        request: &Request,
+        trace: &HeaderMap,
+        attempt: u32,
+        mut sink: Option<&mut dyn Write>,
+        written: &Cell<bool>,
+        cancel: &CancelToken,
+    ) -> Result<Sent, FetchError> {
+        let (tx, rx) = mpsc::sync_channel(CHUNKS_IN_FLIGHT);
+        let sent = (
+            self.clone(),
+            request.clone(),
+            trace.clone(),
+            sink.is_some(),
+            cancel.clone(),
+        );
+        thread::spawn(move || {
+            let (client, request, trace, saving, cancel) = sent;
+            let chunks = tx.clone();
+            // A send fails only once the fetch has been given up.
+            let mut save = |chunk: &[u8]| {
+                chunks
+                    .send(Attempt::Chunk(chunk.to_vec()))
+                    .map_err(|_| FetchError::Cancelled)
+            };
+            let save = saving.then_some::<SaveChunk<'_>>(&mut save);
+            let _ = tx.send(Attempt::Done(
+                client.send(&request, &trace, attempt, save, &cancel),
+            ));
+        });
+        loop {
+            match rx.recv_timeout(CANCEL_POLL_INTERVAL) {
+                Ok(Attempt::Chunk(chunk)) => {
+                    written.set(true);
+                    if let Some(ref mut sink) = sink {
+                        sink.write_all(&chunk).map_err(FetchError::Write)?;
+                    }
+                }
+                Ok(Attempt::Done(sent)) => {
+                    if let (Ok(_), Some(sink)) = (&sent, sink) {
+                        sink.flush().map_err(FetchError::Write)?;
+                    }
+                    return sent;
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) if !cancel.is_cancelled() => {}
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    tracing::warn!(url = request.url(), "HTTP {} cancelled", request.method());
+                    return Err(FetchError::Cancelled);
+                }
+                // The request thread hung up without a result, so it panicked.
+                Err(mpsc::RecvTimeoutError::Disconnected) => return Err(FetchError::Panicked),
+            }
+        }
+    }
+}
+
+/// Takes each chunk of a response body that is saved.
+type SaveChunk<'a> = &'a mut dyn FnMut(&[u8]) -> Result<(), FetchError>;
+
+/// What the background thread of
+/// [`send_cancellable`](Client::send_cancellable) reports.
+enum Attempt {
+    /// A chunk of the response body to save.
+    Chunk(Vec<u8>),
+    /// The outcome of the attempt.
+    Done(Result<Sent, FetchError>),
+}
+
+/// Connector layer counting the connections a client opens.
+#[derive(Debug, Clone)]
+struct CountConnections(Arc<AtomicU64>);
+
+impl<S> tower::Layer<S> for CountConnections {
+    type Service = CountingConnector<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CountingConnector {
+            inner,
+            opened: Arc::clone(&self.0),
+        }
+    }
+}
+
+/// Connector that counts every connection it is asked to open.
+#[derive(Debug, Clone)]
+struct CountingConnector<S> {
+    inner: S,
+    opened: Arc<AtomicU64>,
+}
+
+impl<S: tower::Service<R>, R> tower::Service<R> for CountingConnector<S> {
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: R) -> Self::Future {
+        self.opened.fetch_add(1, Ordering::Relaxed);
+        self.inner.call(request)
+    }
+}
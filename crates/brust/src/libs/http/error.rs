@@ -0,0 +1,333 @@
+//! Why an HTTP fetch failed, classified by the `error.type` it is recorded
+//! with.
+
+use std::fmt;
+use std::io;
+
+use crate::libs::units::ByteSize;
+
+/// Why an HTTP fetch failed.
+#[derive(Debug)]
+pub enum FetchError {
+    /// The URL could not be parsed.
+    InvalidUrl(String),
+    /// The HTTP client could not be built.
+    Client(reqwest::Error),
+    /// A connect, read or total timeout expired.
+    Timeout(reqwest::Error),
+    /// The server refused the connection.
+    ConnectionRefused(reqwest::Error),
+    /// The host name could not be resolved.
+    Dns(reqwest::Error),
+    /// The TLS handshake or certificate verification failed.
+    Tls(reqwest::Error),
+    /// The response body could not be read in full.
+    BodyRead(io::Error),
+    /// The response body is larger than the limit, in bytes, of
+    /// [`ClientOptions::max_body_size`](super::ClientOptions::max_body_size).
+    BodyTooLarge(u64),
+    /// The response body could not be written to the sink of
+    /// [`Client::fetch_to`](super::Client::fetch_to).
+    Write(io::Error),
+    /// Connecting, sending the request or reading the response failed
+    /// otherwise.
+    Network(reqwest::Error),
+    /// The background thread of the attempt panicked.
+    Panicked,
+    /// The fetch was cancelled before the response was read.
+    Cancelled,
+}
+
+impl FetchError {
+    /// `error.type` attribute value.
+    #[must_use]
+    pub const fn error_type(&self) -> &'static str {
+        match self {
+            Self::InvalidUrl(_) => "invalid_url",
+            Self::Client(_) => "client",
+            Self::Timeout(_) => "timeout",
+            Self::ConnectionRefused(_) => "connection_refused",
+            Self::Dns(_) => "dns",
+            Self::Tls(_) => "tls",
+            Self::BodyRead(_) => "body_read",
+            Self::BodyTooLarge(_) => "body_too_large",
+            Self::Write(_) => "write",
+            Self::Network(_) => "network",
+            Self::Panicked => "panicked",
+            Self::Cancelled => "cancelled",
+        }
+    }
+
+    /// Whether the request was sent, or at least tried, before failing.
+    pub(super) const fn was_sent(&self) -> bool {
+        !matches!(
+            self,
+            Self::InvalidUrl(_) | Self::Client(_) | Self::Cancelled
+        )
+    }
+
+    /// Classify an error sending the request or reading the response head.
+    pub(super) fn from_request(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            Self::Timeout(e)
+        } else if in_chain(&e, <dyn std::error::Error>::is::<rustls::Error>) {
+            Self::Tls(e)
+        } else if in_chain(&e, |error| {
+            error
+                .downcast_ref::<std::io::Error>()
+                .is_some_and(|io| io.kind() == std::io::ErrorKind::ConnectionRefused)
+        }) {
+            Self::ConnectionRefused(e)
+        } else if e.is_dns() {
+            Self::Dns(e)
+        } else {
+            Self::Network(e)
+        }
+    }
+
+    /// Classify an error reading the response body.
+    ///
+    /// Timeouts come as I/O errors wrapping the [`reqwest::Error`].
+    pub(super) fn from_body(e: io::Error) -> Self {
+        let timed_out = e
+            .get_ref()
+            .and_then(|inner| inner.downcast_ref::<reqwest::Error>())
+            .is_some_and(reqwest::Error::is_timeout);
+        if !timed_out {
+            return Self::BodyRead(e);
+        }
+        let kind = e.kind();
+        match e
+            .into_inner()
+            .map(<dyn std::error::Error + Send + Sync>::downcast::<reqwest::Error>)
+        {
+            Some(Ok(inner)) => Self::Timeout(*inner),
+            Some(Err(inner)) => Self::BodyRead(io::Error::new(kind, inner)),
+            None => Self::BodyRead(kind.into()),
+        }
+    }
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidUrl(reason) => write!(f, "invalid URL: {reason}"),
+            Self::Client(e) => write!(f, "failed to build HTTP client: {e}"),
+            Self::Timeout(e) => write!(f, "HTTP request timed out: {}", error_chain(e)),
+            Self::Tls(e) => write!(f, "TLS error: {}", error_chain(e)),
+            Self::BodyRead(e) => {
+                write!(f, "failed to read HTTP response body: {}", error_chain(e))
+            }
+            Self::BodyTooLarge(limit) => {
+                write!(f, "HTTP response body is larger than {}", ByteSize(*limit))
+            }
+            Self::Write(e) => write!(f, "failed to write HTTP response body: {e}"),
+            Self::ConnectionRefused(e) | Self::Dns(e) | Self::Network(e) => {
+                write!(f, "HTTP request failed: {}", error_chain(e))
+            }
+            Self::Panicked => write!(f, "HTTP request failed: the request thread panicked"),
+            Self::Cancelled => write!(f, "HTTP request cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for FetchError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::InvalidUrl(_) | Self::BodyTooLarge(_) | Self::Panicked | Self::Cancelled => None,
+            Self::Client(e)
+            | Self::Timeout(e)
+            | Self::ConnectionRefused(e)
+            | Self::Dns(e)
+            | Self::Tls(e)
+            | Self::Network(e) => Some(e),
+            Self::BodyRead(e) | Self::Write(e) => Some(e),
+        }
+    }
+}
+
+/// Whether `matches` holds for the error or anything in its source chain.
+///
+/// `io::Error::source` skips the wrapped error itself, so I/O errors are
+/// unwrapped with `get_ref` instead.
+fn in_chain(
+    e: &reqwest::Error,
+    matches: impl Fn(&(dyn std::error::Error + 'static)) -> bool,
+) -> bool {
+    let mut current: Option<&(dyn std::error::Error + 'static)> = Some(e);
+    while let Some(error) = current {
+        if matches(error) {
+            return true;
+        }
+        current = error.downcast_ref::<std::io::Error>().map_or_else(
+            || error.source(),
+            |io| io.get_ref().map(without_auto_traits),
+        );
+    }
+    false
+}
+
+const fn without_auto_traits<'a>(
+    e: &'a (dyn std::error::Error + Send + Sync + 'static),
+) -> &'a (dyn std::error::Error + 'static) {
+    e
+}
+
+/// `e` followed by each of its sources, separated by `: `.
+fn error_chain(e: &dyn std::error::Error) -> String {
+    let mut message = e.to_string();
+    let mut source = e.source();
+    while let Some(error) = source {
+        message.push_str(": ");
+        message.push_str(&error.to_string());
+        source = error.source();
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use super::FetchError;
+    use crate::libs::http::{ClientOptions, fetch_url};
+    use crate::telemetry::metrics::Meters;
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)] // reqwest blocking -> setsockopt SO_KEEPALIVE unsupported under Miri
+    async fn fetch_url_connection_refused_returns_error() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+
+        // Bind and immediately drop to get a port that is not listening.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind failed");
+        let port = listener.local_addr().expect("local_addr").port();
+        drop(listener);
+
+        let url = format!("http://127.0.0.1:{port}/");
+        let meters = Meters::default();
+        let result = tokio::task::spawn_blocking(move || {
+            fetch_url(&url, &ClientOptions::default(), &meters)
+        })
+        .await
+        .expect("spawn_blocking panicked");
+        let error = result.expect_err("expected connection refused");
+        assert!(
+            matches!(error, FetchError::ConnectionRefused(_)),
+            "{error:?}"
+        );
+        assert_eq!(error.error_type(), "connection_refused");
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)] // reqwest blocking -> setsockopt SO_KEEPALIVE unsupported under Miri
+    async fn fetch_url_tls_handshake_failure_is_tls_error() {
+        use std::io::{Read as _, Write as _};
+
+        let _ = rustls::crypto::ring::default_provider().install_default();
+
+        // A plain-text server answering a TLS ClientHello makes the handshake fail.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind failed");
+        let port = listener.local_addr().expect("local_addr").port();
+        std::thread::spawn(move || {
+            while let Ok((mut stream, _)) = listener.accept() {
+                let mut buf = [0_u8; 1024];
+                let _ = stream.read(&mut buf);
+                let _ = stream.write_all(b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\n\r\n");
+            }
+        });
+
+        let url = format!("https://127.0.0.1:{port}/");
+        let meters = Meters::default();
+        let result = tokio::task::spawn_blocking(move || {
+            fetch_url(&url, &ClientOptions::default(), &meters)
+        })
+        .await
+        .expect("spawn_blocking panicked");
+        let error = result.expect_err("expected TLS failure");
+        assert!(matches!(error, FetchError::Tls(_)), "{error:?}");
+        assert!(error.to_string().starts_with("TLS error: "), "{error}");
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)] // reqwest blocking -> setsockopt SO_KEEPALIVE unsupported under Miri
+    fn timeouts_are_timeout_errors() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+
+        // Connections are queued by the OS but never answered.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind failed");
+        let url = format!(
+            "http://{}/",
+            listener.local_addr().expect("local_addr failed")
+        );
+        for options in [
+            ClientOptions {
+                timeout: Some(Duration::from_millis(200)),
+                ..ClientOptions::default()
+            },
+            ClientOptions {
+                read_timeout: Some(Duration::from_millis(200)),
+                ..ClientOptions::default()
+            },
+        ] {
+            let start = Instant::now();
+            let error = fetch_url(&url, &options, &Meters::noop()).expect_err("expected timeout");
+            assert!(matches!(error, FetchError::Timeout(_)), "{error:?}");
+            assert_eq!(error.error_type(), "timeout");
+            assert!(
+                error.to_string().starts_with("HTTP request timed out: "),
+                "{error}"
+            );
+            assert!(start.elapsed() < Duration::from_secs(5));
+        }
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)] // reqwest blocking -> getaddrinfo unsupported under Miri
+    fn unknown_hosts_are_dns_errors() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+
+        // `.invalid` never resolves (RFC 6761).
+        let result = fetch_url(
+            "http://brust.invalid/",
+            &ClientOptions::default(),
+            &Meters::noop(),
+        );
+        let error = result.expect_err("expected DNS failure");
+        assert!(matches!(error, FetchError::Dns(_)), "{error:?}");
+        assert_eq!(error.error_type(), "dns");
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)] // reqwest blocking -> setsockopt SO_KEEPALIVE unsupported under Miri
+    fn truncated_bodies_are_body_read_errors() {
+        use std::io::{Read as _, Write as _};
+
+        let _ = rustls::crypto::ring::default_provider().install_default();
+
+        // The server promises ten bytes of body, sends two and hangs up.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind failed");
+        let port = listener.local_addr().expect("local_addr").port();
+        thread::spawn(move || {
+            while let Ok((mut stream, _)) = listener.accept() {
+                let mut buf = [0_u8; 1024];
+                let _ = stream.read(&mut buf);
+                let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nok");
+            }
+        });
+
+        let url = format!("http://127.0.0.1:{port}/");
+        let error = fetch_url(&url, &ClientOptions::default(), &Meters::noop())
+            .expect_err("expected a truncated body");
+        assert!(matches!(error, FetchError::BodyRead(_)), "{error:?}");
+        assert_eq!(error.error_type(), "body_read");
+        assert!(
+            error
+                .to_string()
+                .starts_with("failed to read HTTP response body: "),
+            "{error}"
+        );
+    }
+}
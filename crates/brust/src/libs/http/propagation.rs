@@ -0,0 +1,47 @@
+//! Trace context propagation into request headers, so servers can continue
+//! the client's trace.
+
+#[cfg(feature = "otel")]
+use std::str::FromStr;
+
+use reqwest::header::HeaderMap;
+#[cfg(feature = "otel")]
+use reqwest::header::{HeaderName, HeaderValue};
+
+#[cfg(feature = "otel")]
+use opentelemetry::{global, propagation::Injector};
+#[cfg(feature = "otel")]
+use tracing_opentelemetry::OpenTelemetrySpanExt as _;
+
+/// Trace context headers for the current span from the global propagator.
+///
+/// Empty when no propagator is installed or the span is not recorded by
+/// `OTel`.
+#[cfg(feature = "otel")]
+pub(super) fn trace_headers() -> HeaderMap {
+    let context = tracing::Span::current().context();
+    let mut headers = HeaderMap::new();
+    global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&context, &mut HeaderInjector(&mut headers));
+    });
+    headers
+}
+
+/// Without the `otel` feature there is no trace context to propagate.
+#[cfg(not(feature = "otel"))]
+pub(super) fn trace_headers() -> HeaderMap {
+    HeaderMap::new()
+}
+
+/// Writes propagated fields into request headers, skipping invalid ones.
+#[cfg(feature = "otel")]
+struct HeaderInjector<'a>(&'a mut HeaderMap);
+
+#[cfg(feature = "otel")]
+impl Injector for HeaderInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(name), Ok(value)) = (HeaderName::from_str(key), HeaderValue::from_str(&value)) {
+            self.0.insert(name, value);
+        }
+    }
+}
@@ -0,0 +1,292 @@
+//! HTTP requests: method, URL, headers and body.
+//!
+//! [`read_body`] and [`read_url_file`] read request bodies and URL lists the
+//! way the command line gives them, from text, a file or stdin.
+
+use std::fmt;
+use std::io::{self, Read as _};
+use std::path::Path;
+use std::str::FromStr;
+
+use reqwest::Method;
+use reqwest::header::{CONTENT_TYPE, HeaderMap, HeaderName, HeaderValue};
+use serde::de::IgnoredAny;
+
+/// An HTTP request: method, URL, headers and an optional body.
+///
+/// A body that is a JSON object or array is sent as `application/json`
+/// unless a `Content-Type` header is set; other bodies, JSON scalars such as
+/// `42` or `true` included, are sent without one.
+#[derive(Debug, Clone)]
+pub struct Request {
+    method: Method,
+    url: String,
+    headers: HeaderMap,
+    body: Option<Vec<u8>>,
+}
+
+impl Request {
+    /// Request for `url` with `method`, no headers and no body.
+    #[must_use]
+    pub fn new(method: Method, url: impl Into<String>) -> Self {
+        Self {
+            method,
+            url: url.into(),
+            headers: HeaderMap::new(),
+            body: None,
+        }
+    }
+
+    /// Request for `url` as the command line describes it: `method`, or
+    /// `POST` with a `body` and `GET` otherwise, sending every `headers`
+    /// entry.
+    #[must_use]
+    pub fn from_parts(
+        method: Option<Method>,
+        url: impl Into<String>,
+        headers: &[Header],
+        body: Option<&[u8]>,
+    ) -> Self {
+        let method = method.unwrap_or_else(|| Self::default_method(body.is_some()));
+        let mut request = Self::new(method, url);
+        for header in headers {
+            request = request.with_header(header.clone());
+        }
+        request.body = body.map(<[u8]>::to_vec);
+        request
+    }
+
+    /// Method sent when none is given: `POST` with a body, `GET` otherwise.
+    #[must_use]
+    pub const fn default_method(has_body: bool) -> Method {
+        if has_body { Method::POST } else { Method::GET }
+    }
+
+    /// `GET` request for `url`.
+    #[must_use]
+    pub fn get(url: impl Into<String>) -> Self {
+        Self::new(Method::GET, url)
+    }
+
+    /// Add `header`; repeating a name sends every value.
+    #[must_use]
+    pub fn with_header(mut self, header: Header) -> Self {
+        self.headers.append(header.name, header.value);
+        self
+    }
+
+    /// Send `body` with the request.
+    #[must_use]
+    pub fn with_body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+
+    /// HTTP method.
+    #[must_use]
+    pub const fn method(&self) -> &Method {
+        &self.method
+    }
+
+    /// Target URL.
+    #[must_use]
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Headers sent in addition to `User-Agent` and `Content-Type`.
+    #[must_use]
+    pub const fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Request body, if any.
+    #[must_use]
+    pub fn body(&self) -> Option<&[u8]> {
+        self.body.as_deref()
+    }
+
+    /// `Content-Type` to add for the body: `application/json` for a JSON
+    /// object or array when no `Content-Type` header is set.
+    ///
+    /// Scalars are left untagged: form data like `1` or `true` and plain
+    /// text like `"quoted"` parse as JSON too.
+    pub(super) fn content_type(&self) -> Option<HeaderValue> {
+        let body = self.body.as_deref()?;
+        let structured = body
+            .trim_ascii_start()
+            .first()
+            .is_some_and(|first| matches!(first, b'{' | b'['));
+        let json = structured
+            && !self.headers.contains_key(CONTENT_TYPE)
+            && serde_json::from_slice::<IgnoredAny>(body).is_ok();
+        json.then_some(HeaderValue::from_static("application/json"))
+    }
+}
+
+/// A request header, written `Name: value` on the command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    /// Header name.
+    pub name: HeaderName,
+    /// Header value.
+    pub value: HeaderValue,
+}
+
+/// Error returned when a header cannot be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeaderError(String);
+
+impl fmt::Display for HeaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for HeaderError {}
+
+impl FromStr for Header {
+    type Err = HeaderError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| HeaderError(format!("invalid header {s:?}: {reason}"));
+        let (name, value) = s
+            .split_once(':')
+            .ok_or_else(|| invalid("expected NAME: VALUE"))?;
+        Ok(Self {
+            name: HeaderName::from_str(name.trim()).map_err(|_| invalid("bad name"))?,
+            value: HeaderValue::from_str(value.trim()).map_err(|_| invalid("bad value"))?,
+        })
+    }
+}
+
+/// Request body given as `data`: the text itself, the contents of the file
+/// for `@FILE`, or stdin for `@-`.
+///
+/// # Errors
+///
+/// Returns the I/O error of reading the file or stdin, prefixed with the
+/// file name or `stdin`.
+pub fn read_body(data: &str) -> io::Result<Vec<u8>> {
+    let (source, read) = match data.strip_prefix('@') {
+        None => return Ok(data.as_bytes().to_vec()),
+        Some("-") => {
+            let mut body = Vec::new();
+            (
+                "stdin",
+                io::stdin().lock().read_to_end(&mut body).map(|_| body),
+            )
+        }
+        Some(path) => (path, std::fs::read(path)),
+    };
+    read.map_err(|e| io::Error::new(e.kind(), format!("{source}: {e}")))
+}
+
+/// URLs listed in `path`, or stdin for `-`, one per line; blank lines and
+/// lines starting with `#` are skipped.
+///
+/// # Errors
+///
+/// Returns the I/O error of reading the file or stdin.
+pub fn read_url_file(path: &Path) -> io::Result<Vec<String>> {
+    let text = if path == Path::new("-") {
+        let mut text = String::new();
+        io::stdin().lock().read_to_string(&mut text)?;
+        text
+    } else {
+        std::fs::read_to_string(path)?
+    };
+    Ok(text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headers_parse_from_name_and_value() {
+        let header: Header = "X-Request-Id:  42 ".parse().expect("valid header");
+        assert_eq!(header.name, "x-request-id");
+        assert_eq!(header.value, "42");
+        let header: Header = "Accept:".parse().expect("empty value is valid");
+        assert_eq!(header.value, "");
+        for invalid in [
+            "no colon",
+            ": value",
+            "bad name: value",
+            "X-Bad: line\nbreak",
+        ] {
+            let error = invalid.parse::<Header>().expect_err(invalid);
+            assert!(error.to_string().starts_with("invalid header"), "{error}");
+        }
+    }
+
+    #[test]
+    fn json_bodies_default_to_a_json_content_type() {
+        let json = Request::new(Method::POST, "http://127.0.0.1/").with_body(r#"{"a": 1}"#);
+        assert_eq!(
+            json.content_type(),
+            Some(HeaderValue::from_static("application/json"))
+        );
+        assert_eq!(json.body(), Some(br#"{"a": 1}"#.as_slice()));
+        let array = Request::new(Method::POST, "http://127.0.0.1/").with_body(" [1, 2]");
+        assert_eq!(
+            array.content_type(),
+            Some(HeaderValue::from_static("application/json"))
+        );
+        let text = Request::new(Method::POST, "http://127.0.0.1/").with_body("a=1");
+        assert_eq!(text.content_type(), None);
+        for scalar in ["42", "true", "null", r#""quoted""#] {
+            let request = Request::new(Method::POST, "http://127.0.0.1/").with_body(scalar);
+            assert_eq!(request.content_type(), None, "{scalar}");
+        }
+        let explicit = json.with_header("Content-Type: text/plain".parse().expect("valid"));
+        assert_eq!(explicit.content_type(), None);
+        assert_eq!(Request::get("http://127.0.0.1/").content_type(), None);
+    }
+
+    #[test]
+    fn requests_from_parts_post_a_body_unless_told_otherwise() {
+        let header: Header = "Accept: text/plain".parse().expect("valid header");
+        let get = Request::from_parts(None, "http://127.0.0.1/", &[header], None);
+        assert_eq!(get.method(), Method::GET);
+        assert_eq!(get.headers()["accept"], "text/plain");
+        let post = Request::from_parts(None, "http://127.0.0.1/", &[], Some(b"a=1"));
+        assert_eq!(post.method(), Method::POST);
+        assert_eq!(post.body(), Some(b"a=1".as_slice()));
+        let put = Request::from_parts(Some(Method::PUT), "http://127.0.0.1/", &[], Some(b"a"));
+        assert_eq!(put.method(), Method::PUT);
+    }
+
+    #[test]
+    fn url_files_skip_blank_and_comment_lines() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("urls.txt");
+        std::fs::write(&path, "# hosts\n http://a/ \n\nhttp://b/\n").expect("write");
+        assert_eq!(
+            read_url_file(&path).expect("readable"),
+            ["http://a/", "http://b/"]
+        );
+        assert!(read_url_file(&dir.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn bodies_read_text_or_a_file() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("body.json");
+        std::fs::write(&path, "{}").expect("write");
+        assert_eq!(read_body("a=1").expect("text"), b"a=1");
+        assert_eq!(
+            read_body(&format!("@{}", path.display())).expect("file"),
+            b"{}"
+        );
+        let missing = format!("@{}", dir.path().join("missing").display());
+        let error = read_body(&missing).expect_err("missing file");
+        assert!(error.to_string().starts_with(&missing[1..]), "{error}");
+    }
+}
@@ -0,0 +1,424 @@
+//! Retry policy for HTTP requests: exponential backoff with jitter.
+//!
+//! Retry `n` (1 for the first) waits `base_delay * 2^(n-1)`, capped at
+//! `max_delay`, then shortened by a random share of up to `jitter` so clients
+//! that failed together do not retry together. A `Retry-After` header on a
+//! retryable response is a lower bound on the wait; when it asks for more
+//! than `max_delay`, the response is returned instead.
+
+use std::fmt;
+use std::num::NonZeroU32;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+
+use rand::{Rng, RngExt as _};
+use reqwest::Method;
+use serde::Deserialize;
+
+use super::FetchError;
+
+/// When and how often a failed request is sent again.
+///
+/// The default sends every request once; raise `max_attempts` to retry.
+/// Only idempotent methods (`GET`, `HEAD`, `PUT`, `DELETE`, `OPTIONS`,
+/// `TRACE`) are retried unless `non_idempotent` is set, since sending a
+/// `POST` or `PATCH` again may repeat its effect.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Policy {
+    /// Attempts per request, including the first.
+    pub max_attempts: NonZeroU32,
+    /// Wait before the first retry, before jitter.
+    pub base_delay: Duration,
+    /// Longest wait between two attempts.
+    pub max_delay: Duration,
+    /// Largest share of a wait that is randomly cut.
+    pub jitter: Jitter,
+    /// Response statuses that are retried.
+    pub statuses: Vec<u16>,
+    /// Request errors that are retried.
+    pub errors: Vec<RetryableError>,
+    /// Whether requests with non-idempotent methods are retried too.
+    pub non_idempotent: bool,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self {
+            max_attempts: NonZeroU32::MIN,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(10),
+            jitter: Jitter::HALF,
+            statuses: vec![429, 502, 503, 504],
+            errors: vec![
+                RetryableError::Timeout,
+                RetryableError::ConnectionRefused,
+                RetryableError::BodyRead,
+                RetryableError::Network,
+            ],
+            non_idempotent: false,
+        }
+    }
+}
+
+impl Policy {
+    /// Whether requests with `method` are retried at all.
+    #[must_use]
+    pub fn retries_method(&self, method: &Method) -> bool {
+        self.non_idempotent || method.is_idempotent()
+    }
+
+    /// Whether a response with `status` is retried.
+    #[must_use]
+    pub fn retries_status(&self, status: u16) -> bool {
+        self.statuses.contains(&status)
+    }
+
+    /// Whether a request that failed with `error` is retried.
+    #[must_use]
+    pub fn retries_error(&self, error: &FetchError) -> bool {
+        RetryableError::of(error).is_some_and(|kind| self.errors.contains(&kind))
+    }
+
+    /// Wait before retry `retry` (1 for the first) without jitter.
+    #[must_use]
+    pub fn backoff(&self, retry: u32) -> Duration {
+        2_u32
+            .checked_pow(retry.saturating_sub(1))
+            .and_then(|factor| self.base_delay.checked_mul(factor))
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay))
+    }
+
+    /// Whether retry `retry` (1 for the first) may follow an attempt asking
+    /// to wait `retry_after`: attempts are left and the wait is at most
+    /// `max_delay`.
+    #[must_use]
+    pub fn allows(&self, retry: u32, retry_after: Option<Duration>) -> bool {
+        retry < self.max_attempts.get() && retry_after.is_none_or(|after| after <= self.max_delay)
+    }
+
+    /// Wait before retry `retry` (1 for the first), at least `retry_after`;
+    /// `None` when the policy does not [allow](Self::allows) it.
+    #[must_use]
+    pub fn wait(
+        &self,
+        retry: u32,
+        retry_after: Option<Duration>,
+        rng: &mut impl Rng,
+    ) -> Option<Duration> {
+        if !self.allows(retry, retry_after) {
+            return None;
+        }
+        let cut = self.jitter.get() * rng.random::<f64>();
+        Some(
+            self.backoff(retry)
+                .mul_f64(1.0 - cut)
+                .max(retry_after.unwrap_or_default()),
+        )
+    }
+}
+
+/// Parse a `Retry-After` header value received at `now`: either a number of
+/// seconds or an HTTP date, which counts as zero once it has passed.
+#[must_use]
+pub fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(date.duration_since(now).unwrap_or_default())
+}
+
+/// Share of a backoff delay that jitter may cut, between 0 and 1 inclusive.
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd, Deserialize)]
+#[serde(try_from = "f64")]
+pub struct Jitter(f64);
+
+// Never NaN: every constructor checks the range.
+impl Eq for Jitter {}
+
+impl Jitter {
+    /// Waits are exactly the backoff delay.
+    pub const NONE: Self = Self(0.0);
+    /// Waits are cut by up to half.
+    pub const HALF: Self = Self(0.5);
+
+    /// Share as a number between 0 and 1.
+    #[must_use]
+    pub const fn get(self) -> f64 {
+        self.0
+    }
+}
+
+impl TryFrom<f64> for Jitter {
+    type Error = PolicyError;
+
+    fn try_from(jitter: f64) -> Result<Self, Self::Error> {
+        if (0.0..=1.0).contains(&jitter) {
+            Ok(Self(jitter))
+        } else {
+            Err(PolicyError(format!(
+                "jitter {jitter} is not between 0 and 1"
+            )))
+        }
+    }
+}
+
+impl FromStr for Jitter {
+    type Err = PolicyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.trim()
+            .parse::<f64>()
+            .map_err(|_| PolicyError(format!("invalid jitter {s:?} (expected e.g. 0.5)")))
+            .and_then(Self::try_from)
+    }
+}
+
+impl fmt::Display for Jitter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Kind of request error a [`Policy`] may retry; named after the
+/// `error.type` of the failed request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(try_from = "String")]
+pub enum RetryableError {
+    /// A connect, read or total timeout expired.
+    Timeout,
+    /// The server refused the connection.
+    ConnectionRefused,
+    /// The host name could not be resolved.
+    Dns,
+    /// The TLS handshake or certificate verification failed.
+    Tls,
+    /// The response body could not be read.
+    BodyRead,
+    /// Any other failure to connect, send the request or read the response.
+    Network,
+}
+
+impl RetryableError {
+    /// Every kind, in declaration order.
+    pub const ALL: [Self; 6] = [
+        Self::Timeout,
+        Self::ConnectionRefused,
+        Self::Dns,
+        Self::Tls,
+        Self::BodyRead,
+        Self::Network,
+    ];
+
+    /// Name used in configuration, the same as the `error.type` value.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Timeout => "timeout",
+            Self::ConnectionRefused => "connection_refused",
+            Self::Dns => "dns",
+            Self::Tls => "tls",
+            Self::BodyRead => "body_read",
+            Self::Network => "network",
+        }
+    }
+
+    /// Kind of `error`; `None` for errors that sending again cannot fix.
+    #[must_use]
+    pub const fn of(error: &FetchError) -> Option<Self> {
+        match error {
+            FetchError::Timeout(_) => Some(Self::Timeout),
+            FetchError::ConnectionRefused(_) => Some(Self::ConnectionRefused),
+            FetchError::Dns(_) => Some(Self::Dns),
+            FetchError::Tls(_) => Some(Self::Tls),
+            FetchError::BodyRead(_) => Some(Self::BodyRead),
+            FetchError::Network(_) => Some(Self::Network),
+            FetchError::InvalidUrl(_)
+            | FetchError::Client(_)
+            | FetchError::BodyTooLarge(_)
+            | FetchError::Write(_)
+            | FetchError::Panicked
+            | FetchError::Cancelled => None,
+        }
+    }
+}
+
+impl fmt::Display for RetryableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for RetryableError {
+    type Err = PolicyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == s.trim())
+            .ok_or_else(|| {
+                PolicyError(format!(
+                    "unknown retryable error {s:?} (expected timeout, connection_refused, dns, tls, body_read or network)"
+                ))
+            })
+    }
+}
+
+impl TryFrom<String> for RetryableError {
+    type Error = PolicyError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// Error returned when a jitter or retryable error cannot be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyError(String);
+
+impl fmt::Display for PolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for PolicyError {}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use rand::SeedableRng as _;
+    use rand::rngs::StdRng;
+
+    use super::*;
+
+    fn policy(max_attempts: u32, jitter: f64) -> Policy {
+        Policy {
+            max_attempts: NonZeroU32::new(max_attempts).unwrap(),
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(1),
+            jitter: Jitter::try_from(jitter).unwrap(),
+            ..Policy::default()
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_max_delay() {
+        let policy = policy(10, 0.0);
+        let backoff: Vec<_> = (1..=6).map(|retry| policy.backoff(retry)).collect();
+        assert_eq!(
+            backoff,
+            [100, 200, 400, 800, 1_000, 1_000].map(Duration::from_millis)
+        );
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn only_idempotent_methods_are_retried_by_default() {
+        let mut policy = Policy::default();
+        for method in ["GET", "HEAD", "PUT", "DELETE", "OPTIONS", "TRACE"] {
+            assert!(policy.retries_method(&method.parse().unwrap()), "{method}");
+        }
+        for method in ["POST", "PATCH", "CONNECT"] {
+            assert!(!policy.retries_method(&method.parse().unwrap()), "{method}");
+        }
+        policy.non_idempotent = true;
+        assert!(policy.retries_method(&Method::POST));
+    }
+
+    #[test]
+    fn wait_stops_after_the_last_attempt() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let policy = policy(3, 0.0);
+        assert_eq!(
+            policy.wait(1, None, &mut rng),
+            Some(Duration::from_millis(100))
+        );
+        assert_eq!(
+            policy.wait(2, None, &mut rng),
+            Some(Duration::from_millis(200))
+        );
+        assert_eq!(policy.wait(3, None, &mut rng), None);
+        assert_eq!(Policy::default().wait(1, None, &mut rng), None);
+    }
+
+    #[test]
+    fn jitter_cuts_at_most_its_share() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let policy = policy(10, 0.5);
+        let waits: Vec<_> = (0..100)
+            .filter_map(|_| policy.wait(3, None, &mut rng))
+            .collect();
+        assert_eq!(waits.len(), 100);
+        assert!(
+            waits.iter().all(
+                |wait| (Duration::from_millis(200)..=Duration::from_millis(400)).contains(wait)
+            )
+        );
+        assert!(waits.iter().any(|wait| *wait < Duration::from_millis(300)));
+    }
+
+    #[test]
+    fn retry_after_is_a_lower_bound_up_to_the_max_delay() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let policy = policy(3, 0.0);
+        let mut wait = |after| policy.wait(1, Some(after), &mut rng);
+        assert_eq!(
+            wait(Duration::from_millis(10)),
+            Some(Duration::from_millis(100))
+        );
+        assert_eq!(
+            wait(Duration::from_millis(700)),
+            Some(Duration::from_millis(700))
+        );
+        assert_eq!(wait(Duration::from_secs(2)), None);
+        assert_eq!(wait(Duration::ZERO), Some(Duration::from_millis(100)));
+    }
+
+    #[test]
+    fn retry_after_parses_seconds_and_dates() {
+        let now = httpdate::parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT").unwrap();
+        assert_eq!(
+            parse_retry_after(" 90 ", now),
+            Some(Duration::from_secs(90))
+        );
+        assert_eq!(
+            parse_retry_after("Sun, 06 Nov 1994 08:50:07 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            parse_retry_after("Sun, 06 Nov 1994 08:00:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        for invalid in ["", "-1", "1.5", "soon"] {
+            assert_eq!(parse_retry_after(invalid, now), None, "{invalid}");
+        }
+    }
+
+    #[test]
+    fn policy_values_parse() {
+        assert!(("0.25".parse::<Jitter>().unwrap().get() - 0.25).abs() < f64::EPSILON);
+        for invalid in ["-0.1", "1.5", "half"] {
+            let error = invalid.parse::<Jitter>().unwrap_err();
+            assert!(error.to_string().contains("jitter"), "{error}");
+        }
+        assert_eq!(
+            " tls".parse::<RetryableError>().unwrap(),
+            RetryableError::Tls
+        );
+        let error = "http".parse::<RetryableError>().unwrap_err();
+        assert!(error.to_string().starts_with("unknown retryable error"));
+    }
+
+    #[test]
+    fn only_listed_statuses_and_errors_are_retried() {
+        let policy = Policy::default();
+        assert!(policy.retries_status(503));
+        assert!(!policy.retries_status(500));
+        assert!(!policy.retries_error(&FetchError::Cancelled));
+        assert!(!policy.retries_error(&FetchError::InvalidUrl(String::new())));
+        assert!(!policy.retries_error(&FetchError::BodyTooLarge(1_024)));
+    }
+}
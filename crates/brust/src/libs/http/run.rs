@@ -0,0 +1,364 @@
+//! Fetching a list of URLs end to end, as `brust fetch` does: collecting
+//! the URLs, building their requests, saving a response body and summing up
+//! the failures.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use reqwest::Method;
+
+use super::{Client, ClientOptions, FetchError, FetchResult, Fetched, Header, Request};
+use super::{read_body, read_url_file};
+use crate::libs::cancellation::CancelToken;
+use crate::telemetry::metrics::Meters;
+
+/// Why a fetch run could not start.
+#[derive(Debug)]
+pub enum SetupError {
+    /// The URL file and the request body would both be read from stdin.
+    StdinTwice,
+    /// No URL was given.
+    NoUrl,
+    /// The URL file could not be read.
+    UrlFile {
+        /// Path of the URL file.
+        path: PathBuf,
+        /// Why reading it failed.
+        source: io::Error,
+    },
+    /// The request body could not be read.
+    Body(io::Error),
+    /// A response body was to be saved for this many URLs instead of one.
+    SaveMany(usize),
+    /// The file to save the response body to could not be created.
+    SaveCreate {
+        /// Path of the file.
+        path: PathBuf,
+        /// Why creating it failed.
+        source: io::Error,
+    },
+}
+
+impl fmt::Display for SetupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::StdinTwice => write!(
+                f,
+                "the URL file and the request body cannot both be read from stdin"
+            ),
+            Self::NoUrl => write!(f, "no URL given"),
+            Self::UrlFile { path, source } => {
+                write!(f, "failed to read URL file: {}: {source}", path.display())
+            }
+            Self::Body(e) => write!(f, "failed to read request body: {e}"),
+            Self::SaveMany(urls) => {
+                write!(f, "a response body is saved for a single URL, got {urls}")
+            }
+            Self::SaveCreate { path, source } => {
+                write!(f, "failed to create {}: {source}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for SetupError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::StdinTwice | Self::NoUrl | Self::SaveMany(_) => None,
+            Self::UrlFile { source, .. } | Self::SaveCreate { source, .. } => Some(source),
+            Self::Body(e) => Some(e),
+        }
+    }
+}
+
+/// URLs to fetch: `urls`, then those of the [URL file](read_url_file) at
+/// `url_file`, or `fallback` when there are none.
+///
+/// `data` is the [body spec](read_body) of the requests, checked so that
+/// stdin is not read twice.
+///
+/// # Errors
+///
+/// Returns [`SetupError::StdinTwice`] if both `url_file` and `data` read
+/// stdin, [`SetupError::UrlFile`] if the URL file cannot be read and
+/// [`SetupError::NoUrl`] if there is no URL at all.
+pub fn collect_urls(
+    urls: &[String],
+    url_file: Option<&Path>,
+    data: Option<&str>,
+    fallback: Option<&str>,
+) -> Result<Vec<String>, SetupError> {
+    if url_file == Some(Path::new("-")) && data == Some("@-") {
+        return Err(SetupError::StdinTwice);
+    }
+    let mut urls = urls.to_vec();
+    if let Some(path) = url_file {
+        urls.extend(read_url_file(path).map_err(|source| SetupError::UrlFile {
+            path: path.to_owned(),
+            source,
+        })?);
+    }
+    if urls.is_empty() {
+        urls.extend(fallback.map(String::from));
+    }
+    if urls.is_empty() {
+        return Err(SetupError::NoUrl);
+    }
+    Ok(urls)
+}
+
+/// One request per URL of `urls` with `method`, `headers` and the body of
+/// the [body spec](read_body) `data`, which is read once.
+///
+/// # Errors
+///
+/// Returns [`SetupError::Body`] if the body file or stdin cannot be read.
+pub fn build_requests(
+    urls: &[String],
+    method: Option<&Method>,
+    headers: &[Header],
+    data: Option<&str>,
+) -> Result<Vec<Request>, SetupError> {
+    let body = data.map(read_body).transpose().map_err(SetupError::Body)?;
+    Ok(urls
+        .iter()
+        .map(|url| Request::from_parts(method.cloned(), url, headers, body.as_deref()))
+        .collect())
+}
+
+/// Where [`run_fetch`] writes response bodies.
+#[derive(Debug)]
+pub enum Save {
+    /// To the run's output, in place of the result lines.
+    Stdout,
+    /// To a file, created or truncated before the request.
+    File(BufWriter<File>),
+}
+
+impl Save {
+    /// Destination for the response body of the single URL of a run of
+    /// `urls` URLs: the file at `path`, or the output for `-`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SetupError::SaveMany`] for more than one URL and
+    /// [`SetupError::SaveCreate`] if the file cannot be created.
+    pub fn open(path: &Path, urls: usize) -> Result<Self, SetupError> {
+        if urls > 1 {
+            return Err(SetupError::SaveMany(urls));
+        }
+        if path == Path::new("-") {
+            return Ok(Self::Stdout);
+        }
+        let file = File::create(path).map_err(|source| SetupError::SaveCreate {
+            path: path.to_owned(),
+            source,
+        })?;
+        Ok(Self::File(BufWriter::new(file)))
+    }
+}
+
+/// Fetch `requests` in turn with one client built from `options`, until
+/// `cancel` fires.
+///
+/// Passes the status and latency of each completed request to `line`, along
+/// with `out`, and writes response bodies as `save` says: to `out` for
+/// [`Save::Stdout`], which then gets no lines.
+///
+/// # Errors
+///
+/// Returns [`FetchError::Client`] if the client cannot be built.
+pub fn run_fetch<O: Write>(
+    requests: &[Request],
+    options: ClientOptions,
+    meters: &Meters,
+    cancel: &CancelToken,
+    mut save: Option<Save>,
+    out: &mut O,
+    mut line: impl FnMut(&mut O, fmt::Arguments<'_>),
+) -> Result<Fetched, FetchError> {
+    let client = Client::new(options)?;
+    let mut print = |out: &mut O, request: &Request, result: &FetchResult| {
+        let retried = match result.attempts {
+            0 | 1 => String::new(),
+            attempts => format!(" after {attempts} attempts"),
+        };
+        line(
+            out,
+            format_args!(
+                "{} {} in {:.3} s{retried}",
+                result.status,
+                request.url(),
+                result.duration_s
+            ),
+        );
+    };
+    Ok(match save {
+        None => client.fetch_all(requests, meters, cancel, None, |request, result| {
+            print(out, request, result);
+        }),
+        Some(Save::File(ref mut file)) => {
+            client.fetch_all(requests, meters, cancel, Some(file), |request, result| {
+                print(out, request, result);
+            })
+        }
+        Some(Save::Stdout) => client.fetch_all(requests, meters, cancel, Some(out), |_, _| {}),
+    })
+}
+
+/// Failed requests of a fetch run.
+#[derive(Debug, Clone, Copy)]
+pub struct Failures<'a> {
+    /// Error of the first request that failed.
+    pub first: &'a FetchError,
+    /// Number of requests that failed.
+    pub failed: usize,
+    /// Number of requests that were sent.
+    pub total: usize,
+}
+
+impl fmt::Display for Failures<'_> {
+    /// The error of the only request or a cancellation, otherwise a count of
+    /// the failed requests with the error of the first.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.total == 1 || matches!(self.first, FetchError::Cancelled) {
+            return write!(f, "{}", self.first);
+        }
+        write!(
+            f,
+            "{} of {} URLs could not be fetched; first: {}",
+            self.failed, self.total, self.first
+        )
+    }
+}
+
+impl std::error::Error for Failures<'_> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.first)
+    }
+}
+
+/// The failures of a fetch run, if any request failed. The `error.type` of
+/// the first is recorded on the current span.
+///
+/// # Errors
+///
+/// Returns the [`Failures`] when at least one request failed.
+pub fn fetch_outcome(fetched: &Fetched) -> Result<(), Failures<'_>> {
+    let mut failed = fetched
+        .iter()
+        .filter_map(|(_, result)| result.as_ref().err());
+    let Some(first) = failed.next() else {
+        return Ok(());
+    };
+    tracing::Span::current().record("error.type", first.error_type());
+    Err(Failures {
+        first,
+        failed: failed.count().saturating_add(1),
+        total: fetched.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use std::path::Path;
+
+    use reqwest::Method;
+
+    use super::{Save, SetupError, build_requests, collect_urls, fetch_outcome};
+    use crate::libs::http::{FetchError, FetchResult, Request};
+
+    fn urls(urls: &[&str]) -> Vec<String> {
+        urls.iter().map(|&url| String::from(url)).collect()
+    }
+
+    #[test]
+    fn collect_urls_appends_the_url_file_and_falls_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("urls.txt");
+        std::fs::write(&path, "http://b.test/\n\n# comment\nhttp://c.test/\n").unwrap();
+
+        let given = urls(&["http://a.test/"]);
+        let collected = collect_urls(&given, Some(&path), None, Some("http://x.test/")).unwrap();
+        assert_eq!(
+            collected,
+            urls(&["http://a.test/", "http://b.test/", "http://c.test/"])
+        );
+        let collected = collect_urls(&[], None, None, Some("http://x.test/")).unwrap();
+        assert_eq!(collected, urls(&["http://x.test/"]));
+
+        let missing = dir.path().join("missing.txt");
+        let error = collect_urls(&given, Some(&missing), None, None).unwrap_err();
+        assert!(matches!(error, SetupError::UrlFile { .. }), "{error}");
+        let error = collect_urls(&[], None, None, None).unwrap_err();
+        assert!(matches!(error, SetupError::NoUrl));
+        let error = collect_urls(&[], Some(Path::new("-")), Some("@-"), None).unwrap_err();
+        assert!(matches!(error, SetupError::StdinTwice));
+    }
+
+    #[test]
+    fn build_requests_share_the_method_headers_and_body() {
+        let given = urls(&["http://a.test/", "http://b.test/"]);
+        let headers = ["X-Test: 1".parse().unwrap()];
+        let requests = build_requests(&given, None, &headers, Some("{}")).unwrap();
+        assert_eq!(requests.len(), 2);
+        for (request, url) in requests.iter().zip(&given) {
+            assert_eq!(request.method(), &Method::POST);
+            assert_eq!(request.url(), url);
+            assert_eq!(request.body(), Some(b"{}".as_slice()));
+        }
+
+        let error = build_requests(&given, Some(&Method::PUT), &[], Some("@/nonexistent/body"))
+            .unwrap_err();
+        assert!(matches!(error, SetupError::Body(_)), "{error}");
+    }
+
+    #[test]
+    fn save_takes_a_single_url() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("body");
+        let error = Save::open(&path, 2).unwrap_err();
+        assert!(matches!(error, SetupError::SaveMany(2)));
+        assert!(!path.exists());
+
+        assert!(matches!(Save::open(Path::new("-"), 1), Ok(Save::Stdout)));
+        assert!(matches!(Save::open(&path, 1), Ok(Save::File(_))));
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn fetch_outcome_counts_the_failed_requests() {
+        let ok = FetchResult {
+            status: 200,
+            duration_s: 0.1,
+            host: String::from("a.test"),
+            scheme: String::from("http"),
+            attempts: 1,
+            reused_connection: false,
+            body_size: 2,
+        };
+        let request = |url: &str| Request::get(url);
+        let mut fetched = vec![(request("http://a.test/"), Ok(ok))];
+        assert!(fetch_outcome(&fetched).is_ok());
+
+        fetched.push((request("http://b.test/"), Err(FetchError::Panicked)));
+        fetched.push((request("http://c.test/"), Err(FetchError::Cancelled)));
+        let failures = fetch_outcome(&fetched).unwrap_err();
+        assert_eq!((failures.failed, failures.total), (2, 3));
+        assert_eq!(
+            failures.to_string(),
+            format!(
+                "2 of 3 URLs could not be fetched; first: {}",
+                FetchError::Panicked
+            )
+        );
+
+        let only = vec![(request("http://b.test/"), Err(FetchError::Panicked))];
+        let failures = fetch_outcome(&only).unwrap_err();
+        assert_eq!(failures.to_string(), FetchError::Panicked.to_string());
+    }
+}
@@ -0,0 +1,310 @@
+//! Human-readable durations, rates and byte sizes for command-line and
+//! config values.
+
+use std::fmt;
+use std::num::NonZeroU32;
+use std::str::FromStr;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+/// Units accepted by [`HumanDuration`], largest first.
+const UNITS: [(&str, Duration); 4] = [
+    ("h", Duration::from_hours(1)),
+    ("m", Duration::from_mins(1)),
+    ("s", Duration::from_secs(1)),
+    ("ms", Duration::from_millis(1)),
+];
+
+/// A length of time written as whole numbers of `h`, `m`, `s` and `ms`,
+/// such as `10m`, `1h30m` or `250ms`.
+///
+/// [`Display`](fmt::Display) writes the shortest such form back, e.g. `90s`
+/// as `1m30s`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(try_from = "String")]
+pub struct HumanDuration(pub Duration);
+
+impl fmt::Display for HumanDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_zero() {
+            return f.write_str("0s");
+        }
+        let mut left = self.0.as_millis();
+        for (unit, size) in UNITS {
+            let size = size.as_millis();
+            let whole = left.checked_div(size).unwrap_or_default();
+            if whole > 0 {
+                write!(f, "{whole}{unit}")?;
+                left = left.checked_rem(size).unwrap_or_default();
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Error returned when a duration or rate cannot be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnitError(String);
+
+impl fmt::Display for UnitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for UnitError {}
+
+impl FromStr for HumanDuration {
+    type Err = UnitError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            UnitError(format!(
+                "invalid duration {s:?} (expected e.g. 30s, 10m, 1h30m or 250ms)"
+            ))
+        };
+        let mut rest = s.trim();
+        if rest.is_empty() {
+            return Err(invalid());
+        }
+        let mut total = Duration::ZERO;
+        while !rest.is_empty() {
+            let digits = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            let (number, tail) = rest.split_at(digits);
+            let number: u32 = number.parse().map_err(|_| invalid())?;
+            let letters = tail
+                .find(|c: char| c.is_ascii_digit())
+                .unwrap_or(tail.len());
+            let (unit, tail) = tail.split_at(letters);
+            let size = UNITS
+                .iter()
+                .find(|(name, _)| *name == unit)
+                .map(|&(_, size)| size)
+                .ok_or_else(invalid)?;
+            total = size
+                .checked_mul(number)
+                .and_then(|part| total.checked_add(part))
+                .ok_or_else(invalid)?;
+            rest = tail;
+        }
+        Ok(Self(total))
+    }
+}
+
+impl TryFrom<String> for HumanDuration {
+    type Error = UnitError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// A number of events per period, written `COUNT/PERIOD` such as `20/s`,
+/// `5/m` or `3/10s`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Rate {
+    /// Events in each period.
+    pub count: NonZeroU32,
+    /// Length of the period; never zero.
+    pub per: Duration,
+}
+
+impl Rate {
+    /// Time between two consecutive events.
+    #[must_use]
+    pub fn interval(&self) -> Duration {
+        self.per.checked_div(self.count.get()).unwrap_or_default()
+    }
+}
+
+impl fmt::Display for Rate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let per = HumanDuration(self.per).to_string();
+        // A single unit is written without its `1`: `20/s`, not `20/1s`.
+        let per = per
+            .strip_prefix('1')
+            .filter(|unit| UNITS.iter().any(|(name, _)| name == unit));
+        match per {
+            Some(unit) => write!(f, "{}/{unit}", self.count),
+            None => write!(f, "{}/{}", self.count, HumanDuration(self.per)),
+        }
+    }
+}
+
+impl FromStr for Rate {
+    type Err = UnitError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            UnitError(format!(
+                "invalid rate {s:?} (expected COUNT/PERIOD, e.g. 20/s, 5/m or 3/10s)"
+            ))
+        };
+        let (count, per) = s.trim().split_once('/').ok_or_else(invalid)?;
+        let count = count.trim().parse().map_err(|_| invalid())?;
+        let per = per.trim();
+        let per = if per.starts_with(|c: char| c.is_ascii_digit()) {
+            per.parse::<HumanDuration>().map_err(|_| invalid())?.0
+        } else {
+            format!("1{per}")
+                .parse::<HumanDuration>()
+                .map_err(|_| invalid())?
+                .0
+        };
+        if per.is_zero() {
+            return Err(invalid());
+        }
+        Ok(Self { count, per })
+    }
+}
+
+impl TryFrom<String> for Rate {
+    type Error = UnitError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// Units accepted by [`ByteSize`], largest first.
+const BYTE_UNITS: [(&str, u64); 7] = [
+    ("GiB", 1 << 30),
+    ("GB", 1_000_000_000),
+    ("MiB", 1 << 20),
+    ("MB", 1_000_000),
+    ("KiB", 1 << 10),
+    ("KB", 1_000),
+    ("B", 1),
+];
+
+/// A number of bytes written as a whole number with an optional unit, such
+/// as `512`, `64KiB`, `10MB` or `1GiB`.
+///
+/// [`Display`](fmt::Display) writes the largest unit that divides the size
+/// exactly, e.g. `1048576` as `1MiB`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(try_from = "String")]
+pub struct ByteSize(pub u64);
+
+impl fmt::Display for ByteSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (unit, size) = BYTE_UNITS
+            .into_iter()
+            .find(|&(_, size)| self.0 > 0 && self.0.checked_rem(size) == Some(0))
+            .unwrap_or(("B", 1));
+        write!(f, "{}{unit}", self.0.checked_div(size).unwrap_or_default())
+    }
+}
+
+impl FromStr for ByteSize {
+    type Err = UnitError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            UnitError(format!(
+                "invalid size {s:?} (expected bytes, e.g. 512, 64KiB, 10MB or 1GiB)"
+            ))
+        };
+        let text = s.trim();
+        let digits = text
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(text.len());
+        let (number, unit) = text.split_at(digits);
+        let number: u64 = number.parse().map_err(|_| invalid())?;
+        let size = if unit.is_empty() {
+            1
+        } else {
+            BYTE_UNITS
+                .iter()
+                .find(|(name, _)| *name == unit)
+                .map(|&(_, size)| size)
+                .ok_or_else(invalid)?
+        };
+        number.checked_mul(size).map(Self).ok_or_else(invalid)
+    }
+}
+
+impl TryFrom<String> for ByteSize {
+    type Error = UnitError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    #[test]
+    fn durations_parse_and_print_in_shortest_form() {
+        for (text, expected, shown) in [
+            ("10m", Duration::from_mins(10), "10m"),
+            ("1h30m", Duration::from_mins(90), "1h30m"),
+            ("90s", Duration::from_secs(90), "1m30s"),
+            ("250ms", Duration::from_millis(250), "250ms"),
+            ("1s500ms", Duration::from_millis(1_500), "1s500ms"),
+            ("0s", Duration::ZERO, "0s"),
+        ] {
+            let parsed: HumanDuration = text.parse().unwrap();
+            assert_eq!(parsed.0, expected, "{text}");
+            assert_eq!(parsed.to_string(), shown);
+        }
+    }
+
+    #[test]
+    fn invalid_durations_are_rejected() {
+        for text in ["", "10", "m", "1.5s", "5d", "-1s", "1h 30m"] {
+            let error = text.parse::<HumanDuration>().unwrap_err();
+            assert!(error.to_string().starts_with("invalid duration"), "{text}");
+        }
+    }
+
+    #[test]
+    fn rates_parse_and_print() {
+        let rate: Rate = "20/s".parse().unwrap();
+        assert_eq!(rate.interval(), Duration::from_millis(50));
+        assert_eq!(rate.to_string(), "20/s");
+
+        let rate: Rate = "3/10s".parse().unwrap();
+        assert_eq!(rate.per, Duration::from_secs(10));
+        assert_eq!(rate.to_string(), "3/10s");
+
+        assert_eq!("5/m".parse::<Rate>().unwrap().to_string(), "5/m");
+        assert_eq!("1/1h".parse::<Rate>().unwrap().to_string(), "1/h");
+    }
+
+    #[test]
+    fn invalid_rates_are_rejected() {
+        for text in ["20", "0/s", "20/", "20/0s", "x/s", "20/d"] {
+            let error = text.parse::<Rate>().unwrap_err();
+            assert!(error.to_string().starts_with("invalid rate"), "{text}");
+        }
+    }
+
+    #[test]
+    fn byte_sizes_parse_and_print_in_largest_unit() {
+        for (text, expected, shown) in [
+            ("512", 512, "512B"),
+            ("64KiB", 65_536, "64KiB"),
+            ("10MB", 10_000_000, "10MB"),
+            ("1GiB", 1 << 30, "1GiB"),
+            ("1048576B", 1 << 20, "1MiB"),
+            ("0", 0, "0B"),
+        ] {
+            let parsed: ByteSize = text.parse().unwrap();
+            assert_eq!(parsed.0, expected, "{text}");
+            assert_eq!(parsed.to_string(), shown);
+        }
+        for text in ["", "MB", "1.5MB", "10mb", "-1", "20000000000GiB"] {
+            let error = text.parse::<ByteSize>().unwrap_err();
+            assert!(error.to_string().starts_with("invalid size"), "{text}");
+        }
+    }
+}
@@ -1,11 +1,25 @@
 //! Brust - Rust ボイラープレートプロジェクト
 
-/// ライブラリモジュール群
-pub mod libs;
-/// OpenTelemetry instrumentation (metrics, future: tracing, logs)
-mod telemetry;
+/// CLI argument definitions
+mod cli;
+/// Layered configuration (defaults, TOML file, env vars, CLI flags)
+mod config;
+/// Typed command errors and exit codes
+mod error;
+/// Shell completion and man page generation
+mod generate;
+/// User-facing command output (text, JSON, YAML)
+mod output;
+/// Ctrl-C / SIGTERM handling
+mod signal;
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::process::ExitCode;
+
+use tracing::field::Empty;
 
-use clap::Parser;
 use tracing_subscriber::filter::EnvFilter;
 #[cfg(not(feature = "otel"))]
 use tracing_subscriber::fmt;
@@ -14,75 +28,429 @@ use tracing_subscriber::layer::SubscriberExt;
 #[cfg(feature = "otel")]
 use tracing_subscriber::util::SubscriberInitExt;
 
-use crate::libs::count;
-use crate::libs::hello::{GreetingError, sayhello};
-use crate::libs::http;
-use crate::telemetry::metrics::Meters;
-
-#[derive(Parser)]
-#[command(about, version = APP_VERSION)]
-struct Args {
-    /// Name of the person to greet
-    #[arg(short, long, default_value = "Youre")]
-    name: String,
-    /// Gender for greeting (man, woman)
-    #[arg(short, long)]
-    gender: Option<String>,
-    /// Number of iterations to run with random delays (metrics demo)
-    #[arg(short = 'c', long = "count")]
-    count: Option<u32>,
-    /// URL to fetch via HTTP GET (HTTP client metrics demo)
-    #[arg(short = 'u', long = "url")]
-    url: Option<String>,
-}
+use brust::libs::batch;
+use brust::libs::count;
+use brust::libs::hello::honorific::{Gender, Honorific};
+use brust::libs::hello::locale::Locale;
+use brust::libs::hello::template::Template;
+use brust::libs::http;
+use brust::{CancelToken, Greeter, GreetingError, Meters, SystemClock};
+
+use crate::cli::{Cli, Commands, ConfigCommand, FetchArgs, LegacyArgs, Verbosity};
+use crate::config::{Config, CountConfig};
+use crate::error::{CliError, ErrorKind};
+use crate::output::{
+    CommandResult, CountOutput, Document, FetchOutput, FetchedUrl, GreetOutput, Output,
+    OutputFormat,
+};
 
 const APP_VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), " (rev:", env!("GIT_HASH"), ")",);
 
-fn main() {
+fn main() -> ExitCode {
     // Install TLS crypto provider for reqwest (required by rustls-no-provider feature).
     // Ignored if a provider is already installed (e.g., across tests).
     let _ = rustls::crypto::ring::default_provider().install_default();
 
+    let cli = Cli::parse_args();
+
+    // Telemetry settings come from the config, so load it before initializing
+    // tracing; a load error is reported once logging is available.
+    let (config, config_error) = match Config::load(&cli) {
+        Ok(config) => (config, None),
+        Err(e) => (
+            Config::default(),
+            Some(CliError::new(
+                ErrorKind::InvalidInput,
+                format!("failed to load configuration: {e:#}"),
+            )),
+        ),
+    };
+
+    // stdout carries command results only; diagnostics always go to stderr.
     #[cfg(not(feature = "otel"))]
     {
         fmt()
-            .with_env_filter(
-                EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
-            )
+            .with_writer(io::stderr)
+            .with_env_filter(env_filter(cli.verbosity, ""))
             .init();
     }
 
     #[cfg(feature = "otel")]
-    let otel_providers = init_otel();
+    let (otel_providers, telemetry_error) =
+        init_otel(&config.telemetry, config.exporter_endpoint(), cli.verbosity);
+    #[cfg(not(feature = "otel"))]
+    let telemetry_error: Option<CliError> = None;
 
     // Create metric instruments after the global MeterProvider is set up.
     let meters = Meters::default();
 
-    let args = Args::parse();
+    // Only count and fetch stop early; other commands keep the default
+    // signal behavior.
+    let cancel = CancelToken::new();
+    if matches!(
+        cli.command,
+        None | Some(Commands::Count(_) | Commands::Fetch(_))
+    ) {
+        signal::install(cancel.clone());
+    }
 
-    {
-        // Root span wraps all command processing so child spans (run, run_count,
-        // HTTP fetch) share a single trace_id and errors are captured in context.
-        let root = tracing::info_span!("main");
-        let _guard = root.enter();
+    // Each command runs inside its own root span; the span is closed when the
+    // command returns, before OTel shutdown.
+    // Telemetry is not required to run a command: without exporters it still
+    // runs, and the telemetry error only decides the exit code on success.
+    if let Some(ref e) = telemetry_error {
+        tracing::warn!("{}; running without telemetry export", e.message);
+    }
+    let mut output = Output::new(cli.output_format(), io::stdout().lock());
+    let result = config_error.map_or_else(
+        || match &cli.command {
+            Some(command) => run_command(command, &config, &meters, &cancel, &mut output),
+            None => run_legacy(&cli.legacy, &config, &meters, &cancel, &mut output),
+        },
+        Err,
+    );
+    let result = result.and_then(|()| {
+        if output.failed() {
+            Err(CliError::new(
+                ErrorKind::Failure,
+                "failed to write to stdout",
+            ))
+        } else {
+            Ok(())
+        }
+    });
+    let result = result.and_then(|()| telemetry_error.map_or(Ok(()), Err));
+    drop(output);
+
+    let exit_code = result.map_or_else(
+        |e| {
+            // Nothing is left to report a failed stderr write to.
+            let _ = error::report(&e, cli.error_format, io::stderr().lock());
+            e.exit_code()
+        },
+        |()| ExitCode::SUCCESS,
+    );
 
-        run(&args.name, args.gender.as_deref(), &meters);
+    #[cfg(feature = "otel")]
+    shutdown_otel(otel_providers);
 
-        if let Some(count) = args.count {
-            run_count(count, &meters);
-        }
+    exit_code
+}
 
-        if let Some(ref url) = args.url {
-            let start = std::time::Instant::now();
-            if let Err(e) = http::fetch_url(url, &meters) {
-                tracing::error!("HTTP fetch failed: {e:#}");
+/// Run a subcommand inside a root span named after it.
+///
+/// Argument values are taken from the merged `config`, which already includes
+/// the subcommand's flags. Results are written to `output`. Count and fetch
+/// stop early when `cancel` fires.
+///
+/// # Errors
+///
+/// Returns the [`CliError`] that decides the exit code when the command fails.
+fn run_command(
+    command: &Commands,
+    config: &Config,
+    meters: &Meters,
+    cancel: &CancelToken,
+    output: &mut Output<impl Write>,
+) -> Result<(), CliError> {
+    match command {
+        Commands::Greet(_) => {
+            let root = tracing::info_span!("greet");
+            let _guard = root.enter();
+            let honorific = config.greet.honorific();
+            let locale = resolve_locale(config);
+            let result = run(
+                &config.greet.name,
+                honorific.as_ref(),
+                locale,
+                config.greet.template.as_deref(),
+                meters,
+                output,
+            );
+            let document = Document::new(
+                CommandResult::Greet(GreetOutput {
+                    name: config.greet.name.clone(),
+                    honorific: honorific.as_ref().map(Honorific::metric_value),
+                    locale: locale.as_str(),
+                    greeting: result.as_ref().ok().cloned(),
+                }),
+                result.as_ref().err().map(ToString::to_string),
+            );
+            output.document(&document);
+            result.map(drop).map_err(CliError::from)
+        }
+        Commands::Count(args) => {
+            let root = tracing::info_span!("count", error.r#type = Empty);
+            let _guard = root.enter();
+            count_command(config, args.csv.as_deref(), meters, cancel, output)
+        }
+        Commands::Fetch(args) => {
+            let root = tracing::info_span!("fetch", error.r#type = Empty);
+            let _guard = root.enter();
+            let fetched = fetch_plan(args, config, output.format()).and_then(|(requests, save)| {
+                http::run_fetch(
+                    &requests,
+                    config.http.client_options(),
+                    meters,
+                    cancel,
+                    save,
+                    output,
+                    |output, line| output.line(line),
+                )
+                .map_err(CliError::from)
+            });
+            let result = fetched
+                .as_ref()
+                .map_err(Clone::clone)
+                .and_then(|fetched| http::fetch_outcome(fetched).map_err(CliError::from))
+                .inspect_err(record_cancelled);
+            let method = args
+                .method
+                .clone()
+                .unwrap_or_else(|| http::Request::default_method(args.data.is_some()));
+            let requests = fetched
+                .unwrap_or_default()
+                .iter()
+                .map(|(request, result)| FetchedUrl::new(request.url(), result.as_ref()))
+                .collect();
+            let document = Document::new(
+                CommandResult::Fetch(FetchOutput::new(method.as_str(), requests)),
+                result.as_ref().err().map(ToString::to_string),
+            );
+            output.document(&document);
+            result
+        }
+        Commands::Batch(args) => {
+            let root = tracing::info_span!("batch");
+            let _guard = root.enter();
+            let failed = batch::run(
+                args.input.as_deref(),
+                args.format,
+                resolve_locale(config),
+                config.greet.template.as_deref(),
+                meters,
+                output.writer(),
+            )?;
+            match failed {
+                0 => Ok(()),
+                failed => Err(CliError::new(
+                    ErrorKind::InvalidInput,
+                    format!("{failed} batch records could not be greeted"),
+                )),
             }
-            meters.record_run_duration(start.elapsed().as_secs_f64(), "http");
         }
-    } // _guard dropped here: root span exits before OTel shutdown
+        Commands::Config(ConfigCommand::Show) => {
+            output.print(&config.render());
+            Ok(())
+        }
+        Commands::Completions(args) => {
+            output.print(&generate::completions(args.shell));
+            Ok(())
+        }
+        Commands::Man(args) => run_man(&args.out_dir, output),
+    }
+}
 
-    #[cfg(feature = "otel")]
-    shutdown_otel(otel_providers);
+/// Run `brust count`, print its document and export the iterations to `csv`
+/// if given; partial results are kept when `cancel` fires.
+fn count_command(
+    config: &Config,
+    csv: Option<&Path>,
+    meters: &Meters,
+    cancel: &CancelToken,
+    output: &mut Output<impl Write>,
+) -> Result<(), CliError> {
+    let settings = &config.count;
+    let limited = settings.is_limited();
+    let counted = if limited {
+        let clock = SystemClock::new();
+        let (results, wall) =
+            count::run(settings, &clock, cancel, meters, |line| output.line(line));
+        let counted = CountOutput::new(settings, &results, wall);
+        output.line(&counted);
+        counted
+    } else {
+        CountOutput::new(settings, &[], std::time::Duration::ZERO)
+    };
+    let exported = match csv {
+        Some(path) if limited => write_count_csv(&counted, path),
+        _ => Ok(()),
+    };
+    let error = if limited {
+        cancel
+            .is_cancelled()
+            .then(|| count_cancelled(counted.iterations.len(), settings.iterations))
+    } else {
+        Some(CliError::new(
+            ErrorKind::Usage,
+            "no iteration count or duration given; pass --count or --duration, \
+             or set count.iterations or count.duration",
+        ))
+    }
+    .or_else(|| exported.err());
+    let document = Document::new(
+        CommandResult::Count(counted),
+        error.as_ref().map(ToString::to_string),
+    );
+    output.document(&document);
+    error.map_or(Ok(()), Err)
+}
+
+/// Write the iterations of `counted` to the CSV file at `path`.
+fn write_count_csv(counted: &CountOutput, path: &Path) -> Result<(), CliError> {
+    File::create(path)
+        .map_err(csv::Error::from)
+        .and_then(|file| counted.write_csv(BufWriter::new(file)))
+        .map_err(|e| {
+            CliError::new(
+                ErrorKind::Failure,
+                format!("failed to write CSV to {}: {e}", path.display()),
+            )
+        })
+}
+
+/// Write the man pages into `dir` and list the written files on `output`.
+fn run_man(dir: &Path, output: &mut Output<impl Write>) -> Result<(), CliError> {
+    let paths = generate::write_man_pages(dir).map_err(|e| {
+        CliError::new(
+            ErrorKind::Failure,
+            format!("failed to write man pages to {}: {e}", dir.display()),
+        )
+    })?;
+    for path in paths {
+        output.line(path.display());
+    }
+    Ok(())
+}
+
+/// Run the deprecated flat-flag path: greet, then count and fetch if requested.
+///
+/// Count and fetch only run when their flags are given, as before subcommands
+/// existed. Every step runs even if an earlier one failed, unless `cancel`
+/// fires.
+///
+/// # Errors
+///
+/// Returns the first failure among the greeting, a cancelled count and the
+/// fetch.
+fn run_legacy(
+    args: &LegacyArgs,
+    config: &Config,
+    meters: &Meters,
+    cancel: &CancelToken,
+    output: &mut Output<impl Write>,
+) -> Result<(), CliError> {
+    // Root span wraps all command processing so child spans (run, run_count,
+    // HTTP fetch) share a single trace_id and errors are captured in context.
+    let root = tracing::info_span!("main", error.r#type = Empty);
+    let _guard = root.enter();
+
+    tracing::warn!(
+        "running without a subcommand is deprecated; use `brust greet`, `brust count` or `brust fetch`"
+    );
+
+    let locale = resolve_locale(config);
+    let greeted = match args.gender.as_deref().map(str::parse::<Gender>).transpose() {
+        Ok(_) => run(
+            &config.greet.name,
+            config.greet.honorific().as_ref(),
+            locale,
+            config.greet.template.as_deref(),
+            meters,
+            output,
+        ),
+        Err(e) => {
+            Greeter::new(locale).record(meters, Some(&e));
+            output.line(format_greeting(&config.greet.name, locale, Err(e.clone())));
+            Err(e)
+        }
+    };
+
+    let counted = args.count.map_or(Ok(()), |iterations| {
+        let settings = CountConfig {
+            iterations: Some(iterations),
+            ..config.count.clone()
+        };
+        let (results, wall) = count::run(&settings, &SystemClock::new(), cancel, meters, |line| {
+            output.line(line);
+        });
+        output.line(CountOutput::new(&settings, &results, wall));
+        if cancel.is_cancelled() {
+            Err(count_cancelled(results.len(), Some(iterations)))
+        } else {
+            Ok(())
+        }
+    });
+
+    let fetched = args.url.as_deref().map_or(Ok(()), |url| {
+        http::run_fetch(
+            &[http::Request::get(url)],
+            config.http.client_options(),
+            meters,
+            cancel,
+            None,
+            output,
+            |output, line| output.line(line),
+        )
+        .map_err(CliError::from)
+        .and_then(|fetched| http::fetch_outcome(&fetched).map_err(CliError::from))
+        .inspect_err(record_cancelled)
+    });
+
+    greeted
+        .map(drop)
+        .map_err(CliError::from)
+        .and(counted)
+        .and(fetched)
+}
+
+/// A [`ErrorKind::Cancelled`] error, recorded as `error.type=cancelled` on
+/// the current span.
+fn cancelled(message: String) -> CliError {
+    let error = CliError::new(ErrorKind::Cancelled, message);
+    record_cancelled(&error);
+    error
+}
+
+/// The error for a count cancelled after `done` of `count` iterations.
+fn count_cancelled(done: usize, count: Option<u32>) -> CliError {
+    cancelled(count.map_or_else(
+        || format!("count cancelled after {done} iterations"),
+        |count| format!("count cancelled after {done} of {count} iterations"),
+    ))
+}
+
+/// Set `error.type=cancelled` on the current span if `error` is a
+/// cancellation.
+fn record_cancelled(error: &CliError) {
+    if error.kind == ErrorKind::Cancelled {
+        tracing::Span::current().record("error.type", "cancelled");
+    }
+}
+
+/// Build the log filter: `--quiet`/`--verbose` win over `RUST_LOG`, which
+/// wins over the `info` default. `directives` is appended to either level.
+fn env_filter(verbosity: Verbosity, directives: &str) -> EnvFilter {
+    verbosity.level().map_or_else(
+        || {
+            EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| EnvFilter::new(format!("info{directives}")))
+        },
+        |level| EnvFilter::new(format!("{level}{directives}")),
+    )
+}
+
+/// Resolve the greeting locale from `greet.locale`, then `LC_ALL`,
+/// `LC_MESSAGES` and `LANG`, warning when the configured tag is unsupported.
+fn resolve_locale(config: &Config) -> Locale {
+    if let Some(tag) = config.greet.locale.as_deref()
+        && Locale::from_tag(tag).is_none()
+    {
+        tracing::warn!("unsupported locale '{tag}', falling back to the environment");
+    }
+    Locale::resolve(config.greet.locale.as_deref(), |var| {
+        std::env::var(var).ok()
+    })
 }
 
 /// Providers returned by `OTel` initialization for shutdown.
@@ -95,13 +463,12 @@ type OtelProviders = (
 
 /// Build an `OTel` `Resource` for this process.
 ///
-/// `OTEL_SERVICE_NAME` env var overrides the compiled-in package name.
+/// `service_name` comes from `telemetry.service_name`, which defaults to the
+/// compiled-in package name and honours `OTEL_SERVICE_NAME`.
 #[cfg(feature = "otel")]
-fn build_resource() -> opentelemetry_sdk::Resource {
-    let service_name =
-        std::env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| String::from(env!("CARGO_PKG_NAME")));
+fn build_resource(service_name: &str) -> opentelemetry_sdk::Resource {
     opentelemetry_sdk::Resource::builder()
-        .with_service_name(service_name)
+        .with_service_name(service_name.to_owned())
         .with_attributes([
             opentelemetry::KeyValue::new(
                 opentelemetry_semantic_conventions::attribute::SERVICE_VERSION,
@@ -119,77 +486,120 @@ fn build_resource() -> opentelemetry_sdk::Resource {
         .build()
 }
 
+/// Set `endpoint` on an OTLP exporter builder, if there is one; otherwise the
+/// exporter resolves it from the `OTEL_EXPORTER_OTLP_*` variables.
+#[cfg(feature = "otel")]
+fn with_endpoint<B>(builder: B, endpoint: Option<String>) -> B
+where
+    B: opentelemetry_otlp::WithExportConfig,
+{
+    match endpoint {
+        Some(endpoint) => builder.with_endpoint(endpoint),
+        None => builder,
+    }
+}
+
 /// Initialize `OTel` tracing, logging, and metrics providers.
+///
+/// Export is enabled only when `telemetry.endpoint` is set. With an
+/// `exporter_endpoint`, each signal is sent to `{endpoint}/v1/{signal}` over
+/// OTLP/HTTP; without one the exporters resolve their endpoints from the
+/// `OTEL_EXPORTER_OTLP_*` variables. Log lines are printed to stderr at the
+/// level chosen by `verbosity`.
+///
+/// Logging is always initialized. If the exporters cannot be built, export
+/// stays disabled and the error is returned alongside the empty providers.
 #[cfg(feature = "otel")]
-fn init_otel() -> OtelProviders {
-    let env_filter = EnvFilter::try_from_default_env()
-        .unwrap_or_else(|_| EnvFilter::new("info,opentelemetry=off"));
-    let fmt_layer = tracing_subscriber::fmt::layer();
-
-    let (otel_trace_layer, tp, mp, lp, otel_log_layer) =
-        std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT")
-            .ok()
-            .filter(|ep| !ep.is_empty())
-            .and_then(|_| {
-                let resource = build_resource();
-
-                // --- Traces (batch: non-blocking, suitable for production) ---
-                let span_exporter = opentelemetry_otlp::SpanExporter::builder()
-                    .with_http()
-                    .build()
-                    .ok()?;
-                let tracer_provider = opentelemetry_sdk::trace::SdkTracerProvider::builder()
-                    .with_resource(resource.clone())
-                    .with_batch_exporter(span_exporter)
-                    .build();
-                opentelemetry::global::set_text_map_propagator(
-                    opentelemetry_sdk::propagation::TraceContextPropagator::new(),
-                );
-                opentelemetry::global::set_tracer_provider(tracer_provider.clone());
-                let tracer = opentelemetry::trace::TracerProvider::tracer(
-                    &tracer_provider,
-                    env!("CARGO_PKG_NAME"),
-                );
-                let trace_layer = tracing_opentelemetry::layer().with_tracer(tracer);
-
-                // --- Logs (batch: non-blocking) ---
-                let log_exporter = opentelemetry_otlp::LogExporter::builder()
-                    .with_http()
-                    .build()
-                    .ok()?;
-                let logger_provider = opentelemetry_sdk::logs::SdkLoggerProvider::builder()
-                    .with_resource(resource.clone())
-                    .with_batch_exporter(log_exporter)
-                    .build();
-                let log_layer =
-                    opentelemetry_appender_tracing::layer::OpenTelemetryTracingBridge::new(
-                        &logger_provider,
-                    );
-
-                // --- Metrics (PeriodicReader: exports every 5 s) ---
-                let metric_exporter = opentelemetry_otlp::MetricExporter::builder()
-                    .with_http()
-                    .build()
-                    .ok()?;
-                let metric_reader =
-                    opentelemetry_sdk::metrics::PeriodicReader::builder(metric_exporter)
-                        .with_interval(std::time::Duration::from_secs(5))
-                        .build();
-                let meter_provider = opentelemetry_sdk::metrics::SdkMeterProvider::builder()
-                    .with_resource(resource)
-                    .with_reader(metric_reader)
+fn init_otel(
+    telemetry: &config::TelemetryConfig,
+    exporter_endpoint: Option<&str>,
+    verbosity: Verbosity,
+) -> (OtelProviders, Option<CliError>) {
+    let env_filter = env_filter(verbosity, ",opentelemetry=off");
+    let fmt_layer = tracing_subscriber::fmt::layer().with_writer(io::stderr);
+
+    let layers = telemetry
+        .endpoint
+        .as_deref()
+        .filter(|ep| !ep.is_empty())
+        .map(|_| {
+            let endpoint = |signal: &str| {
+                exporter_endpoint.map(|ep| format!("{}/v1/{signal}", ep.trim_end_matches('/')))
+            };
+            let resource = build_resource(&telemetry.service_name);
+
+            // --- Traces (batch: non-blocking, suitable for production) ---
+            let span_exporter = with_endpoint(
+                opentelemetry_otlp::SpanExporter::builder().with_http(),
+                endpoint("traces"),
+            )
+            .build()?;
+            let tracer_provider = opentelemetry_sdk::trace::SdkTracerProvider::builder()
+                .with_resource(resource.clone())
+                .with_batch_exporter(span_exporter)
+                .build();
+            opentelemetry::global::set_text_map_propagator(
+                opentelemetry_sdk::propagation::TraceContextPropagator::new(),
+            );
+            opentelemetry::global::set_tracer_provider(tracer_provider.clone());
+            let tracer = opentelemetry::trace::TracerProvider::tracer(
+                &tracer_provider,
+                env!("CARGO_PKG_NAME"),
+            );
+            let trace_layer = tracing_opentelemetry::layer().with_tracer(tracer);
+
+            // --- Logs (batch: non-blocking) ---
+            let log_exporter = with_endpoint(
+                opentelemetry_otlp::LogExporter::builder().with_http(),
+                endpoint("logs"),
+            )
+            .build()?;
+            let logger_provider = opentelemetry_sdk::logs::SdkLoggerProvider::builder()
+                .with_resource(resource.clone())
+                .with_batch_exporter(log_exporter)
+                .build();
+            let log_layer = opentelemetry_appender_tracing::layer::OpenTelemetryTracingBridge::new(
+                &logger_provider,
+            );
+
+            // --- Metrics (PeriodicReader: exports every 5 s) ---
+            let metric_exporter = with_endpoint(
+                opentelemetry_otlp::MetricExporter::builder().with_http(),
+                endpoint("metrics"),
+            )
+            .build()?;
+            let metric_reader =
+                opentelemetry_sdk::metrics::PeriodicReader::builder(metric_exporter)
+                    .with_interval(std::time::Duration::from_secs(5))
                     .build();
-                opentelemetry::global::set_meter_provider(meter_provider.clone());
-
-                Some((
-                    Some(trace_layer),
-                    Some(tracer_provider),
-                    Some(meter_provider),
-                    Some(logger_provider),
-                    Some(log_layer),
-                ))
-            })
-            .unwrap_or((None, None, None, None, None));
+            let meter_provider = opentelemetry_sdk::metrics::SdkMeterProvider::builder()
+                .with_resource(resource)
+                .with_reader(metric_reader)
+                .build();
+            opentelemetry::global::set_meter_provider(meter_provider.clone());
+
+            Ok::<_, opentelemetry_otlp::ExporterBuildError>((
+                Some(trace_layer),
+                Some(tracer_provider),
+                Some(meter_provider),
+                Some(logger_provider),
+                Some(log_layer),
+            ))
+        })
+        .transpose();
+    let ((otel_trace_layer, tp, mp, lp, otel_log_layer), telemetry_error) = match layers {
+        Ok(layers) => (layers.unwrap_or_default(), None),
+        Err(e) => (
+            Default::default(),
+            Some(CliError::new(
+                ErrorKind::Telemetry,
+                format!(
+                    "failed to set up telemetry export to {}: {e}",
+                    telemetry.endpoint.as_deref().unwrap_or_default()
+                ),
+            )),
+        ),
+    };
 
     tracing_subscriber::registry()
         .with(env_filter)
@@ -198,7 +608,7 @@ fn init_otel() -> OtelProviders {
         .with(otel_log_layer)
         .init();
 
-    (tp, mp, lp)
+    ((tp, mp, lp), telemetry_error)
 }
 
 /// Shut down `OTel` providers in reverse initialization order.
@@ -228,230 +638,347 @@ fn shutdown_otel((tracer_provider, meter_provider, logger_provider): OtelProvide
 ///
 /// # Arguments
 /// * `name` - 挨拶対象の名前
-/// * `gender` - 性別オプション（None, Some("man"), Some("woman"), その他）
+/// * `honorific` - 敬称（None なら敬称なし）
+/// * `locale` - 挨拶に使うメッセージカタログのロケール
+/// * `template` - 挨拶行のユーザー定義テンプレート（None ならカタログの文言）
 /// * `meters` - Metric instruments; no-op when `otel` feature is disabled
-#[cfg_attr(feature = "otel", tracing::instrument(skip(meters)))]
-pub fn run(name: &str, gender: Option<&str>, meters: &Meters) {
+/// * `output` - 挨拶行を書き出す出力チャネル
+///
+/// # Returns
+/// The greeting line that was written to `output`.
+///
+/// # Errors
+/// Returns the `GreetingError` after writing a fallback greeting, so callers
+/// can report a failed exit status.
+#[cfg_attr(feature = "otel", tracing::instrument(skip(meters, output)))]
+pub fn run(
+    name: &str,
+    honorific: Option<&Honorific>,
+    locale: Locale,
+    template: Option<&str>,
+    meters: &Meters,
+    output: &mut Output<impl Write>,
+) -> Result<String, GreetingError> {
     let start = std::time::Instant::now();
-    let result = sayhello(name, gender);
-
-    match &result {
-        Ok(_) => meters.record_greeting(gender.unwrap_or("none")),
-        Err(GreetingError::InvalidGender(_)) => {
-            meters.record_greeting("invalid");
-            meters.record_greeting_error("invalid_gender");
+    let greeter = Greeter::new(locale).with_honorific(honorific.cloned());
+    let result = match template.map(Template::parse).transpose() {
+        Ok(template) => greeter.with_template(template).greet(name, meters),
+        Err(e) => {
+            greeter.record(meters, Some(&e));
+            Err(e)
         }
-        Err(GreetingError::UnknownGender) => {
-            // NOTEST(unreachable): sayhello() never returns UnknownGender; exhaustive guard only
-            meters.record_greeting("none");
-            meters.record_greeting_error("unknown");
-        }
-    }
+    };
 
-    let greeting = format_greeting(name, result);
-    tracing::info!("{}, new world!!", greeting);
+    let error = result.as_ref().err().cloned();
+    let line = format_greeting(name, locale, result);
+    output.line(&line);
     meters.record_run_duration(start.elapsed().as_secs_f64(), "greet");
+
+    error.map_or(Ok(line), Err)
 }
 
-/// Format a greeting from a `sayhello` result, handling errors gracefully.
-fn format_greeting(name: &str, result: Result<String, GreetingError>) -> String {
+/// Format the greeting line from a greeting result, handling errors gracefully.
+///
+/// On error the name is greeted without an honorific or template, using the
+/// `locale` message catalog.
+fn format_greeting(name: &str, locale: Locale, result: Result<String, GreetingError>) -> String {
     match result {
         Ok(msg) => msg,
-        Err(GreetingError::InvalidGender(invalid_gender)) => {
-            tracing::warn!(
-                "Invalid gender '{}' specified, using default greeting",
-                invalid_gender
-            );
-            format!("Hi, {name} (invalid gender: {invalid_gender})")
-        }
-        Err(GreetingError::UnknownGender) => {
-            tracing::error!("Unexpected error in greeting generation, using default");
-            format!("Hi, {name}")
+        Err(e) => {
+            tracing::warn!("{e}, using default greeting");
+            Greeter::new(locale).fallback(name)
         }
     }
 }
 
-/// Run iteration count demo and record `OTel` metrics.
-#[cfg_attr(feature = "otel", tracing::instrument(skip(meters)))]
-fn run_count(count: u32, meters: &Meters) {
-    let start = std::time::Instant::now();
-    meters.in_flight_add(1);
-
-    let results = count::run_iterations(count);
-
-    for result in &results {
-        #[allow(clippy::cast_precision_loss, clippy::as_conversions)]
-        // duration_secs (u64 1..=5) fits f64 losslessly
-        meters.record_iteration(result.duration_secs as f64);
+/// Requests and `--save` destination of `fetch`, from its flags and
+/// `http.url`.
+///
+/// # Errors
+///
+/// Returns the [`http::SetupError`] of a flag that cannot be used, and an
+/// [`ErrorKind::Usage`] error for `--save -` with JSON or YAML output.
+fn fetch_plan(
+    args: &FetchArgs,
+    config: &Config,
+    format: OutputFormat,
+) -> Result<(Vec<http::Request>, Option<http::Save>), CliError> {
+    let urls = http::collect_urls(
+        &args.urls,
+        args.url_file.as_deref(),
+        args.data.as_deref(),
+        config.http.url.as_deref(),
+    )?;
+    let requests = http::build_requests(
+        &urls,
+        args.method.as_ref(),
+        &args.headers,
+        args.data.as_deref(),
+    )?;
+    let save = args
+        .save
+        .as_deref()
+        .map(|path| http::Save::open(path, urls.len()))
+        .transpose()?;
+    if matches!(save, Some(http::Save::Stdout)) && format.is_machine() {
+        return Err(CliError::new(
+            ErrorKind::Usage,
+            "--save - cannot be combined with JSON or YAML output",
+        ));
     }
-
-    meters.in_flight_add(-1);
-    meters.record_run_duration(start.elapsed().as_secs_f64(), "count");
+    Ok((requests, save))
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Meters, format_greeting, run};
-    use crate::libs::hello::GreetingError;
+    #![allow(clippy::unwrap_used)]
+    #![allow(clippy::indexing_slicing)]
+
+    use std::time::Duration;
+
+    use brust::CancelToken;
+    use brust::libs::hello::GreetingError;
+    use brust::libs::hello::honorific::Honorific;
+    use brust::libs::hello::locale::Locale;
     use tracing::subscriber::with_default;
     use tracing_mock::{expect, subscriber};
 
-    /// Build a mock subscriber that expects the `run` instrumentation span
-    /// wrapping a single event with the given message.
-    fn mock_run_single_event(msg: &str) -> (impl tracing::Subscriber, subscriber::MockHandle) {
-        let run_span = expect::span().named("run");
-        subscriber::mock()
-            .new_span(run_span.clone())
-            .enter(run_span.clone())
-            .event(expect::event().with_fields(expect::msg(msg)))
-            .exit(run_span.clone())
-            .drop_span(run_span)
-            .only()
-            .run_with_handle()
+    use super::{Meters, count_command, format_greeting, run};
+    use crate::config::Config;
+    use crate::error::ErrorKind;
+    use crate::output::{Output, OutputFormat};
+
+    /// Run the greeting with text output captured in memory and return what
+    /// was written.
+    fn greet(name: &str, honorific: Option<&Honorific>, locale: Locale) -> String {
+        greet_with(name, honorific, locale, None).0
     }
 
-    #[test]
-    fn test_run_with_default_name() {
+    fn greet_with(
+        name: &str,
+        honorific: Option<&Honorific>,
+        locale: Locale,
+        template: Option<&str>,
+    ) -> (String, Result<String, GreetingError>) {
         let meters = Meters::default();
-        let (subscriber, handle) = mock_run_single_event("Hi, Youre, new world!!");
-
-        with_default(subscriber, || {
-            run("Youre", None, &meters);
-        });
+        let mut output = Output::new(OutputFormat::Text, Vec::new());
+        let result = run(name, honorific, locale, template, &meters, &mut output);
+        (String::from_utf8(output.into_inner()).unwrap(), result)
+    }
 
-        handle.assert_finished();
+    #[test]
+    fn test_run_with_default_name() {
+        assert_eq!(greet("Youre", None, Locale::En), "Hi, Youre, new world!!\n");
     }
 
     #[test]
     fn test_run_with_custom_name() {
-        let meters = Meters::default();
-        let (subscriber, handle) = mock_run_single_event("Hi, Alice, new world!!");
-
-        with_default(subscriber, || {
-            run("Alice", None, &meters);
-        });
-
-        handle.assert_finished();
+        assert_eq!(greet("Alice", None, Locale::En), "Hi, Alice, new world!!\n");
     }
 
     #[test]
     fn test_run_with_empty_name() {
-        let meters = Meters::default();
-        let (subscriber, handle) = mock_run_single_event("Hi, , new world!!");
+        assert_eq!(greet("", None, Locale::En), "Hi, , new world!!\n");
+    }
 
-        with_default(subscriber, || {
-            run("", None, &meters);
-        });
+    #[test]
+    fn test_run_with_japanese_name() {
+        assert_eq!(greet("世界", None, Locale::En), "Hi, 世界, new world!!\n");
+    }
 
-        handle.assert_finished();
+    #[test]
+    fn test_run_with_gender_man() {
+        assert_eq!(
+            greet("John", Some(&Honorific::Mr), Locale::En),
+            "Hi, Mr. John, new world!!\n"
+        );
     }
 
     #[test]
-    fn test_run_with_japanese_name() {
-        let meters = Meters::default();
-        let (subscriber, handle) = mock_run_single_event("Hi, 世界, new world!!");
+    fn test_run_with_gender_woman() {
+        assert_eq!(
+            greet("Alice", Some(&Honorific::Ms), Locale::En),
+            "Hi, Ms. Alice, new world!!\n"
+        );
+    }
 
-        with_default(subscriber, || {
-            run("世界", None, &meters);
-        });
+    #[test]
+    fn test_run_with_locale_ja() {
+        assert_eq!(
+            greet("太郎", Some(&Honorific::Mr), Locale::Ja),
+            "こんにちは、太郎さん、新しい世界!!\n"
+        );
+    }
 
-        handle.assert_finished();
+    #[test]
+    fn test_run_with_neutral_and_custom_honorifics() {
+        assert_eq!(
+            greet("Sam", Some(&Honorific::Mx), Locale::En),
+            "Hi, Mx. Sam, new world!!\n"
+        );
+        let custom = Honorific::Custom(String::from("Sir"));
+        assert_eq!(
+            greet("Galahad", Some(&custom), Locale::En),
+            "Hi, Sir Galahad, new world!!\n"
+        );
     }
 
     #[test]
-    fn test_run_with_gender_man() {
+    fn test_run_writes_nothing_for_machine_output() {
         let meters = Meters::default();
-        let (subscriber, handle) = mock_run_single_event("Hi, Mr. John, new world!!");
+        let mut output = Output::new(OutputFormat::Json, Vec::new());
+        let line = run("Alice", None, Locale::En, None, &meters, &mut output).unwrap();
+        assert_eq!(line, "Hi, Alice, new world!!");
+        assert!(output.into_inner().is_empty());
+    }
+
+    #[test]
+    fn test_run_does_not_log_the_greeting() {
+        let run_span = expect::span().named("run");
+        let (subscriber, handle) = subscriber::mock()
+            .new_span(run_span.clone())
+            .enter(run_span.clone())
+            .exit(run_span.clone())
+            .drop_span(run_span)
+            .only()
+            .run_with_handle();
 
         with_default(subscriber, || {
-            run("John", Some("man"), &meters);
+            assert_eq!(greet("Alice", None, Locale::En), "Hi, Alice, new world!!\n");
         });
 
         handle.assert_finished();
     }
 
     #[test]
-    fn test_run_with_gender_woman() {
-        let meters = Meters::default();
-        let (subscriber, handle) = mock_run_single_event("Hi, Ms. Alice, new world!!");
+    fn test_cancelled_count_reports_partial_results() {
+        let mut config = Config::default();
+        config.count.iterations = Some(3);
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        let mut output = Output::new(OutputFormat::Json, Vec::new());
+
+        let error =
+            count_command(&config, None, &Meters::noop(), &cancel, &mut output).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Cancelled);
+        assert_eq!(error.message, "count cancelled after 0 of 3 iterations");
+
+        let document: serde_json::Value = serde_json::from_slice(&output.into_inner()).unwrap();
+        assert_eq!(document["status"], "error");
+        assert_eq!(document["result"]["summary"]["count"], 0);
+    }
 
-        with_default(subscriber, || {
-            run("Alice", Some("woman"), &meters);
-        });
+    #[test]
+    fn test_count_without_a_limit_is_a_usage_error() {
+        let mut output = Output::new(OutputFormat::Json, Vec::new());
+        let error = count_command(
+            &Config::default(),
+            None,
+            &Meters::noop(),
+            &CancelToken::new(),
+            &mut output,
+        )
+        .unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Usage);
+
+        let mut config = Config::default();
+        config.count.duration = Some(Duration::from_mins(10));
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        let error =
+            count_command(&config, None, &Meters::noop(), &cancel, &mut output).unwrap_err();
+        assert_eq!(error.message, "count cancelled after 0 iterations");
+    }
 
-        handle.assert_finished();
+    #[test]
+    fn test_format_greeting_invalid_honorific_ja() {
+        let result = format_greeting(
+            "Bob",
+            Locale::Ja,
+            Err(GreetingError::InvalidHonorific(String::new())),
+        );
+        assert_eq!(result, "こんにちは、Bob、新しい世界!!");
     }
 
     #[test]
-    fn test_format_greeting_unknown_gender() {
+    fn test_format_greeting_invalid_honorific() {
         let (subscriber, handle) = subscriber::mock()
             .event(
                 expect::event()
                     .with_target(env!("CARGO_PKG_NAME"))
-                    .at_level(tracing::Level::ERROR),
+                    .at_level(tracing::Level::WARN),
             )
             .only()
             .run_with_handle();
 
         with_default(subscriber, || {
-            let result = format_greeting("Unknown", Err(GreetingError::UnknownGender));
-            assert_eq!(result, "Hi, Unknown");
+            let result = format_greeting(
+                "Bob",
+                Locale::En,
+                Err(GreetingError::InvalidHonorific(String::from(" "))),
+            );
+            assert_eq!(result, "Hi, Bob, new world!!");
         });
 
         handle.assert_finished();
     }
 
     #[test]
-    fn test_format_greeting_invalid_gender() {
+    fn test_format_greeting_ok() {
+        let result = format_greeting("Alice", Locale::En, Ok(String::from("Hi, Alice")));
+        assert_eq!(result, "Hi, Alice");
+    }
+
+    #[test]
+    fn test_run_with_invalid_honorific() {
+        let run_span = expect::span().named("run");
         let (subscriber, handle) = subscriber::mock()
+            .new_span(run_span.clone())
+            .enter(run_span.clone())
             .event(
                 expect::event()
                     .with_target(env!("CARGO_PKG_NAME"))
                     .at_level(tracing::Level::WARN),
             )
+            .exit(run_span.clone())
+            .drop_span(run_span)
             .only()
             .run_with_handle();
 
         with_default(subscriber, || {
-            let result = format_greeting(
-                "Bob",
-                Err(GreetingError::InvalidGender(String::from("other"))),
-            );
-            assert_eq!(result, "Hi, Bob (invalid gender: other)");
+            let custom = Honorific::Custom(String::from(" "));
+            let (written, result) = greet_with("Bob", Some(&custom), Locale::En, None);
+            assert_eq!(written, "Hi, Bob, new world!!\n");
+            assert!(result.is_err());
         });
 
         handle.assert_finished();
     }
 
     #[test]
-    fn test_format_greeting_ok() {
-        let result = format_greeting("Alice", Ok(String::from("Hi, Alice")));
-        assert_eq!(result, "Hi, Alice");
+    fn test_run_with_template() {
+        let template = Some("Good day, {title} {name}! ({locale})");
+        let (written, result) = greet_with("John", Some(&Honorific::Mr), Locale::En, template);
+        assert_eq!(written, "Good day, Mr. John! (en)\n");
+        assert_eq!(result.unwrap(), "Good day, Mr. John! (en)");
     }
 
     #[test]
-    fn test_run_with_invalid_gender() {
+    fn test_run_with_invalid_template_falls_back() {
         let run_span = expect::span().named("run");
         let (subscriber, handle) = subscriber::mock()
             .new_span(run_span.clone())
             .enter(run_span.clone())
-            .event(
-                expect::event()
-                    .with_target(env!("CARGO_PKG_NAME"))
-                    .at_level(tracing::Level::WARN),
-            )
-            .event(
-                expect::event()
-                    .with_fields(expect::msg("Hi, Bob (invalid gender: other), new world!!")),
-            )
+            .event(expect::event().at_level(tracing::Level::WARN))
             .exit(run_span.clone())
             .drop_span(run_span)
             .only()
             .run_with_handle();
 
-        let meters = Meters::default();
         with_default(subscriber, || {
-            run("Bob", Some("other"), &meters);
+            let (written, result) = greet_with("Bob", None, Locale::En, Some("Hi {nmae}"));
+            assert_eq!(written, "Hi, Bob, new world!!\n");
+            assert!(matches!(result, Err(GreetingError::InvalidTemplate(_))));
         });
 
         handle.assert_finished();
@@ -0,0 +1,840 @@
+//! User-facing command output.
+//!
+//! Results reach the user through an [`Output`] channel on stdout, separate
+//! from the diagnostic log on stderr. With `--output json|yaml` every document follows the versioned schema in
+//! `docs/specs/schemas/brust-output-v2.schema.json`: a fixed envelope
+//! (`schema_version`, `command`, `status`, `error`) around a per-command
+//! `result`. Fields may be added within a version; bump [`SCHEMA_VERSION`]
+//! and add a new schema file on any other change.
+
+use std::fmt::{self, Write as _};
+use std::io::{self, Write};
+use std::time::Duration;
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use brust::libs::count::stats::Summary;
+use brust::libs::count::{self, IterationError, IterationResult};
+use brust::libs::http::{FetchError, FetchResult};
+
+use crate::config::CountConfig;
+
+/// Version of the output document schema.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Output format selected with `--output`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable lines, printed as the command runs.
+    #[default]
+    Text,
+    /// One pretty-printed JSON document.
+    Json,
+    /// One YAML document.
+    Yaml,
+}
+
+impl OutputFormat {
+    /// Whether stdout carries a machine-readable document.
+    #[must_use]
+    pub const fn is_machine(self) -> bool {
+        !matches!(self, Self::Text)
+    }
+}
+
+/// Outcome of a command in the document envelope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    /// The command succeeded.
+    Ok,
+    /// The command failed; `error` says why.
+    Error,
+}
+
+/// User-facing output channel.
+///
+/// Text lines are written as a command runs; JSON and YAML documents are
+/// written once when it finishes. Write errors are logged and remembered so
+/// the command can still exit non-zero.
+#[derive(Debug)]
+pub struct Output<W: Write> {
+    format: OutputFormat,
+    writer: W,
+    failed: bool,
+}
+
+impl<W: Write> Output<W> {
+    /// Create a channel writing `format` output to `writer`.
+    pub const fn new(format: OutputFormat, writer: W) -> Self {
+        Self {
+            format,
+            writer,
+            failed: false,
+        }
+    }
+
+    /// Selected output format.
+    #[must_use]
+    pub const fn format(&self) -> OutputFormat {
+        self.format
+    }
+
+    /// Write one human-readable result line; ignored for JSON and YAML.
+    pub fn line(&mut self, line: impl fmt::Display) {
+        if !self.format.is_machine() {
+            let result = writeln!(self.writer, "{line}");
+            self.check(result);
+        }
+    }
+
+    /// Write `document`; ignored for text output, which was written line by line.
+    pub fn document(&mut self, document: &Document) {
+        let result = emit(self.format, document, &mut self.writer);
+        self.check(result);
+    }
+
+    /// Write `text` verbatim regardless of the format, for commands without a
+    /// structured document.
+    pub fn print(&mut self, text: &str) {
+        let result = self.writer.write_all(text.as_bytes());
+        self.check(result);
+    }
+
+    /// Underlying writer, for commands that stream their own record format.
+    pub const fn writer(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Whether any write to this channel failed.
+    #[must_use]
+    pub const fn failed(&self) -> bool {
+        self.failed
+    }
+
+    /// Consume the channel and return the writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn check(&mut self, result: io::Result<()>) {
+        if let Err(e) = result {
+            tracing::error!("failed to write output: {e}");
+            self.failed = true;
+        }
+    }
+}
+
+impl<W: Write> Write for Output<W> {
+    /// Write raw bytes regardless of the format, such as a response body
+    /// streamed by `fetch --save -`.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writer.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Top-level output document.
+#[derive(Debug, Serialize)]
+pub struct Document {
+    /// Always [`SCHEMA_VERSION`].
+    pub schema_version: u32,
+    /// Subcommand that produced the document.
+    pub command: &'static str,
+    /// Whether the command succeeded.
+    pub status: Status,
+    /// Error message when `status` is `error`.
+    pub error: Option<String>,
+    /// Command-specific result.
+    pub result: CommandResult,
+}
+
+impl Document {
+    /// Wrap `result`, deriving `status` from `error`.
+    #[must_use]
+    pub const fn new(result: CommandResult, error: Option<String>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            command: result.command(),
+            status: if error.is_some() {
+                Status::Error
+            } else {
+                Status::Ok
+            },
+            error,
+            result,
+        }
+    }
+}
+
+/// Command-specific part of a [`Document`].
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum CommandResult {
+    /// `brust greet`.
+    Greet(GreetOutput),
+    /// `brust count`.
+    Count(CountOutput),
+    /// `brust fetch`.
+    Fetch(FetchOutput),
+}
+
+impl CommandResult {
+    const fn command(&self) -> &'static str {
+        match self {
+            Self::Greet(_) => "greet",
+            Self::Count(_) => "count",
+            Self::Fetch(_) => "fetch",
+        }
+    }
+}
+
+/// Result of `brust greet`.
+#[derive(Debug, Serialize)]
+pub struct GreetOutput {
+    /// Name that was greeted.
+    pub name: String,
+    /// Honorific kind (`man`, `woman`, `neutral`, `dr`, `prof`, `custom`), if any.
+    pub honorific: Option<&'static str>,
+    /// Locale of the greeting (`en`, `ja`).
+    pub locale: &'static str,
+    /// Greeting line; unset when the greeting failed.
+    pub greeting: Option<String>,
+}
+
+/// Result of `brust count`.
+#[derive(Debug, Serialize)]
+pub struct CountOutput {
+    /// Delay distribution, e.g. `uniform:1000,5000`.
+    pub delay: String,
+    /// RNG seed; unset when delays were not seeded.
+    pub seed: Option<u64>,
+    /// Time limit on starting iterations in seconds; unset when unlimited.
+    pub duration_secs: Option<f64>,
+    /// Iteration schedule, e.g. `20/s`; unset when iterations ran back to back.
+    pub rate: Option<String>,
+    /// Every iteration in order.
+    pub iterations: Vec<IterationOutput>,
+    /// Aggregates over `iterations`.
+    pub summary: CountSummary,
+}
+
+/// One iteration of `brust count`.
+#[derive(Debug, Serialize)]
+pub struct IterationOutput {
+    /// 1-based iteration number.
+    pub iteration: u32,
+    /// Random delay of this iteration in seconds (millisecond resolution).
+    pub delay_secs: f64,
+    /// Seconds between the scheduled and the actual start; zero without a rate.
+    pub lag_secs: f64,
+    /// Injected failure kind, e.g. `timeout`; unset when the iteration
+    /// succeeded.
+    pub error: Option<&'static str>,
+}
+
+/// Aggregates of a `brust count` run.
+#[derive(Debug, Serialize)]
+pub struct CountSummary {
+    /// Number of iterations run.
+    pub count: u32,
+    /// Number of iterations that failed.
+    pub errors: u32,
+    /// Sum of all delays in seconds.
+    pub total_secs: f64,
+    /// Shortest delay; unset when no iteration ran.
+    pub min_secs: Option<f64>,
+    /// Longest delay; unset when no iteration ran.
+    pub max_secs: Option<f64>,
+    /// Mean delay; unset when no iteration ran.
+    pub mean_secs: Option<f64>,
+    /// Population standard deviation of the delays; unset when no iteration ran.
+    pub stddev_secs: Option<f64>,
+    /// Median delay; unset when no iteration ran.
+    pub p50_secs: Option<f64>,
+    /// 90th percentile delay; unset when no iteration ran.
+    pub p90_secs: Option<f64>,
+    /// 99th percentile delay; unset when no iteration ran.
+    pub p99_secs: Option<f64>,
+    /// Wall-clock time of the whole run in seconds.
+    pub wall_secs: f64,
+    /// Iterations that started after the next slot was already due; unset
+    /// without a rate.
+    pub missed_slots: Option<u32>,
+}
+
+impl CountOutput {
+    /// Build the output of a run with `settings` that took `wall` time,
+    /// computing the summary from `results`.
+    #[must_use]
+    pub fn new(settings: &CountConfig, results: &[IterationResult], wall: Duration) -> Self {
+        let iterations: Vec<IterationOutput> = (1_u32..)
+            .zip(results)
+            .map(|(iteration, result)| IterationOutput {
+                iteration,
+                delay_secs: result.delay_secs(),
+                lag_secs: result.lag_secs(),
+                error: result.error.map(IterationError::as_str),
+            })
+            .collect();
+        let stats = Summary::new(results.iter().map(|r| r.delay));
+        let secs = |d: Option<Duration>| d.map(|d| d.as_secs_f64());
+        Self {
+            delay: settings.delay.to_string(),
+            seed: settings.seed,
+            duration_secs: settings.duration.map(|d| d.as_secs_f64()),
+            rate: settings.rate.map(|rate| rate.to_string()),
+            iterations,
+            summary: CountSummary {
+                count: u32::try_from(stats.count).unwrap_or(u32::MAX),
+                errors: u32::try_from(results.iter().filter(|r| r.error.is_some()).count())
+                    .unwrap_or(u32::MAX),
+                total_secs: stats.total.as_secs_f64(),
+                min_secs: secs(stats.min),
+                max_secs: secs(stats.max),
+                mean_secs: secs(stats.mean),
+                stddev_secs: secs(stats.stddev),
+                p50_secs: secs(stats.p50),
+                p90_secs: secs(stats.p90),
+                p99_secs: secs(stats.p99),
+                wall_secs: wall.as_secs_f64(),
+                missed_slots: settings.rate.map(|rate| {
+                    u32::try_from(count::missed_slots(results, &rate)).unwrap_or(u32::MAX)
+                }),
+            },
+        }
+    }
+
+    /// Write every iteration to `writer` as CSV with a header row.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to `writer` fails.
+    pub fn write_csv(&self, writer: impl Write) -> csv::Result<()> {
+        let mut csv = csv::Writer::from_writer(writer);
+        for iteration in &self.iterations {
+            csv.serialize(iteration)?;
+        }
+        csv.flush()?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for CountOutput {
+    /// The summary table, followed by the slots missed at the rate if one
+    /// was set.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.summary)?;
+        match (&self.rate, self.summary.missed_slots) {
+            (Some(rate), Some(missed)) => write!(
+                f,
+                "\n{missed} of {} slots missed at {rate}",
+                self.summary.count
+            ),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for CountSummary {
+    /// One `statistic value` row per line, seconds to the millisecond and `-`
+    /// for statistics without a value.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<6} {:>9}", "count", self.count)?;
+        writeln!(f, "{:<6} {:>9}", "errors", self.errors)?;
+        let rows = [
+            ("min", self.min_secs),
+            ("p50", self.p50_secs),
+            ("p90", self.p90_secs),
+            ("p99", self.p99_secs),
+            ("max", self.max_secs),
+            ("mean", self.mean_secs),
+            ("stddev", self.stddev_secs),
+            ("total", Some(self.total_secs)),
+            ("wall", Some(self.wall_secs)),
+        ];
+        for (i, (name, secs)) in rows.into_iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            match secs {
+                Some(secs) => write!(f, "{name:<6} {secs:>9.3} s")?,
+                None => write!(f, "{name:<6} {:>9}", "-")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Result of `brust fetch`.
+#[derive(Debug, Serialize)]
+pub struct FetchOutput {
+    /// Request method, e.g. `GET`.
+    pub method: String,
+    /// Outcome of every URL that was fetched, in order.
+    pub requests: Vec<FetchedUrl>,
+}
+
+impl FetchOutput {
+    /// Build the output from the outcome of every URL.
+    #[must_use]
+    pub fn new(method: &str, requests: Vec<FetchedUrl>) -> Self {
+        Self {
+            method: method.to_owned(),
+            requests,
+        }
+    }
+}
+
+/// Outcome of one URL of `brust fetch`.
+#[derive(Debug, Serialize)]
+pub struct FetchedUrl {
+    /// Requested URL.
+    pub url: String,
+    /// Server host from the URL; unset when the URL is invalid.
+    pub host: Option<String>,
+    /// URL scheme; unset when the URL is invalid.
+    pub scheme: Option<String>,
+    /// HTTP response status code; unset when no response was received.
+    pub status: Option<u16>,
+    /// Round-trip time in seconds of the last attempt; unset when no
+    /// response was received.
+    pub duration_secs: Option<f64>,
+    /// Requests sent, including retries; unset when no response was received.
+    pub attempts: Option<u32>,
+    /// Whether the last attempt reused a connection kept open by an earlier
+    /// request; unset when no response was received.
+    pub reused_connection: Option<bool>,
+    /// Size in bytes of the response body of the last attempt; unset when
+    /// no response was received.
+    pub body_size: Option<u64>,
+    /// Why the request failed; unset when a response was received.
+    pub error: Option<String>,
+}
+
+impl FetchedUrl {
+    /// Build the outcome of fetching `url`.
+    #[must_use]
+    pub fn new(url: &str, result: Result<&FetchResult, &FetchError>) -> Self {
+        let parsed = reqwest::Url::parse(url).ok();
+        let fetched = result.ok();
+        Self {
+            url: url.to_owned(),
+            host: parsed.as_ref().and_then(|u| u.host_str().map(String::from)),
+            scheme: parsed.as_ref().map(|u| String::from(u.scheme())),
+            status: fetched.map(|r| r.status),
+            duration_secs: fetched.map(|r| r.duration_s),
+            attempts: fetched.map(|r| r.attempts),
+            reused_connection: fetched.map(|r| r.reused_connection),
+            body_size: fetched.map(|r| r.body_size),
+            error: result.err().map(ToString::to_string),
+        }
+    }
+}
+
+/// Write `document` to `out` in `format`; text output is a no-op because it
+/// is written line by line through [`Output::line`].
+///
+/// # Errors
+///
+/// Returns an error if `out` cannot be written.
+pub fn emit(format: OutputFormat, document: &Document, mut out: impl Write) -> io::Result<()> {
+    match format {
+        OutputFormat::Text => Ok(()),
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut out, document)?;
+            writeln!(out)
+        }
+        OutputFormat::Yaml => {
+            let value = serde_json::to_value(document)?;
+            let mut yaml = String::new();
+            write_yaml(&mut yaml, &value, 0);
+            out.write_all(yaml.as_bytes())
+        }
+    }
+}
+
+/// Append `value` as block-style YAML, indenting nested blocks by `indent`.
+///
+/// Strings are emitted as double-quoted JSON strings, which are valid YAML
+/// scalars, so no YAML-specific escaping is needed.
+fn write_yaml(out: &mut String, value: &serde_json::Value, indent: usize) {
+    use serde_json::Value;
+
+    let pad = " ".repeat(indent);
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, value) in map {
+                let _ = write!(out, "{pad}{key}:");
+                write_yaml_child(out, value, indent);
+            }
+        }
+        Value::Array(items) if !items.is_empty() => {
+            for item in items {
+                let _ = write!(out, "{pad}-");
+                match item {
+                    Value::Object(map) if !map.is_empty() => {
+                        // First key shares the `- ` line; the rest align under it.
+                        let mut nested = String::new();
+                        write_yaml(&mut nested, item, indent.saturating_add(2));
+                        let _ = write!(out, " {}", nested.trim_start());
+                    }
+                    _ => write_yaml_child(out, item, indent),
+                }
+            }
+        }
+        scalar => {
+            let _ = writeln!(out, "{pad}{}", yaml_scalar(scalar));
+        }
+    }
+}
+
+/// Append the value of a mapping entry or sequence item after its `key:` or `-`.
+fn write_yaml_child(out: &mut String, value: &serde_json::Value, indent: usize) {
+    use serde_json::Value;
+
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            out.push('\n');
+            write_yaml(out, value, indent.saturating_add(2));
+        }
+        Value::Array(items) if !items.is_empty() => {
+            out.push('\n');
+            write_yaml(out, value, indent.saturating_add(2));
+        }
+        scalar => {
+            let _ = writeln!(out, " {}", yaml_scalar(scalar));
+        }
+    }
+}
+
+/// Render a scalar, or an empty collection in flow style.
+fn yaml_scalar(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Null => String::from("null"),
+        serde_json::Value::Object(_) => String::from("{}"),
+        serde_json::Value::Array(_) => String::from("[]"),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    #![allow(clippy::indexing_slicing)]
+
+    use super::{
+        CommandResult, CountOutput, Document, FetchOutput, FetchedUrl, GreetOutput, Output,
+        OutputFormat, emit,
+    };
+    use std::time::Duration;
+
+    use brust::libs::count::{Delay, IterationError, IterationResult};
+    use brust::libs::http::{FetchError, FetchResult};
+
+    use crate::config::CountConfig;
+
+    fn render(format: OutputFormat, document: &Document) -> String {
+        let mut out = Vec::new();
+        emit(format, document, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn greet_document() -> Document {
+        Document::new(
+            CommandResult::Greet(GreetOutput {
+                name: String::from("Alice"),
+                honorific: Some("woman"),
+                locale: "en",
+                greeting: Some(String::from("Hi, Ms. Alice, new world!!")),
+            }),
+            None,
+        )
+    }
+
+    #[test]
+    fn text_format_writes_nothing() {
+        assert_eq!(render(OutputFormat::Text, &greet_document()), "");
+    }
+
+    #[test]
+    fn output_routes_lines_and_documents_by_format() {
+        let mut text = Output::new(OutputFormat::Text, Vec::new());
+        text.line("Hi, Alice");
+        text.document(&greet_document());
+        text.print("raw\n");
+        assert!(!text.failed());
+        assert_eq!(
+            String::from_utf8(text.into_inner()).unwrap(),
+            "Hi, Alice\nraw\n"
+        );
+
+        let mut json = Output::new(OutputFormat::Json, Vec::new());
+        json.line("Hi, Alice");
+        json.document(&greet_document());
+        let out = String::from_utf8(json.into_inner()).unwrap();
+        assert!(out.starts_with('{'), "{out}");
+        assert!(!out.contains("Hi, Alice\n"), "{out}");
+    }
+
+    #[test]
+    fn output_remembers_write_errors() {
+        let mut output = Output::new(OutputFormat::Text, FailingWriter);
+        output.line("lost");
+        assert!(output.failed());
+    }
+
+    /// Writer whose every write fails, like a closed pipe.
+    struct FailingWriter;
+
+    impl std::io::Write for FailingWriter {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::from(std::io::ErrorKind::BrokenPipe))
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn json_envelope_is_versioned() {
+        let json: serde_json::Value =
+            serde_json::from_str(&render(OutputFormat::Json, &greet_document())).unwrap();
+        assert_eq!(json["schema_version"], 2);
+        assert_eq!(json["command"], "greet");
+        assert_eq!(json["status"], "ok");
+        assert_eq!(json["error"], serde_json::Value::Null);
+        assert_eq!(json["result"]["greeting"], "Hi, Ms. Alice, new world!!");
+    }
+
+    #[test]
+    fn count_summary_handles_empty_and_filled_runs() {
+        let empty = CountOutput::new(&CountConfig::default(), &[], Duration::ZERO);
+        assert_eq!(empty.summary.count, 0);
+        assert_eq!(empty.summary.min_secs, None);
+        assert_eq!(empty.summary.mean_secs, None);
+        assert_eq!(empty.summary.missed_slots, None);
+
+        let results = [
+            IterationResult {
+                delay: Duration::from_millis(1_250),
+                lag: Duration::ZERO,
+                error: None,
+            },
+            IterationResult {
+                delay: Duration::from_secs(4),
+                lag: Duration::from_millis(750),
+                error: Some(IterationError::Timeout),
+            },
+        ];
+        let settings = CountConfig {
+            delay: Delay::Fixed(0),
+            seed: Some(7),
+            duration: Some(Duration::from_mins(1)),
+            rate: Some("2/s".parse().unwrap()),
+            ..CountConfig::default()
+        };
+        let output = CountOutput::new(&settings, &results, Duration::from_secs(6));
+        assert_eq!(output.delay, "fixed:0");
+        assert_eq!(output.seed, Some(7));
+        assert_eq!(output.duration_secs, Some(60.0));
+        assert_eq!(output.rate.as_deref(), Some("2/s"));
+        assert_eq!(output.summary.missed_slots, Some(1));
+        assert!((output.iterations[1].lag_secs - 0.75).abs() < f64::EPSILON);
+        assert_eq!(output.iterations[0].error, None);
+        assert_eq!(output.iterations[1].error, Some("timeout"));
+        assert_eq!(output.summary.errors, 1);
+        assert_eq!(output.iterations.len(), 2);
+        assert!((output.iterations[0].delay_secs - 1.25).abs() < f64::EPSILON);
+        assert!((output.summary.total_secs - 5.25).abs() < f64::EPSILON);
+        assert_eq!(output.summary.min_secs, Some(1.25));
+        assert_eq!(output.summary.max_secs, Some(4.0));
+        assert_eq!(output.summary.mean_secs, Some(2.625));
+        assert_eq!(output.summary.stddev_secs, Some(1.375));
+        assert_eq!(output.summary.p50_secs, Some(1.25));
+        assert_eq!(output.summary.p99_secs, Some(4.0));
+        assert!((output.summary.wall_secs - 6.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn count_output_renders_missed_slots_after_the_summary() {
+        let empty = CountOutput::new(&CountConfig::default(), &[], Duration::ZERO);
+        assert_eq!(empty.to_string(), empty.summary.to_string());
+
+        let results = [IterationResult {
+            delay: Duration::from_millis(500),
+            lag: Duration::from_millis(600),
+            error: None,
+        }];
+        let settings = CountConfig {
+            rate: Some("2/s".parse().unwrap()),
+            ..CountConfig::default()
+        };
+        let output = CountOutput::new(&settings, &results, Duration::from_secs(1));
+        assert!(
+            output
+                .to_string()
+                .ends_with("wall       1.000 s\n1 of 1 slots missed at 2/s"),
+            "{output}"
+        );
+    }
+
+    #[test]
+    fn count_summary_renders_as_a_table_and_iterations_as_csv() {
+        let results = [IterationResult {
+            delay: Duration::from_millis(1_500),
+            lag: Duration::from_millis(20),
+            error: Some(IterationError::Invalid),
+        }];
+        let output = CountOutput::new(&CountConfig::default(), &results, Duration::from_secs(2));
+        assert_eq!(
+            output.summary.to_string(),
+            "count          1\n\
+             errors         1\n\
+             min        1.500 s\n\
+             p50        1.500 s\n\
+             p90        1.500 s\n\
+             p99        1.500 s\n\
+             max        1.500 s\n\
+             mean       1.500 s\n\
+             stddev     0.000 s\n\
+             total      1.500 s\n\
+             wall       2.000 s"
+        );
+        let empty = CountOutput::new(&CountConfig::default(), &[], Duration::ZERO);
+        assert!(empty.summary.to_string().contains("\np50            -\n"));
+
+        let mut csv = Vec::new();
+        output.write_csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "iteration,delay_secs,lag_secs,error\n1,1.5,0.02,invalid\n"
+        );
+    }
+
+    #[test]
+    fn yaml_renders_nested_blocks_and_sequences() {
+        let results = [
+            IterationResult {
+                delay: Duration::from_secs(2),
+                lag: Duration::ZERO,
+                error: None,
+            },
+            IterationResult {
+                delay: Duration::from_millis(3_500),
+                lag: Duration::ZERO,
+                error: None,
+            },
+        ];
+        let document = Document::new(
+            CommandResult::Count(CountOutput::new(
+                &CountConfig::default(),
+                &results,
+                Duration::from_secs(6),
+            )),
+            None,
+        );
+        assert_eq!(
+            render(OutputFormat::Yaml, &document),
+            "command: \"count\"\n\
+             error: null\n\
+             result:\n  \
+               delay: \"uniform:1000,5000\"\n  \
+               duration_secs: null\n  \
+               iterations:\n    \
+                 - delay_secs: 2.0\n      \
+                   error: null\n      \
+                   iteration: 1\n      \
+                   lag_secs: 0.0\n    \
+                 - delay_secs: 3.5\n      \
+                   error: null\n      \
+                   iteration: 2\n      \
+                   lag_secs: 0.0\n  \
+               rate: null\n  \
+               seed: null\n  \
+               summary:\n    \
+                 count: 2\n    \
+                 errors: 0\n    \
+                 max_secs: 3.5\n    \
+                 mean_secs: 2.75\n    \
+                 min_secs: 2.0\n    \
+                 missed_slots: null\n    \
+                 p50_secs: 2.0\n    \
+                 p90_secs: 3.5\n    \
+                 p99_secs: 3.5\n    \
+                 stddev_secs: 0.75\n    \
+                 total_secs: 5.5\n    \
+                 wall_secs: 6.0\n\
+             schema_version: 2\n\
+             status: \"ok\"\n"
+        );
+    }
+
+    #[test]
+    fn fetch_output_lists_every_url() {
+        let fetched = FetchResult {
+            status: 200,
+            duration_s: 0.25,
+            host: String::from("127.0.0.1"),
+            scheme: String::from("http"),
+            attempts: 1,
+            reused_connection: true,
+            body_size: 512,
+        };
+        let requests = vec![
+            FetchedUrl::new("http://127.0.0.1/a", Ok(&fetched)),
+            FetchedUrl::new("http://127.0.0.1/b", Err(&FetchError::Cancelled)),
+            FetchedUrl::new("not-a-url", Err(&FetchError::InvalidUrl(String::from("x")))),
+        ];
+        let document = Document::new(
+            CommandResult::Fetch(FetchOutput::new("POST", requests)),
+            None,
+        );
+        let json: serde_json::Value =
+            serde_json::from_str(&render(OutputFormat::Json, &document)).unwrap();
+        assert_eq!(json["result"]["method"], "POST");
+        assert_eq!(
+            json["result"]["requests"],
+            serde_json::json!([
+                {
+                    "url": "http://127.0.0.1/a",
+                    "host": "127.0.0.1",
+                    "scheme": "http",
+                    "status": 200,
+                    "duration_secs": 0.25,
+                    "attempts": 1,
+                    "reused_connection": true,
+                    "body_size": 512,
+                    "error": null,
+                },
+                {
+                    "url": "http://127.0.0.1/b",
+                    "host": "127.0.0.1",
+                    "scheme": "http",
+                    "status": null,
+                    "duration_secs": null,
+                    "attempts": null,
+                    "reused_connection": null,
+                    "body_size": null,
+                    "error": "HTTP request cancelled",
+                },
+                {
+                    "url": "not-a-url",
+                    "host": null,
+                    "scheme": null,
+                    "status": null,
+                    "duration_secs": null,
+                    "attempts": null,
+                    "reused_connection": null,
+                    "body_size": null,
+                    "error": "invalid URL: x",
+                },
+            ])
+        );
+    }
+}
@@ -0,0 +1,95 @@
+//! Ctrl-C / `SIGTERM` handling for commands that can stop early.
+//!
+//! The first signal fires a [`CancelToken`] so the running command stops
+//! cooperatively, reports its partial results and flushes telemetry. A second
+//! signal exits at once without flushing.
+
+use std::process;
+use std::thread;
+
+use brust::CancelToken;
+
+use crate::error::ErrorKind;
+
+/// Cancel `cancel` on the first `SIGINT` or `SIGTERM` and exit with the
+/// cancelled exit code on the second.
+///
+/// Signals are awaited on a background thread running a single-threaded tokio
+/// runtime. If the handlers cannot be installed, a warning is logged and
+/// signals keep their default behavior.
+pub fn install(cancel: CancelToken) {
+    let installed = tokio::runtime::Builder::new_current_thread()
+        .enable_io()
+        .build()
+        .and_then(|runtime| {
+            // Register before returning so no early signal is missed.
+            let mut signals = {
+                let _context = runtime.enter();
+                Signals::new()?
+            };
+            thread::Builder::new()
+                .name(String::from("signal"))
+                .spawn(move || {
+                    runtime.block_on(async {
+                        let name = signals.recv().await;
+                        tracing::warn!(
+                            signal = name,
+                            "stopping; send the signal again to exit immediately"
+                        );
+                        cancel.cancel();
+                        signals.recv().await;
+                        #[allow(clippy::exit)] // a second signal must not wait for the command
+                        process::exit(ErrorKind::Cancelled.exit_code().into());
+                    });
+                })
+        });
+    if let Err(e) = installed {
+        tracing::warn!("failed to install signal handlers: {e}"); // NOTEST(unreachable): runtime and handler setup only fail when the OS is out of resources
+    }
+}
+
+/// Registered `SIGINT` and `SIGTERM` streams.
+#[cfg(unix)]
+struct Signals {
+    interrupt: tokio::signal::unix::Signal,
+    terminate: tokio::signal::unix::Signal,
+}
+
+#[cfg(unix)]
+impl Signals {
+    fn new() -> std::io::Result<Self> {
+        use tokio::signal::unix::{SignalKind, signal};
+        Ok(Self {
+            interrupt: signal(SignalKind::interrupt())?,
+            terminate: signal(SignalKind::terminate())?,
+        })
+    }
+
+    /// Wait for the next signal and return its name.
+    async fn recv(&mut self) -> &'static str {
+        tokio::select! {
+            _ = self.interrupt.recv() => "SIGINT",
+            _ = self.terminate.recv() => "SIGTERM",
+        }
+    }
+}
+
+/// Ctrl-C only: `SIGTERM` does not exist outside Unix.
+#[cfg(not(unix))]
+struct Signals;
+
+#[cfg(not(unix))]
+impl Signals {
+    #[expect(clippy::unnecessary_wraps)]
+    const fn new() -> std::io::Result<Self> {
+        Ok(Self)
+    }
+
+    /// Wait for the next Ctrl-C.
+    async fn recv(&mut self) -> &'static str {
+        // An error here means the handler could not be registered; treat it
+        // as a signal rather than waiting forever.
+        let _ = tokio::signal::ctrl_c().await;
+        "Ctrl-C"
+    }
+}
@@ -2,19 +2,41 @@
 //!
 //! Mirrors the layout of `opentelemetry_semantic_conventions::{metric,
 //! attribute}` to provide a single source of truth for `brust.*` names
-//! across all signals (metrics today, tracing/logs in the future).
+//! across all signals: metric attributes and span and log fields alike.
 //! Use these constants instead of string literals to avoid typos and drift.
 
+/// `brust.*` metric instrument names.
 pub mod metric {
+    /// End-to-end command execution latency (histogram, `s`).
     pub const RUN_DURATION: &str = "brust.run.duration";
+    /// Greeting calls by resolved gender and locale (counter).
     pub const GREETING_COUNT: &str = "brust.greeting.count";
+    /// Greeting calls that failed, by `error.type` (counter).
     pub const GREETING_ERRORS: &str = "brust.greeting.errors";
+    /// Iterations executed in the count demo (counter).
     pub const ITERATION_COUNT: &str = "brust.iteration.count";
+    /// Per-iteration delay in the count demo (histogram, `s`).
     pub const ITERATION_DURATION: &str = "brust.iteration.duration";
+    /// Iterations currently executing (up-down counter).
     pub const ITERATION_IN_FLIGHT: &str = "brust.iteration.in_flight";
+    /// Iterations that failed in the count demo, by `error.type` (counter).
+    pub const ITERATION_ERRORS: &str = "brust.iteration.errors";
+    /// Delay between an iteration's scheduled and actual start in rate mode
+    /// (histogram, `s`).
+    pub const ITERATION_LAG: &str = "brust.iteration.lag";
 }
 
+/// `brust.*` attribute keys.
 pub mod attribute {
+    /// Command that produced a measurement (`greet`, `count`, `http`, `batch`).
     pub const COMMAND: &str = "brust.command";
+    /// Resolved honorific choice of a greeting.
     pub const GENDER: &str = "brust.gender";
+    /// Message catalog locale of a greeting.
+    pub const LOCALE: &str = "brust.locale";
+    /// Delay distribution of a count demo iteration (`fixed`, `uniform`, ...).
+    pub const DELAY_DISTRIBUTION: &str = "brust.delay.distribution";
+    /// Whether an HTTP request went over a kept-alive connection (span and
+    /// log field, `http.client.*` metric attribute).
+    pub const HTTP_CONNECTION_REUSED: &str = "brust.http.connection.reused";
 }
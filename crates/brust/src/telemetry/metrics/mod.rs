@@ -2,7 +2,8 @@
 //!
 //! Create a [`Meters`] instance once via [`Meters::default`] after the global
 //! `MeterProvider` has been initialized, then pass it by shared reference to
-//! every function that records measurements.
+//! every function that records measurements. Library callers that do not
+//! want telemetry pass [`Meters::noop`] instead.
 
 // ---------------------------------------------------------------------------
 // OTel implementation (feature = "otel")
@@ -14,15 +15,33 @@ mod process;
 #[cfg(feature = "otel")]
 use crate::telemetry::conventions::{attribute as brust_attr, metric as brust_metric};
 #[cfg(feature = "otel")]
-use opentelemetry::metrics::{Counter, Histogram, UpDownCounter};
+use opentelemetry::metrics::{Counter, Histogram, Meter, MeterProvider as _, UpDownCounter};
 #[cfg(feature = "otel")]
 use opentelemetry_semantic_conventions::{attribute, metric as semconv};
 
+/// Attributes of one HTTP client request, shared by the `http.client.*`
+/// instruments.
+#[derive(Debug, Clone, Copy)]
+pub struct HttpClientAttributes<'a> {
+    /// HTTP verb (`"GET"`, `"POST"`, …).
+    pub method: &'a str,
+    /// Target host name.
+    pub host: &'a str,
+    /// URL scheme (`"http"` or `"https"`).
+    pub scheme: &'a str,
+    /// HTTP response status code; `None` when no response came.
+    pub status: Option<u16>,
+    /// Whether the request went over a kept-alive connection; `None` when no
+    /// response came.
+    pub reused_connection: Option<bool>,
+}
+
 /// Collected `OTel` metric instruments for this application.
 ///
 /// All instruments are created once and reused — do not construct per-request.
 /// The `_process` field keeps observable process metric callbacks registered
-/// for the lifetime of this struct (requires the `process-metrics` feature).
+/// for the lifetime of this struct (requires the `process-metrics` feature);
+/// it is `None` for [`Meters::noop`].
 #[cfg(feature = "otel")]
 pub struct Meters {
     // --- Sync instruments ---
@@ -31,12 +50,15 @@ pub struct Meters {
     greeting_errors: Counter<u64>,
     iteration_count: Counter<u64>,
     iteration_duration: Histogram<f64>,
+    iteration_errors: Counter<u64>,
     iteration_in_flight: UpDownCounter<i64>,
+    iteration_lag: Histogram<f64>,
     http_request_duration: Histogram<f64>,
+    http_response_body_size: Histogram<u64>,
     // --- Observable process metrics (feature = "process-metrics") ---
     // Disabled under Miri: sysinfo calls sysconf(_SC_CLK_TCK) which Miri does not stub.
     #[cfg(all(feature = "process-metrics", not(miri)))]
-    _process: process::ProcessMetricHandles,
+    _process: Option<process::ProcessMetricHandles>,
 }
 
 #[cfg(feature = "otel")]
@@ -63,7 +85,23 @@ impl Meters {
     #[must_use]
     pub fn new() -> Self {
         let meter = opentelemetry::global::meter(env!("CARGO_PKG_NAME"));
+        Self {
+            #[cfg(all(feature = "process-metrics", not(miri)))]
+            _process: Some(process::ProcessMetricHandles::register(&meter)),
+            ..Self::from_meter(&meter)
+        }
+    }
 
+    /// Create instruments that record nothing, regardless of the global
+    /// `MeterProvider`. No process metrics are registered.
+    #[must_use]
+    pub fn noop() -> Self {
+        let provider = opentelemetry::metrics::NoopMeterProvider::new();
+        Self::from_meter(&provider.meter(env!("CARGO_PKG_NAME")))
+    }
+
+    /// Create the sync instruments from `meter`.
+    fn from_meter(meter: &Meter) -> Self {
         Self {
             run_duration: meter
                 .f64_histogram(brust_metric::RUN_DURATION)
@@ -73,7 +111,7 @@ impl Meters {
             greeting_count: meter
                 .u64_counter(brust_metric::GREETING_COUNT)
                 .with_unit("{call}")
-                .with_description("Total greeting calls attributed by resolved gender")
+                .with_description("Total greeting calls attributed by resolved gender and locale")
                 .build(),
             greeting_errors: meter
                 .u64_counter(brust_metric::GREETING_ERRORS)
@@ -90,11 +128,21 @@ impl Meters {
                 .with_unit("s")
                 .with_description("Per-iteration sleep delay in the count demo")
                 .build(),
+            iteration_errors: meter
+                .u64_counter(brust_metric::ITERATION_ERRORS)
+                .with_unit("{error}")
+                .with_description("Iterations that failed in the count demo")
+                .build(),
             iteration_in_flight: meter
                 .i64_up_down_counter(brust_metric::ITERATION_IN_FLIGHT)
                 .with_unit("{iter}")
                 .with_description("Iterations currently executing (UpDownCounter demo)")
                 .build(),
+            iteration_lag: meter
+                .f64_histogram(brust_metric::ITERATION_LAG)
+                .with_unit("s")
+                .with_description("Delay between an iteration's scheduled and actual start")
+                .build(),
             http_request_duration: meter
                 .f64_histogram(semconv::HTTP_CLIENT_REQUEST_DURATION)
                 .with_unit("s")
@@ -103,14 +151,19 @@ impl Meters {
                      (`OTel` HTTP semconv)",
                 )
                 .build(),
+            http_response_body_size: meter
+                .u64_histogram(semconv::HTTP_CLIENT_RESPONSE_BODY_SIZE)
+                .with_unit("By")
+                .with_description("Size of HTTP client response bodies read (`OTel` HTTP semconv)")
+                .build(),
             #[cfg(all(feature = "process-metrics", not(miri)))]
-            _process: process::ProcessMetricHandles::register(&meter),
+            _process: None,
         }
     }
 
     /// Record end-to-end command execution latency.
     ///
-    /// `command` should be one of `"greet"`, `"count"`, or `"http"`.
+    /// `command` should be one of `"greet"`, `"count"`, `"http"` or `"batch"`.
     pub fn record_run_duration(&self, duration_s: f64, command: &str) {
         self.run_duration.record(
             duration_s,
@@ -121,22 +174,24 @@ impl Meters {
         );
     }
 
-    /// Record a greeting call attributed by the resolved gender string.
+    /// Record a greeting call attributed by the resolved gender and locale.
     ///
-    /// Low-cardinality values: `"man"`, `"woman"`, `"none"`, `"invalid"`.
-    pub fn record_greeting(&self, gender: &str) {
+    /// Low-cardinality values: gender `"man"`, `"woman"`, `"neutral"`, `"dr"`,
+    /// `"prof"`, `"custom"`, `"none"`, `"invalid"`; locale `"en"`, `"ja"`.
+    pub fn record_greeting(&self, gender: &str, locale: &str) {
         self.greeting_count.add(
             1,
-            &[opentelemetry::KeyValue::new(
-                brust_attr::GENDER,
-                gender.to_owned(),
-            )],
+            &[
+                opentelemetry::KeyValue::new(brust_attr::GENDER, gender.to_owned()),
+                opentelemetry::KeyValue::new(brust_attr::LOCALE, locale.to_owned()),
+            ],
         );
     }
 
     /// Record a greeting error attributed by error type.
     ///
-    /// Low-cardinality values: `"invalid_gender"`, `"unknown"`.
+    /// Low-cardinality values: `"invalid_gender"`, `"invalid_honorific"`,
+    /// `"invalid_template"`, `"invalid_record"`.
     pub fn record_greeting_error(&self, error_type: &str) {
         self.greeting_errors.add(
             1,
@@ -148,9 +203,32 @@ impl Meters {
     }
 
     /// Record one completed iteration and its sleep duration in seconds.
-    pub fn record_iteration(&self, duration_s: f64) {
+    ///
+    /// `distribution` is the delay distribution name (`"fixed"`,
+    /// `"uniform"`, `"exponential"`, `"normal"`, `"lognormal"`), recorded on
+    /// the duration histogram.
+    pub fn record_iteration(&self, duration_s: f64, distribution: &str) {
         self.iteration_count.add(1, &[]);
-        self.iteration_duration.record(duration_s, &[]);
+        self.iteration_duration.record(
+            duration_s,
+            &[opentelemetry::KeyValue::new(
+                brust_attr::DELAY_DISTRIBUTION,
+                distribution.to_owned(),
+            )],
+        );
+    }
+
+    /// Record a failed iteration attributed by error type.
+    ///
+    /// Low-cardinality values: `"timeout"`, `"panic_like"`, `"invalid"`.
+    pub fn record_iteration_error(&self, error_type: &str) {
+        self.iteration_errors.add(
+            1,
+            &[opentelemetry::KeyValue::new(
+                attribute::ERROR_TYPE,
+                error_type.to_owned(),
+            )],
+        );
     }
 
     /// Adjust the in-flight iteration counter by `delta` (`+1` start, `-1` end).
@@ -158,29 +236,61 @@ impl Meters {
         self.iteration_in_flight.add(delta, &[]);
     }
 
-    /// Record an HTTP client request with `OTel` HTTP semantic convention attributes.
+    /// Record how late an iteration started against its rate schedule.
+    pub fn record_iteration_lag(&self, lag_s: f64) {
+        self.iteration_lag.record(lag_s, &[]);
+    }
+
+    /// Record an HTTP client request with `OTel` HTTP semantic convention
+    /// attributes, plus `brust.http.connection.reused` once a response came.
     ///
-    /// - `method`: HTTP verb (`"GET"`, `"POST"`, …)
-    /// - `status`: HTTP response status code
-    /// - `host`: target host name
-    /// - `scheme`: URL scheme (`"http"` or `"https"`)
+    /// `error_type` says why the request failed, such as `"timeout"` or
+    /// `"503"`.
     pub fn record_http_request(
         &self,
         duration_s: f64,
-        method: &str,
-        status: u16,
-        host: &str,
-        scheme: &str,
+        request: &HttpClientAttributes<'_>,
+        error_type: Option<&str>,
     ) {
-        use opentelemetry::KeyValue;
-        let attrs = [
-            KeyValue::new(attribute::HTTP_REQUEST_METHOD, method.to_owned()),
-            KeyValue::new(attribute::HTTP_RESPONSE_STATUS_CODE, i64::from(status)),
-            KeyValue::new(attribute::SERVER_ADDRESS, host.to_owned()),
-            KeyValue::new(attribute::URL_SCHEME, scheme.to_owned()),
-        ];
+        let mut attrs = http_attributes(request);
+        if let Some(error_type) = error_type {
+            attrs.push(opentelemetry::KeyValue::new(
+                attribute::ERROR_TYPE,
+                error_type.to_owned(),
+            ));
+        }
         self.http_request_duration.record(duration_s, &attrs);
     }
+
+    /// Record the size in bytes of an HTTP response body read in full,
+    /// with the same attributes as
+    /// [`record_http_request`](Self::record_http_request).
+    pub fn record_http_response_body_size(&self, size: u64, request: &HttpClientAttributes<'_>) {
+        self.http_response_body_size
+            .record(size, &http_attributes(request));
+    }
+}
+
+/// `OTel` HTTP semantic convention attributes shared by the HTTP client
+/// instruments.
+#[cfg(feature = "otel")]
+fn http_attributes(request: &HttpClientAttributes<'_>) -> Vec<opentelemetry::KeyValue> {
+    use opentelemetry::KeyValue;
+    let mut attrs = vec![
+        KeyValue::new(attribute::HTTP_REQUEST_METHOD, request.method.to_owned()),
+        KeyValue::new(attribute::SERVER_ADDRESS, request.host.to_owned()),
+        KeyValue::new(attribute::URL_SCHEME, request.scheme.to_owned()),
+    ];
+    if let Some(status) = request.status {
+        attrs.push(KeyValue::new(
+            attribute::HTTP_RESPONSE_STATUS_CODE,
+            i64::from(status),
+        ));
+    }
+    if let Some(reused) = request.reused_connection {
+        attrs.push(KeyValue::new(brust_attr::HTTP_CONNECTION_REUSED, reused));
+    }
+    attrs
 }
 
 // ---------------------------------------------------------------------------
@@ -194,26 +304,40 @@ pub struct Meters;
 
 #[cfg(not(feature = "otel"))]
 impl Meters {
+    /// Create the no-op instruments.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self
+    }
+    /// Create the no-op instruments.
+    #[must_use]
+    pub const fn noop() -> Self {
+        Self
+    }
     /// Record end-to-end command execution latency (no-op).
     pub fn record_run_duration(&self, _duration_s: f64, _command: &str) {}
     /// Record a greeting call (no-op).
-    pub fn record_greeting(&self, _gender: &str) {}
+    pub fn record_greeting(&self, _gender: &str, _locale: &str) {}
     /// Record a greeting error (no-op).
     pub fn record_greeting_error(&self, _error_type: &str) {}
     /// Record one completed iteration (no-op).
-    pub fn record_iteration(&self, _duration_s: f64) {}
+    pub fn record_iteration(&self, _duration_s: f64, _distribution: &str) {}
+    /// Record a failed iteration (no-op).
+    pub fn record_iteration_error(&self, _error_type: &str) {}
     /// Adjust the in-flight counter (no-op).
     pub fn in_flight_add(&self, _delta: i64) {}
+    /// Record iteration scheduling lag (no-op).
+    pub fn record_iteration_lag(&self, _lag_s: f64) {}
     /// Record an HTTP client request (no-op).
     pub fn record_http_request(
         &self,
         _duration_s: f64,
-        _method: &str,
-        _status: u16,
-        _host: &str,
-        _scheme: &str,
+        _request: &HttpClientAttributes<'_>,
+        _error_type: Option<&str>,
     ) {
     }
+    /// Record an HTTP response body size (no-op).
+    pub fn record_http_response_body_size(&self, _size: u64, _request: &HttpClientAttributes<'_>) {}
 }
 
 // ---------------------------------------------------------------------------
@@ -224,7 +348,7 @@ impl Meters {
 mod tests {
     #![allow(clippy::unwrap_used, clippy::expect_used, clippy::panic)]
 
-    use super::Meters;
+    use super::{HttpClientAttributes, Meters};
     use crate::telemetry::conventions::{attribute as brust_attr, metric as brust_metric};
     use opentelemetry::metrics::MeterProvider as _;
     use opentelemetry_sdk::metrics::{
@@ -286,6 +410,50 @@ mod tests {
         provider.shutdown().unwrap();
     }
 
+    #[test]
+    fn greeting_count_breaks_down_by_locale() {
+        let (provider, exporter) = test_provider();
+        let meters = Meters::from_meter(&provider.meter("test"));
+        meters.record_greeting("none", "en");
+        meters.record_greeting("none", "ja");
+        meters.record_greeting("none", "ja");
+
+        provider.force_flush().expect("flush failed");
+
+        let metrics = exporter.get_finished_metrics().expect("no data");
+        let metric = find_metric(&metrics, brust_metric::GREETING_COUNT)
+            .expect("brust.greeting.count not found");
+        let mut points: Vec<(String, String, u64)> = match metric.data() {
+            AggregatedMetrics::U64(MetricData::Sum(sum)) => sum
+                .data_points()
+                .map(|dp| {
+                    let value = |key: &str| {
+                        dp.attributes()
+                            .find(|kv| kv.key.as_str() == key)
+                            .map(|kv| kv.value.as_str().into_owned())
+                            .unwrap_or_default()
+                    };
+                    (
+                        value(brust_attr::GENDER),
+                        value(brust_attr::LOCALE),
+                        dp.value(),
+                    )
+                })
+                .collect(),
+            other => panic!("unexpected metric type: {other:?}"), // NOTEST(unreachable): exhaustive guard; OTel SDK returns expected type
+        };
+        points.sort();
+        assert_eq!(
+            points,
+            [
+                ("none".to_owned(), "en".to_owned(), 1),
+                ("none".to_owned(), "ja".to_owned(), 2),
+            ]
+        );
+
+        provider.shutdown().unwrap();
+    }
+
     #[test]
     fn iteration_histogram_records_all_durations() {
         let (provider, exporter) = test_provider();
@@ -320,6 +488,121 @@ mod tests {
         provider.shutdown().unwrap();
     }
 
+    #[test]
+    fn iteration_duration_records_the_delay_distribution() {
+        let (provider, exporter) = test_provider();
+        let meters = Meters::from_meter(&provider.meter("test"));
+        meters.record_iteration(1.0, "fixed");
+        meters.record_iteration(2.0, "uniform");
+        meters.record_iteration(4.0, "uniform");
+
+        provider.force_flush().expect("flush failed");
+
+        let metrics = exporter.get_finished_metrics().expect("no data");
+        let metric = find_metric(&metrics, brust_metric::ITERATION_DURATION)
+            .expect("brust.iteration.duration not found");
+        let mut points: Vec<(String, u64, f64)> = match metric.data() {
+            AggregatedMetrics::F64(MetricData::Histogram(hist)) => hist
+                .data_points()
+                .map(|dp| {
+                    let distribution = dp
+                        .attributes()
+                        .find(|kv| kv.key.as_str() == brust_attr::DELAY_DISTRIBUTION)
+                        .map(|kv| kv.value.as_str().into_owned())
+                        .unwrap_or_default();
+                    (distribution, dp.count(), dp.sum())
+                })
+                .collect(),
+            other => panic!("unexpected metric type: {other:?}"), // NOTEST(unreachable): exhaustive guard; OTel SDK returns expected type
+        };
+        points.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            points,
+            [("fixed".to_owned(), 1, 1.0), ("uniform".to_owned(), 2, 6.0)]
+        );
+
+        provider.shutdown().unwrap();
+    }
+
+    #[test]
+    fn rated_runs_record_iteration_lag() {
+        use crate::libs::count::{self, Runner, time::VirtualClock};
+
+        let (provider, exporter) = test_provider();
+        let meters = Meters::from_meter(&provider.meter("test"));
+        // Slots every 100 ms, but each iteration sleeps 250 ms: iterations
+        // 2 and 3 start 150 ms and 300 ms late.
+        let runner = Runner::new("fixed:250".parse().unwrap()).with_rate("10/s".parse().unwrap());
+        let results = runner.run(
+            Some(3),
+            &mut count::rng(Some(1)),
+            &VirtualClock::new(),
+            &meters,
+        );
+        assert_eq!(results.len(), 3);
+
+        provider.force_flush().expect("flush failed");
+
+        let metrics = exporter.get_finished_metrics().expect("no data");
+        let metric = find_metric(&metrics, brust_metric::ITERATION_LAG)
+            .expect("brust.iteration.lag not found");
+        assert_eq!(metric.unit(), "s");
+        let (count, sum, max) = match metric.data() {
+            AggregatedMetrics::F64(MetricData::Histogram(hist)) => {
+                let dp = hist.data_points().next().expect("no data points");
+                (dp.count(), dp.sum(), dp.max())
+            }
+            other => panic!("unexpected metric type: {other:?}"), // NOTEST(unreachable): exhaustive guard; OTel SDK returns expected type
+        };
+        assert_eq!(count, 3);
+        assert!((sum - 0.45).abs() < 1e-9, "{sum}");
+        assert!(max.is_some_and(|max| (max - 0.3).abs() < 1e-9), "{max:?}");
+
+        provider.shutdown().unwrap();
+    }
+
+    #[test]
+    fn iteration_errors_count_each_kind() {
+        use opentelemetry_semantic_conventions::attribute;
+
+        let (provider, exporter) = test_provider();
+        let meters = Meters::from_meter(&provider.meter("test"));
+        for kind in ["timeout", "panic_like", "timeout", "invalid", "timeout"] {
+            meters.record_iteration_error(kind);
+        }
+
+        provider.force_flush().expect("flush failed");
+
+        let metrics = exporter.get_finished_metrics().expect("no data");
+        let metric = find_metric(&metrics, brust_metric::ITERATION_ERRORS)
+            .expect("brust.iteration.errors not found");
+        let mut counts: Vec<(String, u64)> = match metric.data() {
+            AggregatedMetrics::U64(MetricData::Sum(sum)) => sum
+                .data_points()
+                .map(|dp| {
+                    let kind = dp
+                        .attributes()
+                        .find(|kv| kv.key.as_str() == attribute::ERROR_TYPE)
+                        .map(|kv| kv.value.as_str().into_owned())
+                        .unwrap_or_default();
+                    (kind, dp.value())
+                })
+                .collect(),
+            other => panic!("unexpected metric type: {other:?}"), // NOTEST(unreachable): exhaustive guard; OTel SDK returns expected type
+        };
+        counts.sort();
+        assert_eq!(
+            counts,
+            [
+                ("invalid".to_owned(), 1),
+                ("panic_like".to_owned(), 1),
+                ("timeout".to_owned(), 3),
+            ]
+        );
+
+        provider.shutdown().unwrap();
+    }
+
     #[test]
     fn in_flight_counter_tracks_net_change() {
         let (provider, exporter) = test_provider();
@@ -403,4 +686,100 @@ mod tests {
 
         provider.shutdown().unwrap();
     }
+
+    #[test]
+    fn failed_http_requests_record_error_type() {
+        use opentelemetry_semantic_conventions::{attribute, metric as semconv};
+
+        let (provider, exporter) = test_provider();
+        let meters = Meters::from_meter(&provider.meter("test"));
+        let request = |status, reused_connection| HttpClientAttributes {
+            method: "GET",
+            host: "example.com",
+            scheme: "https",
+            status,
+            reused_connection,
+        };
+        meters.record_http_request(0.2, &request(Some(200), Some(false)), None);
+        meters.record_http_request(0.3, &request(Some(503), Some(true)), Some("503"));
+        meters.record_http_request(5.0, &request(None, None), Some("timeout"));
+
+        provider.force_flush().expect("flush failed");
+
+        let metrics = exporter.get_finished_metrics().expect("no data");
+        let metric = find_metric(&metrics, semconv::HTTP_CLIENT_REQUEST_DURATION)
+            .expect("http.client.request.duration not found");
+        let mut points: Vec<(Option<String>, Option<String>, Option<String>)> = match metric.data()
+        {
+            AggregatedMetrics::F64(MetricData::Histogram(hist)) => hist
+                .data_points()
+                .map(|dp| {
+                    let value = |key: &str| {
+                        dp.attributes()
+                            .find(|kv| kv.key.as_str() == key)
+                            .map(|kv| kv.value.as_str().into_owned())
+                    };
+                    (
+                        value(attribute::HTTP_RESPONSE_STATUS_CODE),
+                        value(attribute::ERROR_TYPE),
+                        value(brust_attr::HTTP_CONNECTION_REUSED),
+                    )
+                })
+                .collect(),
+            other => panic!("unexpected metric type: {other:?}"), // NOTEST(unreachable): exhaustive guard; OTel SDK returns expected type
+        };
+        points.sort();
+        assert_eq!(
+            points,
+            [
+                (None, Some("timeout".to_owned()), None),
+                (Some("200".to_owned()), None, Some("false".to_owned())),
+                (
+                    Some("503".to_owned()),
+                    Some("503".to_owned()),
+                    Some("true".to_owned())
+                ),
+            ]
+        );
+
+        provider.shutdown().unwrap();
+    }
+
+    #[test]
+    fn http_response_body_size_records_bytes() {
+        use opentelemetry_semantic_conventions::{attribute, metric as semconv};
+
+        let (provider, exporter) = test_provider();
+        let meters = Meters::from_meter(&provider.meter("test"));
+        let request = HttpClientAttributes {
+            method: "GET",
+            host: "example.com",
+            scheme: "https",
+            status: Some(200),
+            reused_connection: Some(false),
+        };
+        meters.record_http_response_body_size(1_024, &request);
+        meters.record_http_response_body_size(2_048, &request);
+
+        provider.force_flush().expect("flush failed");
+
+        let metrics = exporter.get_finished_metrics().expect("no data");
+        let metric = find_metric(&metrics, semconv::HTTP_CLIENT_RESPONSE_BODY_SIZE)
+            .expect("http.client.response.body.size not found");
+        assert_eq!(metric.unit(), "By");
+        match metric.data() {
+            AggregatedMetrics::U64(MetricData::Histogram(hist)) => {
+                let dp = hist.data_points().next().expect("no data point");
+                assert_eq!(dp.count(), 2);
+                assert_eq!(dp.sum(), 3_072);
+                assert!(
+                    dp.attributes()
+                        .any(|kv| kv.key.as_str() == attribute::HTTP_RESPONSE_STATUS_CODE)
+                );
+            }
+            other => panic!("unexpected metric type: {other:?}"), // NOTEST(unreachable): exhaustive guard; OTel SDK returns expected type
+        }
+
+        provider.shutdown().unwrap();
+    }
 }
@@ -0,0 +1,210 @@
+//! Integration test for the pooled `libs::http::Client` against local
+//! servers: connection reuse, requests with a method, headers and body,
+//! runs over several URLs and the response body size limit.
+#![allow(clippy::unwrap_used)]
+#![allow(clippy::indexing_slicing)]
+#![allow(missing_docs)]
+
+use std::thread;
+
+use brust::libs::http::{
+    Client, ClientOptions, FetchError, FetchResult, Request, fetch, fetch_url,
+};
+use brust::{CancelToken, Meters};
+use reqwest::Method;
+
+/// Serve `/` on a random port, answering every request with `ok`.
+async fn start_ok_server() -> u16 {
+    use axum::{Router, routing::get};
+
+    let _ = rustls::crypto::ring::default_provider().install_default();
+
+    let app = Router::new().route("/", get(|| async { "ok" }));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap(); // NOTEST(unreachable): test server panic path; unreachable in passing tests
+    });
+    port
+}
+
+#[tokio::test]
+#[cfg_attr(miri, ignore)] // reqwest blocking -> setsockopt SO_KEEPALIVE unsupported under Miri
+async fn client_reuses_its_connection() {
+    let port = start_ok_server().await;
+    let url = format!("http://127.0.0.1:{port}/");
+    tokio::task::spawn_blocking(move || {
+        let meters = Meters::noop();
+        let client = Client::new(ClientOptions::default()).expect("client");
+        let request = Request::get(&url);
+        let first = client.fetch(&request, &meters).expect("first fetch");
+        assert!(!first.reused_connection);
+        let second = client
+            .fetch_cancellable(&request, &meters, &CancelToken::new())
+            .expect("second fetch");
+        assert!(second.reused_connection);
+        assert_eq!(client.connections_opened(), 1);
+
+        // Every free function call builds its own client.
+        let once = fetch_url(&url, &ClientOptions::default(), &meters).expect("fetch_url");
+        assert!(!once.reused_connection);
+    })
+    .await
+    .expect("spawn_blocking panicked");
+}
+
+#[tokio::test]
+#[cfg_attr(miri, ignore)] // reqwest blocking -> setsockopt SO_KEEPALIVE unsupported under Miri
+async fn fetch_sends_method_headers_and_body() {
+    use axum::{Router, body::Bytes, http::HeaderMap, routing::any};
+    use std::sync::{Arc, Mutex};
+
+    let _ = rustls::crypto::ring::default_provider().install_default();
+
+    let seen = Arc::new(Mutex::new(None));
+    let seen_in_handler = Arc::clone(&seen);
+    let app = Router::new().route(
+        "/",
+        any(
+            move |method: axum::http::Method, headers: HeaderMap, body: Bytes| async move {
+                let header = |name: &str| {
+                    headers
+                        .get_all(name)
+                        .iter()
+                        .filter_map(|v| v.to_str().ok())
+                        .collect::<Vec<_>>()
+                        .join(",")
+                };
+                *seen_in_handler.lock().unwrap() = Some((
+                    method.to_string(),
+                    header("x-tag"),
+                    header("content-type"),
+                    body.to_vec(),
+                ));
+                (axum::http::StatusCode::CREATED, "created")
+            },
+        ),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind failed");
+    let port = listener.local_addr().expect("local_addr failed").port();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.expect("server error"); // NOTEST(unreachable): test server panic path; unreachable in passing tests
+    });
+
+    let request = Request::new(Method::PATCH, format!("http://127.0.0.1:{port}/"))
+        .with_header("X-Tag: a".parse().expect("valid header"))
+        .with_header("X-Tag: b".parse().expect("valid header"))
+        .with_body("[1, 2]");
+    let result = tokio::task::spawn_blocking(move || {
+        fetch(&request, &ClientOptions::default(), &Meters::default())
+    })
+    .await
+    .expect("spawn_blocking panicked")
+    .expect("expected Ok for PATCH");
+    assert_eq!(result.status, 201);
+    assert_eq!(
+        seen.lock().unwrap().take(),
+        Some((
+            String::from("PATCH"),
+            String::from("a,b"),
+            String::from("application/json"),
+            b"[1, 2]".to_vec(),
+        ))
+    );
+}
+
+#[tokio::test]
+#[cfg_attr(miri, ignore)] // reqwest blocking -> setsockopt SO_KEEPALIVE unsupported under Miri
+async fn fetch_all_continues_past_failures_and_stops_when_cancelled() {
+    let port = start_ok_server().await;
+    let url = format!("http://127.0.0.1:{port}/");
+    let (fetched, responses, cancelled) = tokio::task::spawn_blocking(move || {
+        let client = Client::new(ClientOptions::default()).expect("client");
+        let requests = [Request::get("not-a-url"), Request::get(&url)];
+        let mut responses = Vec::new();
+        let fetched = client.fetch_all(
+            &requests,
+            &Meters::noop(),
+            &CancelToken::new(),
+            None,
+            |request, result| responses.push((request.url().to_owned(), result.status)),
+        );
+
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        let twice = [requests[1].clone(), requests[1].clone()];
+        let cancelled = client.fetch_all(&twice, &Meters::noop(), &cancel, None, |_, _| {});
+        (fetched, responses, cancelled)
+    })
+    .await
+    .expect("spawn_blocking panicked");
+
+    assert!(matches!(
+        fetched.as_slice(),
+        [
+            (_, Err(FetchError::InvalidUrl(_))),
+            (_, Ok(FetchResult { status: 200, .. }))
+        ]
+    ));
+    assert_eq!(responses, [(format!("http://127.0.0.1:{port}/"), 200)]);
+    assert!(matches!(
+        cancelled.as_slice(),
+        [(_, Err(FetchError::Cancelled))]
+    ));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)] // reqwest blocking -> setsockopt SO_KEEPALIVE unsupported under Miri
+fn bodies_over_the_max_size_are_not_saved_past_it() {
+    use std::io::{Read as _, Write as _};
+
+    let _ = rustls::crypto::ring::default_provider().install_default();
+
+    // `/sized` announces its length; the other path streams 256 KiB
+    // until the connection closes.
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind failed");
+    let port = listener.local_addr().expect("local_addr").port();
+    thread::spawn(move || {
+        while let Ok((mut stream, _)) = listener.accept() {
+            let mut buf = [0_u8; 1024];
+            let read = stream.read(&mut buf).unwrap_or_default();
+            let body = vec![b'x'; 256 * 1024];
+            let head = if buf
+                .get(..read)
+                .unwrap_or_default()
+                .starts_with(b"GET /sized ")
+            {
+                format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", body.len())
+            } else {
+                String::from("HTTP/1.1 200 OK\r\nConnection: close\r\n\r\n")
+            };
+            let _ = stream.write_all(head.as_bytes());
+            let _ = stream.write_all(&body);
+        }
+    });
+
+    let options = ClientOptions {
+        max_body_size: Some(100 * 1024),
+        ..ClientOptions::default()
+    };
+    let client = Client::new(options).expect("client");
+    for path in ["sized", "streamed"] {
+        let mut saved = Vec::new();
+        let request = Request::get(format!("http://127.0.0.1:{port}/{path}"));
+        let error = client
+            .fetch_to(&request, &Meters::noop(), &CancelToken::new(), &mut saved)
+            .expect_err("expected the body to be too large");
+        assert!(
+            matches!(error, FetchError::BodyTooLarge(102_400)),
+            "{error:?}"
+        );
+        assert_eq!(error.error_type(), "body_too_large");
+        assert_eq!(
+            error.to_string(),
+            "HTTP response body is larger than 100KiB"
+        );
+        assert!(saved.len() <= 100 * 1024, "{path}: {}", saved.len());
+    }
+}
@@ -0,0 +1,106 @@
+//! Integration test for the `brust.http.connection.reused` span and metric
+//! attribute recorded by `libs::http` for two fetches to one axum server.
+#![cfg(feature = "otel")]
+#![allow(clippy::unwrap_used)]
+#![allow(clippy::panic)]
+#![allow(missing_docs)]
+
+use axum::{Router, routing::get};
+use brust::Meters;
+use brust::libs::http::{Client, ClientOptions, Request};
+use brust::telemetry::conventions::attribute::HTTP_CONNECTION_REUSED;
+use opentelemetry::trace::{SpanKind, TracerProvider as _};
+use opentelemetry::{Key, Value};
+use opentelemetry_sdk::metrics::data::{
+    AggregatedMetrics, MetricData, ResourceMetrics, ScopeMetrics,
+};
+use opentelemetry_sdk::metrics::{InMemoryMetricExporter, PeriodicReader, SdkMeterProvider};
+use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider};
+use opentelemetry_semantic_conventions::metric as semconv;
+use tracing_subscriber::layer::SubscriberExt as _;
+
+#[tokio::test]
+#[cfg_attr(miri, ignore)] // reqwest blocking -> setsockopt SO_KEEPALIVE unsupported under Miri
+async fn second_fetch_reuses_the_connection() {
+    let _ = rustls::crypto::ring::default_provider().install_default();
+    let app = Router::new().route("/", get(|| async { "ok" }));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap(); // NOTEST(unreachable): test server panic path; unreachable in passing tests
+    });
+
+    let metric_exporter = InMemoryMetricExporter::default();
+    let meter_provider = SdkMeterProvider::builder()
+        .with_reader(PeriodicReader::builder(metric_exporter.clone()).build())
+        .build();
+    opentelemetry::global::set_meter_provider(meter_provider.clone());
+    let span_exporter = InMemorySpanExporter::default();
+    let tracer_provider = SdkTracerProvider::builder()
+        .with_simple_exporter(span_exporter.clone())
+        .build();
+    let subscriber = tracing_subscriber::registry()
+        .with(tracing_opentelemetry::layer().with_tracer(tracer_provider.tracer("test")));
+
+    let url = format!("http://127.0.0.1:{port}/");
+    let reused = tokio::task::spawn_blocking(move || {
+        tracing::subscriber::with_default(subscriber, || {
+            let meters = Meters::new();
+            let client = Client::new(ClientOptions::default()).unwrap();
+            let request = Request::get(&url);
+            [
+                client.fetch(&request, &meters).unwrap().reused_connection,
+                client.fetch(&request, &meters).unwrap().reused_connection,
+            ]
+        })
+    })
+    .await
+    .unwrap();
+    assert_eq!(reused, [false, true]);
+
+    tracer_provider.force_flush().unwrap();
+    let spans = span_exporter.get_finished_spans().unwrap();
+    let span_values: Vec<Option<Value>> = spans
+        .iter()
+        .filter(|span| span.span_kind == SpanKind::Client)
+        .map(|span| {
+            span.attributes
+                .iter()
+                .find(|kv| kv.key == Key::from_static_str(HTTP_CONNECTION_REUSED))
+                .map(|kv| kv.value.clone())
+        })
+        .collect();
+    assert_eq!(
+        span_values,
+        [Some(Value::Bool(false)), Some(Value::Bool(true))]
+    );
+
+    meter_provider.force_flush().unwrap();
+    let metrics = metric_exporter.get_finished_metrics().unwrap();
+    let metric = metrics
+        .iter()
+        .flat_map(ResourceMetrics::scope_metrics)
+        .flat_map(ScopeMetrics::metrics)
+        .find(|m| m.name() == semconv::HTTP_CLIENT_REQUEST_DURATION)
+        .expect("http.client.request.duration not found");
+    let AggregatedMetrics::F64(MetricData::Histogram(histogram)) = metric.data() else {
+        panic!("unexpected metric type: {:?}", metric.data()); // NOTEST(unreachable): exhaustive guard; OTel SDK returns expected type
+    };
+    let mut points: Vec<(Option<String>, u64)> = histogram
+        .data_points()
+        .map(|dp| {
+            let reused = dp
+                .attributes()
+                .find(|kv| kv.key.as_str() == HTTP_CONNECTION_REUSED)
+                .map(|kv| kv.value.as_str().into_owned());
+            (reused, dp.count())
+        })
+        .collect();
+    points.sort();
+    assert_eq!(
+        points,
+        [(Some("false".to_owned()), 1), (Some("true".to_owned()), 1)]
+    );
+
+    meter_provider.shutdown().unwrap();
+}
@@ -0,0 +1,118 @@
+//! Integration test for the `http.client.request.duration` points recorded
+//! by `libs::http` for requests that got no response, and the
+//! `http.client.response.body.size` points of those that did.
+#![cfg(feature = "otel")]
+#![allow(clippy::unwrap_used)]
+#![allow(clippy::panic)]
+#![allow(clippy::indexing_slicing)]
+#![allow(missing_docs)]
+
+use std::io::{Read as _, Write as _};
+use std::net::TcpListener;
+use std::time::Duration;
+
+use brust::Meters;
+use brust::libs::http::{ClientOptions, fetch_url};
+use opentelemetry_sdk::metrics::data::{
+    AggregatedMetrics, MetricData, ResourceMetrics, ScopeMetrics,
+};
+use opentelemetry_sdk::metrics::{InMemoryMetricExporter, PeriodicReader, SdkMeterProvider};
+use opentelemetry_semantic_conventions::{attribute, metric as semconv};
+
+#[test]
+#[cfg_attr(miri, ignore)] // reqwest blocking -> setsockopt SO_KEEPALIVE unsupported under Miri
+fn requests_are_recorded_with_their_error_type_and_body_size() {
+    let _ = rustls::crypto::ring::default_provider().install_default();
+    let exporter = InMemoryMetricExporter::default();
+    let provider = SdkMeterProvider::builder()
+        .with_reader(PeriodicReader::builder(exporter.clone()).build())
+        .build();
+    opentelemetry::global::set_meter_provider(provider.clone());
+    let meters = Meters::new();
+
+    // Bind and drop to get a port that is not listening.
+    let refused = TcpListener::bind("127.0.0.1:0").unwrap();
+    let refused_url = format!("http://{}/", refused.local_addr().unwrap());
+    drop(refused);
+    let error = fetch_url(&refused_url, &ClientOptions::default(), &meters).unwrap_err();
+    assert_eq!(error.error_type(), "connection_refused");
+
+    // Connections are queued by the OS but never answered.
+    let hanging = TcpListener::bind("127.0.0.1:0").unwrap();
+    let hanging_url = format!("http://{}/", hanging.local_addr().unwrap());
+    let options = ClientOptions {
+        timeout: Some(Duration::from_millis(200)),
+        ..ClientOptions::default()
+    };
+    let error = fetch_url(&hanging_url, &options, &meters).unwrap_err();
+    assert_eq!(error.error_type(), "timeout");
+
+    // Answers every request with a five-byte body.
+    let answering = TcpListener::bind("127.0.0.1:0").unwrap();
+    let answering_url = format!("http://{}/", answering.local_addr().unwrap());
+    std::thread::spawn(move || {
+        while let Ok((mut stream, _)) = answering.accept() {
+            let mut buf = [0_u8; 1024];
+            let _ = stream.read(&mut buf);
+            let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello");
+        }
+    });
+    let result = fetch_url(&answering_url, &ClientOptions::default(), &meters).unwrap();
+    assert_eq!(result.body_size, 5);
+
+    provider.force_flush().unwrap();
+    let metrics = exporter.get_finished_metrics().unwrap();
+    let find = |name: &str| {
+        metrics
+            .iter()
+            .flat_map(ResourceMetrics::scope_metrics)
+            .flat_map(ScopeMetrics::metrics)
+            .find(|m| m.name() == name)
+    };
+    let metric = find(semconv::HTTP_CLIENT_REQUEST_DURATION)
+        .expect("http.client.request.duration not found");
+    let AggregatedMetrics::F64(MetricData::Histogram(histogram)) = metric.data() else {
+        panic!("unexpected metric type: {:?}", metric.data()); // NOTEST(unreachable): exhaustive guard; OTel SDK returns expected type
+    };
+    let mut points: Vec<(String, Option<String>, f64)> = histogram
+        .data_points()
+        .map(|dp| {
+            let value = |key: &str| {
+                dp.attributes()
+                    .find(|kv| kv.key.as_str() == key)
+                    .map(|kv| kv.value.as_str().into_owned())
+            };
+            (
+                value(attribute::ERROR_TYPE).unwrap_or_default(),
+                value(attribute::HTTP_RESPONSE_STATUS_CODE),
+                dp.sum(),
+            )
+        })
+        .collect();
+    points.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(points.len(), 3, "{points:?}");
+    assert_eq!(points[0].0, "");
+    assert_eq!(points[0].1.as_deref(), Some("200"));
+    assert_eq!(points[1].0, "connection_refused");
+    assert_eq!(points[2].0, "timeout");
+    assert!(
+        points[1..].iter().all(|point| point.1.is_none()),
+        "{points:?}"
+    );
+    // The timed-out attempt is recorded with the time it took to give up.
+    assert!(points[2].2 >= 0.2, "{points:?}");
+
+    // Only the response that was read in full has a body size.
+    let metric = find(semconv::HTTP_CLIENT_RESPONSE_BODY_SIZE)
+        .expect("http.client.response.body.size not found");
+    let AggregatedMetrics::U64(MetricData::Histogram(histogram)) = metric.data() else {
+        panic!("unexpected metric type: {:?}", metric.data()); // NOTEST(unreachable): exhaustive guard; OTel SDK returns expected type
+    };
+    let sizes: Vec<(u64, u64)> = histogram
+        .data_points()
+        .map(|dp| (dp.count(), dp.sum()))
+        .collect();
+    assert_eq!(sizes, [(1, 5)]);
+
+    provider.shutdown().unwrap();
+}
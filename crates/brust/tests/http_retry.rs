@@ -0,0 +1,142 @@
+//! Integration test for the spans of retried requests from `libs::http`
+//! against a local flaky server.
+#![cfg(feature = "otel")]
+#![allow(clippy::unwrap_used)]
+#![allow(clippy::indexing_slicing)]
+#![allow(missing_docs)]
+
+use std::num::NonZeroU32;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::{Router, http::HeaderMap, http::StatusCode, routing::get};
+use brust::Meters;
+use brust::libs::http::retry::{Jitter, Policy};
+use brust::libs::http::{ClientOptions, fetch_url};
+use brust::telemetry::conventions::attribute as brust_attr;
+use opentelemetry::trace::{SpanKind, Status, TracerProvider as _};
+use opentelemetry::{Key, Value};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider, SpanData};
+use tracing_subscriber::layer::SubscriberExt as _;
+
+/// Serve `/` on a random port, answering `503` to the first two requests and
+/// `200` afterwards; remembers the `traceparent` of every request.
+async fn start_flaky_server() -> (u16, Arc<Mutex<Vec<String>>>) {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let seen_in_handler = Arc::clone(&seen);
+    let app = Router::new().route(
+        "/",
+        get(move |headers: HeaderMap| async move {
+            let mut seen = seen_in_handler.lock().unwrap();
+            seen.push(
+                headers
+                    .get("traceparent")
+                    .and_then(|v| v.to_str().ok())
+                    .unwrap_or_default()
+                    .to_owned(),
+            );
+            if seen.len() <= 2 {
+                StatusCode::SERVICE_UNAVAILABLE
+            } else {
+                StatusCode::OK
+            }
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap(); // NOTEST(unreachable): test server panic path; unreachable in passing tests
+    });
+    (port, seen)
+}
+
+fn attribute<'a>(span: &'a SpanData, key: &'static str) -> Option<&'a Value> {
+    span.attributes
+        .iter()
+        .find(|kv| kv.key == Key::from_static_str(key))
+        .map(|kv| &kv.value)
+}
+
+#[tokio::test]
+#[cfg_attr(miri, ignore)] // reqwest blocking -> setsockopt SO_KEEPALIVE unsupported under Miri
+async fn every_attempt_is_a_client_span_with_its_resend_count() {
+    let _ = rustls::crypto::ring::default_provider().install_default();
+    opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+    let (port, seen) = start_flaky_server().await;
+
+    let exporter = InMemorySpanExporter::default();
+    let provider = SdkTracerProvider::builder()
+        .with_simple_exporter(exporter.clone())
+        .build();
+    let subscriber = tracing_subscriber::registry()
+        .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+    let url = format!("http://127.0.0.1:{port}/");
+    let result = tokio::task::spawn_blocking(move || {
+        tracing::subscriber::with_default(subscriber, || {
+            let options = ClientOptions {
+                retry: Policy {
+                    max_attempts: NonZeroU32::new(3).unwrap(),
+                    base_delay: Duration::from_millis(10),
+                    jitter: Jitter::NONE,
+                    ..Policy::default()
+                },
+                ..ClientOptions::default()
+            };
+            fetch_url(&url, &options, &Meters::noop())
+        })
+    })
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!((result.status, result.attempts), (200, 3));
+
+    provider.force_flush().unwrap();
+    let spans = exporter.get_finished_spans().unwrap();
+    let parent = spans.iter().find(|span| span.name == "fetch_url").unwrap();
+    assert_eq!(parent.span_kind, SpanKind::Internal);
+    let attempts: Vec<_> = spans
+        .iter()
+        .filter(|span| span.span_kind == SpanKind::Client)
+        .collect();
+    assert_eq!(attempts.len(), 3);
+
+    let mut traceparents = Vec::new();
+    for (resends, span) in attempts.iter().enumerate() {
+        assert_eq!(span.name, "GET");
+        assert_eq!(span.parent_span_id, parent.span_context.span_id());
+        let resend_count = attribute(span, "http.request.resend_count");
+        if resends == 0 {
+            assert_eq!(resend_count, None);
+        } else {
+            assert_eq!(resend_count, Some(&Value::I64(resends.try_into().unwrap())));
+        }
+        // Retries go over the connection the first attempt opened.
+        assert_eq!(
+            attribute(span, brust_attr::HTTP_CONNECTION_REUSED),
+            Some(&Value::Bool(resends > 0))
+        );
+        traceparents.push(format!(
+            "00-{}-{}-01",
+            span.span_context.trace_id(),
+            span.span_context.span_id()
+        ));
+    }
+
+    for span in &attempts[..2] {
+        assert_eq!(
+            attribute(span, "http.response.status_code"),
+            Some(&Value::I64(503))
+        );
+        assert_eq!(attribute(span, "error.type"), Some(&"503".into()));
+        assert!(matches!(span.status, Status::Error { .. }), "{span:?}");
+    }
+    assert_eq!(
+        attribute(attempts[2], "http.response.status_code"),
+        Some(&Value::I64(200))
+    );
+    assert_eq!(attempts[2].status, Status::Unset);
+    // Each attempt carries its own span as the server-side parent.
+    assert_eq!(*seen.lock().unwrap(), traceparents);
+}
@@ -1,4 +1,5 @@
 #![allow(clippy::unwrap_used)] // テストコードではunwrapを許可
+#![allow(clippy::indexing_slicing)] // テストコードではJSONのインデックスアクセスを許可
 #![allow(missing_docs)] // テストコードではdocコメント不要
 
 use std::net::TcpListener;
@@ -7,6 +8,14 @@ use std::time::Duration;
 use assert_cmd::cargo_bin_cmd;
 use predicates::prelude::{PredicateBooleanExt, predicate};
 
+/// Build a `brust` command pinned to the `C` locale so greeting assertions do
+/// not depend on the developer's `LANG`.
+fn brust_cmd() -> assert_cmd::Command {
+    let mut cmd = cargo_bin_cmd!("brust");
+    cmd.env("LC_ALL", "C");
+    cmd
+}
+
 /// Spawn a minimal HTTP server that accepts connections and returns 200 for any request.
 /// Reused for both plain HTTP fetch tests and OTLP receiver stubbing.
 fn start_fake_http_server() -> u16 {
@@ -26,10 +35,99 @@ fn start_fake_http_server() -> u16 {
     port
 }
 
+/// Spawn an HTTP server that keeps each connection open and answers 200 to
+/// every request on it.
+fn start_keep_alive_http_server() -> u16 {
+    use std::io::{Read as _, Write as _};
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    std::thread::spawn(move || {
+        while let Ok((mut stream, _)) = listener.accept() {
+            std::thread::spawn(move || {
+                let mut buf = [0u8; 4096];
+                // Requests have no body, so each read is one request.
+                while matches!(stream.read(&mut buf), Ok(n) if n > 0) {
+                    let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok");
+                }
+            });
+        }
+    });
+
+    port
+}
+
+/// Spawn an HTTP server that answers 503 to the first `failures` requests
+/// and 200 to the rest.
+fn start_flaky_http_server(failures: usize) -> u16 {
+    use std::io::{Read as _, Write as _};
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    std::thread::spawn(move || {
+        for (seen, stream) in listener.incoming().enumerate() {
+            let Ok(mut stream) = stream else { return };
+            let mut buf = [0u8; 4096];
+            let _ = stream.read(&mut buf);
+            let response: &[u8] = if seen < failures {
+                b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+            } else {
+                b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok"
+            };
+            let _ = stream.write_all(response);
+        }
+    });
+
+    port
+}
+
+/// Spawn an HTTP server that answers 200 to one request and sends back the
+/// raw request it received.
+fn start_capturing_http_server() -> (u16, std::sync::mpsc::Receiver<String>) {
+    use std::io::{Read as _, Write as _};
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let (tx, rx) = std::sync::mpsc::channel();
+
+    std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = Vec::new();
+        let mut buf = [0u8; 4096];
+        // Read until the headers and the announced body have arrived.
+        loop {
+            let text = String::from_utf8_lossy(&request).into_owned();
+            if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                let length = head
+                    .lines()
+                    .find_map(|line| {
+                        line.to_ascii_lowercase()
+                            .strip_prefix("content-length:")
+                            .map(|v| v.trim().parse::<usize>().unwrap())
+                    })
+                    .unwrap_or(0);
+                if body.len() >= length {
+                    let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok");
+                    let _ = tx.send(text);
+                    return;
+                }
+            }
+            match stream.read(&mut buf) {
+                Ok(0) | Err(_) => return,
+                Ok(n) => request.extend_from_slice(&buf[..n]),
+            }
+        }
+    });
+
+    (port, rx)
+}
+
 #[test]
 #[cfg_attr(miri, ignore)]
 fn test_cli_with_custom_name() {
-    let mut cmd = cargo_bin_cmd!("brust");
+    let mut cmd = brust_cmd();
     cmd.arg("--name")
         .arg("Alice")
         .assert()
@@ -40,7 +138,7 @@ fn test_cli_with_custom_name() {
 #[test]
 #[cfg_attr(miri, ignore)]
 fn test_cli_with_short_flag() {
-    let mut cmd = cargo_bin_cmd!("brust");
+    let mut cmd = brust_cmd();
     cmd.arg("-n")
         .arg("Bob")
         .assert()
@@ -51,7 +149,7 @@ fn test_cli_with_short_flag() {
 #[test]
 #[cfg_attr(miri, ignore)]
 fn test_cli_version_flag() {
-    let mut cmd = cargo_bin_cmd!("brust");
+    let mut cmd = brust_cmd();
     cmd.arg("--version")
         .assert()
         .success()
@@ -62,7 +160,7 @@ fn test_cli_version_flag() {
 #[test]
 #[cfg_attr(miri, ignore)]
 fn test_cli_version_short_flag() {
-    let mut cmd = cargo_bin_cmd!("brust");
+    let mut cmd = brust_cmd();
     cmd.arg("-V")
         .assert()
         .success()
@@ -73,7 +171,7 @@ fn test_cli_version_short_flag() {
 #[test]
 #[cfg_attr(miri, ignore)]
 fn test_cli_with_gender_man() {
-    let mut cmd = cargo_bin_cmd!("brust");
+    let mut cmd = brust_cmd();
     cmd.arg("--name")
         .arg("John")
         .arg("--gender")
@@ -86,7 +184,7 @@ fn test_cli_with_gender_man() {
 #[test]
 #[cfg_attr(miri, ignore)]
 fn test_cli_with_gender_woman() {
-    let mut cmd = cargo_bin_cmd!("brust");
+    let mut cmd = brust_cmd();
     cmd.arg("--name")
         .arg("Alice")
         .arg("--gender")
@@ -99,7 +197,7 @@ fn test_cli_with_gender_woman() {
 #[test]
 #[cfg_attr(miri, ignore)]
 fn test_cli_with_gender_short_flag() {
-    let mut cmd = cargo_bin_cmd!("brust");
+    let mut cmd = brust_cmd();
     cmd.arg("-n")
         .arg("Bob")
         .arg("-g")
@@ -112,22 +210,21 @@ fn test_cli_with_gender_short_flag() {
 #[test]
 #[cfg_attr(miri, ignore)]
 fn test_cli_with_invalid_gender() {
-    let mut cmd = cargo_bin_cmd!("brust");
+    let mut cmd = brust_cmd();
     cmd.arg("--name")
         .arg("Charlie")
         .arg("--gender")
         .arg("other")
         .assert()
-        .success()
-        .stdout(predicate::str::contains(
-            "Hi, Charlie (invalid gender: other), new world!!",
-        ));
+        .code(3)
+        .stdout("Hi, Charlie, new world!!\n")
+        .stderr(predicate::str::contains("invalid gender: other"));
 }
 
 #[test]
 #[cfg_attr(miri, ignore)]
 fn test_cli_without_gender() {
-    let mut cmd = cargo_bin_cmd!("brust");
+    let mut cmd = brust_cmd();
     cmd.arg("--name")
         .arg("Dave")
         .assert()
@@ -138,31 +235,35 @@ fn test_cli_without_gender() {
 #[test]
 #[cfg_attr(miri, ignore)]
 fn test_cli_count_basic() {
-    let mut cmd = cargo_bin_cmd!("brust");
+    let mut cmd = brust_cmd();
     cmd.arg("-c")
         .arg("1")
         .timeout(Duration::from_secs(10))
         .assert()
         .success()
-        .stdout(predicate::str::contains("starting iteration"))
-        .stdout(predicate::str::contains("finished iteration"));
+        .stdout(predicate::str::contains("iteration 1: "))
+        .stdout(predicate::str::contains("\ncount          1\n"))
+        .stderr(predicate::str::contains("starting iteration"))
+        .stderr(predicate::str::contains("finished iteration"));
 }
 
 #[test]
 #[cfg_attr(miri, ignore)]
 fn test_cli_count_zero() {
-    let mut cmd = cargo_bin_cmd!("brust");
+    let mut cmd = brust_cmd();
     cmd.arg("--count")
         .arg("0")
         .assert()
         .success()
-        .stdout(predicate::str::contains("starting iteration").not());
+        .stdout(predicate::str::contains("count          0\n"))
+        .stdout(predicate::str::contains("total      0.000 s\n"))
+        .stderr(predicate::str::contains("starting iteration").not());
 }
 
 #[test]
 #[cfg_attr(miri, ignore)]
 fn test_cli_count_with_name() {
-    let mut cmd = cargo_bin_cmd!("brust");
+    let mut cmd = brust_cmd();
     cmd.arg("-n")
         .arg("Alice")
         .arg("-c")
@@ -171,7 +272,8 @@ fn test_cli_count_with_name() {
         .assert()
         .success()
         .stdout(predicate::str::contains("Hi, Alice, new world!!"))
-        .stdout(predicate::str::contains("finished iteration"));
+        .stdout(predicate::str::contains("iteration 1: "))
+        .stderr(predicate::str::contains("finished iteration"));
 }
 
 #[test]
@@ -179,7 +281,7 @@ fn test_cli_count_with_name() {
 fn test_cli_url_fetch_success() {
     let port = start_fake_http_server();
 
-    let mut cmd = cargo_bin_cmd!("brust");
+    let mut cmd = brust_cmd();
     cmd.arg("--url")
         .arg(format!("http://127.0.0.1:{port}/"))
         .timeout(Duration::from_secs(15))
@@ -196,12 +298,14 @@ fn test_cli_url_fetch_connection_refused() {
         l.local_addr().unwrap().port()
     };
 
-    let mut cmd = cargo_bin_cmd!("brust");
+    let mut cmd = brust_cmd();
     cmd.arg("--url")
         .arg(format!("http://127.0.0.1:{port}/"))
         .timeout(Duration::from_secs(15))
         .assert()
-        .success(); // exits 0 even on HTTP error (logs error, continues)
+        .code(4)
+        .stdout(predicate::str::contains("Hi, Youre, new world!!"))
+        .stderr(predicate::str::contains("HTTP request failed"));
 }
 
 #[cfg(feature = "otel")]
@@ -210,7 +314,7 @@ fn test_cli_url_fetch_connection_refused() {
 fn test_cli_otel_init_and_shutdown() {
     let port = start_fake_http_server();
 
-    let mut cmd = cargo_bin_cmd!("brust");
+    let mut cmd = brust_cmd();
     cmd.arg("--name")
         .arg("OTel")
         .env(
@@ -222,3 +326,1176 @@ fn test_cli_otel_init_and_shutdown() {
         .success()
         .stdout(predicate::str::contains("Hi, OTel, new world!!"));
 }
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_otel_per_signal_endpoint_wins() {
+    let port = start_fake_http_server();
+    let (traces_port, requests) = start_capturing_http_server();
+
+    brust_cmd()
+        .args(["greet", "--name", "OTel"])
+        .env(
+            "OTEL_EXPORTER_OTLP_ENDPOINT",
+            format!("http://127.0.0.1:{port}"),
+        )
+        .env(
+            "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
+            format!("http://127.0.0.1:{traces_port}/custom/traces"),
+        )
+        .timeout(Duration::from_secs(30))
+        .assert()
+        .success();
+
+    let request = requests.recv_timeout(Duration::from_secs(10)).unwrap();
+    assert!(request.starts_with("POST /custom/traces "), "{request}");
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_greet_subcommand() {
+    let mut cmd = brust_cmd();
+    cmd.arg("greet")
+        .arg("--name")
+        .arg("Alice")
+        .arg("--gender")
+        .arg("woman")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Hi, Ms. Alice, new world!!"))
+        .stderr(predicate::str::contains("deprecated").not());
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_greet_subcommand_invalid_gender_fails() {
+    let mut cmd = brust_cmd();
+    cmd.arg("greet")
+        .arg("-n")
+        .arg("Charlie")
+        .arg("-g")
+        .arg("other")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("invalid value 'other'"))
+        .stderr(predicate::str::contains("neutral"));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_greet_subcommand_extended_honorifics() {
+    for (gender, expected) in [
+        ("neutral", "Hi, Mx. Sam, new world!!"),
+        ("dr", "Hi, Dr. Sam, new world!!"),
+        ("prof", "Hi, Prof. Sam, new world!!"),
+    ] {
+        brust_cmd()
+            .args(["greet", "-n", "Sam", "--gender", gender])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(expected));
+    }
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_greet_subcommand_custom_honorific() {
+    brust_cmd()
+        .args(["greet", "-n", "Galahad", "--honorific", "Sir"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Hi, Sir Galahad, new world!!"));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_greet_subcommand_invalid_custom_honorific_fails() {
+    brust_cmd()
+        .args(["greet", "-n", "Galahad", "--honorific", " "])
+        .assert()
+        .code(3)
+        .stderr(predicate::str::contains("invalid honorific prefix"))
+        .stdout(predicate::str::contains("Hi, Galahad, new world!!"));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_greet_subcommand_template() {
+    brust_cmd()
+        .args(["greet", "-n", "Bob", "-g", "dr"])
+        .args(["--template", "Good day, {title} {name}! [{locale}]"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Good day, Dr. Bob! [en]"))
+        .stdout(predicate::str::contains("new world").not());
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_greet_template_from_config_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    std::fs::write(&path, "[greet]\ntemplate = \"Welcome, {addressee}.\"\n").unwrap();
+
+    brust_cmd()
+        .arg("--config")
+        .arg(&path)
+        .args(["greet", "-n", "花子", "-g", "woman", "-l", "ja"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Welcome, 花子さん."));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_greet_subcommand_invalid_template_fails() {
+    brust_cmd()
+        .args(["greet", "-n", "Bob", "--template", "Hi {nmae}"])
+        .assert()
+        .code(3)
+        .stderr(predicate::str::contains(
+            "invalid greeting template: unknown placeholder `{nmae}`",
+        ))
+        .stdout(predicate::str::contains("Hi, Bob, new world!!"));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_count_subcommand_zero() {
+    let mut cmd = brust_cmd();
+    cmd.arg("count")
+        .arg("--count")
+        .arg("0")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("new world!!").not());
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_fetch_subcommand_does_not_greet() {
+    let port = start_fake_http_server();
+
+    let mut cmd = brust_cmd();
+    cmd.arg("fetch")
+        .arg("--url")
+        .arg(format!("http://127.0.0.1:{port}/"))
+        .timeout(Duration::from_secs(15))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "200 http://127.0.0.1:{port}/ in "
+        )))
+        .stderr(predicate::str::contains("HTTP GET completed"))
+        .stdout(predicate::str::contains("new world!!").not());
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_fetch_sends_method_headers_and_file_body() {
+    let (port, received) = start_capturing_http_server();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("body.json");
+    std::fs::write(&path, r#"{"name": "Alice"}"#).unwrap();
+
+    let output = brust_cmd()
+        .args(["fetch", "--url", &format!("http://127.0.0.1:{port}/items")])
+        .args([
+            "-X",
+            "put",
+            "-H",
+            "X-Request-Id: 42",
+            "-H",
+            "Accept: text/plain",
+        ])
+        .arg("--data")
+        .arg(format!("@{}", path.display()))
+        .args(["-o", "json"])
+        .timeout(Duration::from_secs(15))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(document["result"]["method"], "PUT");
+    assert_eq!(document["result"]["requests"][0]["status"], 200);
+
+    let request = received.recv_timeout(Duration::from_secs(5)).unwrap();
+    let lower = request.to_ascii_lowercase();
+    assert!(request.starts_with("PUT /items HTTP/1.1\r\n"), "{request}");
+    assert!(lower.contains("\r\nx-request-id: 42\r\n"), "{request}");
+    assert!(lower.contains("\r\naccept: text/plain\r\n"), "{request}");
+    assert!(
+        lower.contains("\r\ncontent-type: application/json\r\n"),
+        "{request}"
+    );
+    assert!(
+        request.ends_with("\r\n\r\n{\"name\": \"Alice\"}"),
+        "{request}"
+    );
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_fetch_retries_a_flaky_server() {
+    let port = start_flaky_http_server(2);
+    brust_cmd()
+        .args(["fetch", "--url", &format!("http://127.0.0.1:{port}/")])
+        .args(["--max-attempts", "3", "--retry-base-delay", "10ms"])
+        .timeout(Duration::from_secs(15))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "200 http://127.0.0.1:{port}/ in "
+        )))
+        .stdout(predicate::str::contains(" after 3 attempts"))
+        .stderr(predicate::str::contains(
+            "HTTP GET failed (503); retrying in ",
+        ));
+
+    let port = start_flaky_http_server(usize::MAX);
+    let output = brust_cmd()
+        .args(["fetch", "--url", &format!("http://127.0.0.1:{port}/")])
+        .args(["-o", "json"])
+        .env("BRUST_HTTP_MAX_ATTEMPTS", "2")
+        .env("BRUST_HTTP_RETRY_BASE_DELAY", "10ms")
+        .timeout(Duration::from_secs(15))
+        .output()
+        .unwrap();
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(document["result"]["requests"][0]["status"], 503);
+    assert_eq!(document["result"]["requests"][0]["attempts"], 2);
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_fetch_reuses_one_connection_across_urls() {
+    let port = start_keep_alive_http_server();
+    let base = format!("http://127.0.0.1:{port}");
+    let dir = tempfile::tempdir().unwrap();
+    let url_file = dir.path().join("urls.txt");
+    std::fs::write(&url_file, format!("# health checks\n\n{base}/c\n")).unwrap();
+
+    brust_cmd()
+        .args([
+            "fetch",
+            "--url",
+            &format!("{base}/a"),
+            "-u",
+            &format!("{base}/b"),
+        ])
+        .arg("--url-file")
+        .arg(&url_file)
+        .timeout(Duration::from_secs(15))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("200 {base}/a in ")))
+        .stdout(predicate::str::contains(format!("200 {base}/b in ")))
+        .stdout(predicate::str::contains(format!("200 {base}/c in ")));
+
+    let output = brust_cmd()
+        .args(["fetch", "--url", &format!("{base}/a"), "--url-file", "-"])
+        .args(["-o", "json"])
+        .write_stdin(format!("{base}/b\n{base}/c\n"))
+        .timeout(Duration::from_secs(15))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let requests = document["result"]["requests"].as_array().unwrap();
+    assert_eq!(requests.len(), 3);
+    assert_eq!(requests[0]["url"], format!("{base}/a"));
+    assert_eq!(requests[0]["status"], 200);
+    assert_eq!(requests[2]["url"], format!("{base}/c"));
+    let reused: Vec<_> = requests.iter().map(|r| &r["reused_connection"]).collect();
+    assert_eq!(reused, [false, true, true]);
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_fetch_rejects_reading_urls_and_body_from_stdin() {
+    brust_cmd()
+        .args(["fetch", "--url-file", "-", "--data", "@-"])
+        .write_stdin("http://127.0.0.1:1/\n")
+        .timeout(Duration::from_secs(15))
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "--url-file - cannot be combined with --data @-",
+        ));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_fetch_reports_each_failed_url() {
+    let port = start_fake_http_server();
+    let refused = {
+        let l = TcpListener::bind("127.0.0.1:0").unwrap();
+        l.local_addr().unwrap().port()
+    };
+
+    let output = brust_cmd()
+        .args(["fetch", "-u", &format!("http://127.0.0.1:{refused}/")])
+        .args(["-u", &format!("http://127.0.0.1:{port}/")])
+        .args(["-o", "json", "--error-format", "json"])
+        .timeout(Duration::from_secs(15))
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(4));
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let requests = &document["result"]["requests"];
+    assert!(
+        requests[0]["error"]
+            .as_str()
+            .unwrap()
+            .starts_with("HTTP request failed")
+    );
+    assert_eq!(requests[0]["status"], serde_json::Value::Null);
+    assert_eq!(requests[1]["status"], 200);
+    assert_eq!(requests[1]["error"], serde_json::Value::Null);
+    let error = json_error(&output.stderr);
+    assert_eq!(error["kind"], "network");
+    assert!(
+        error["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("1 of 2 URLs could not be fetched; first: HTTP request failed"),
+        "{error}"
+    );
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_fetch_times_out_on_a_hanging_server() {
+    // Connections are queued by the OS but never answered.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let output = brust_cmd()
+        .args(["fetch", "--url", &format!("http://127.0.0.1:{port}/")])
+        .args(["--timeout", "200ms", "--error-format", "json"])
+        .timeout(Duration::from_secs(15))
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(4));
+    let error = json_error(&output.stderr);
+    assert_eq!(error["kind"], "network");
+    assert!(
+        error["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("HTTP request timed out"),
+        "{error}"
+    );
+    drop(listener);
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_fetch_saves_bodies_to_a_file() {
+    let port = start_fake_http_server();
+    let url = format!("http://127.0.0.1:{port}/");
+    let dir = tempfile::tempdir().unwrap();
+    let saved = dir.path().join("body.txt");
+
+    let output = brust_cmd()
+        .args(["fetch", "--url", &url])
+        .arg("--save")
+        .arg(&saved)
+        .args(["-o", "json"])
+        .timeout(Duration::from_secs(15))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(std::fs::read_to_string(&saved).unwrap(), "ok");
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(document["result"]["requests"][0]["body_size"], 2);
+
+    // Several bodies would run together in one file.
+    let rejected = dir.path().join("bodies.txt");
+    brust_cmd()
+        .args(["fetch", "-u", &url, "-u", &url])
+        .arg("--save")
+        .arg(&rejected)
+        .timeout(Duration::from_secs(15))
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("--save takes a single URL, got 2"));
+    assert!(!rejected.exists());
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_fetch_saves_bodies_to_stdout() {
+    let port = start_fake_http_server();
+    let url = format!("http://127.0.0.1:{port}/");
+
+    // The body replaces the result line on stdout.
+    brust_cmd()
+        .args(["fetch", "--url", &url, "--save", "-"])
+        .timeout(Duration::from_secs(15))
+        .assert()
+        .success()
+        .stdout("ok");
+
+    brust_cmd()
+        .args(["fetch", "--url", &url, "--save", "-", "-o", "json"])
+        .timeout(Duration::from_secs(15))
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "--save - cannot be combined with JSON or YAML output",
+        ));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_fetch_rejects_bodies_over_max_size() {
+    let port = start_fake_http_server();
+
+    let output = brust_cmd()
+        .args(["fetch", "--url", &format!("http://127.0.0.1:{port}/")])
+        .args(["--max-size", "1B", "--error-format", "json"])
+        .timeout(Duration::from_secs(15))
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let error = json_error(&output.stderr);
+    assert_eq!(error["kind"], "failure");
+    assert_eq!(error["message"], "HTTP response body is larger than 1B");
+
+    brust_cmd()
+        .args([
+            "fetch",
+            "--url",
+            "http://127.0.0.1:1/",
+            "--max-size",
+            "1 TB",
+        ])
+        .timeout(Duration::from_secs(15))
+        .assert()
+        .code(2);
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_fetch_posts_stdin_body() {
+    let (port, received) = start_capturing_http_server();
+
+    brust_cmd()
+        .args(["fetch", "--url", &format!("http://127.0.0.1:{port}/")])
+        .args(["--data", "@-"])
+        .write_stdin("plain text")
+        .timeout(Duration::from_secs(15))
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("HTTP POST completed"));
+
+    let request = received.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert!(request.starts_with("POST / HTTP/1.1\r\n"), "{request}");
+    assert!(
+        !request.to_ascii_lowercase().contains("content-type"),
+        "{request}"
+    );
+    assert!(request.ends_with("\r\n\r\nplain text"), "{request}");
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_fetch_rejects_bad_request_flags() {
+    for (args, code) in [
+        (["-H", "no colon"], 2),
+        (["-X", "GE T"], 2),
+        (["--data", "@/nonexistent/body.json"], 3),
+    ] {
+        brust_cmd()
+            .args(["fetch", "--url", "http://127.0.0.1:1/"])
+            .args(args)
+            .timeout(Duration::from_secs(15))
+            .assert()
+            .code(code);
+    }
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_fetch_subcommand_connection_refused_fails() {
+    let port = {
+        let l = TcpListener::bind("127.0.0.1:0").unwrap();
+        l.local_addr().unwrap().port()
+    };
+
+    let mut cmd = brust_cmd();
+    cmd.arg("fetch")
+        .arg("--url")
+        .arg(format!("http://127.0.0.1:{port}/"))
+        .timeout(Duration::from_secs(15))
+        .assert()
+        .code(4)
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::contains("HTTP request failed"));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_legacy_flags_warn_deprecated() {
+    let mut cmd = brust_cmd();
+    cmd.arg("--name")
+        .arg("Erin")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("deprecated"))
+        .stdout(predicate::str::contains("Hi, Erin, new world!!"));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_greet_uses_config_file_and_env() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    std::fs::write(&path, "[greet]\nname = \"FromFile\"\ngender = \"man\"\n").unwrap();
+
+    let mut cmd = brust_cmd();
+    cmd.arg("--config")
+        .arg(&path)
+        .arg("greet")
+        .env("BRUST_GREET_NAME", "FromEnv")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Hi, Mr. FromEnv, new world!!"));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_config_show_reports_sources() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("brust")).unwrap();
+    std::fs::write(
+        dir.path().join("brust").join("config.toml"),
+        "[count]\niterations = 4\n",
+    )
+    .unwrap();
+
+    let mut cmd = brust_cmd();
+    cmd.arg("config")
+        .arg("show")
+        .env("XDG_CONFIG_HOME", dir.path())
+        .env("BRUST_HTTP_URL", "http://127.0.0.1/")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("iterations = 4 # file "))
+        .stdout(predicate::str::contains(
+            "url = \"http://127.0.0.1/\" # env BRUST_HTTP_URL",
+        ))
+        .stdout(predicate::str::contains("name = \"Youre\" # default"));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_missing_config_file_fails() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let mut cmd = brust_cmd();
+    cmd.arg("--config")
+        .arg(dir.path().join("absent.toml"))
+        .arg("greet")
+        .assert()
+        .code(3)
+        .stderr(predicate::str::contains("failed to load configuration"));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_greet_locale_flag_ja() {
+    let mut cmd = brust_cmd();
+    cmd.arg("greet")
+        .arg("--name")
+        .arg("太郎")
+        .arg("--gender")
+        .arg("man")
+        .arg("--locale")
+        .arg("ja")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "こんにちは、太郎さん、新しい世界!!",
+        ));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_greet_locale_from_lang() {
+    let mut cmd = cargo_bin_cmd!("brust");
+    cmd.arg("greet")
+        .arg("--name")
+        .arg("花子")
+        .env_remove("LC_ALL")
+        .env_remove("LC_MESSAGES")
+        .env("LANG", "ja_JP.UTF-8")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("こんにちは、花子、新しい世界!!"));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_batch_csv_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("people.csv");
+    std::fs::write(&path, "name,gender,locale\nAlice,woman,\n太郎,man,ja\n").unwrap();
+
+    brust_cmd()
+        .arg("batch")
+        .arg(&path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "record,name,gender,locale,greeting,error",
+        ))
+        .stdout(predicate::str::contains(
+            "1,Alice,woman,en,\"Hi, Ms. Alice, new world!!\",",
+        ))
+        .stdout(predicate::str::contains(
+            "2,太郎,man,ja,こんにちは、太郎さん、新しい世界!!,",
+        ));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_batch_jsonl_stdin_reports_invalid_records() {
+    brust_cmd()
+        .args(["batch", "--format", "jsonl"])
+        .write_stdin("{\"name\":\"Alice\"}\n{\"name\":\"Bob\",\"gender\":\"other\"}\nnot json\n{\"name\":\"Carol\",\"gender\":\"dr\"}\n")
+        .assert()
+        .code(3)
+        .stdout(predicate::str::contains(
+            "\"record\":1,\"name\":\"Alice\",\"gender\":null,\"locale\":\"en\",\"greeting\":\"Hi, Alice, new world!!\",\"error\":null",
+        ))
+        .stdout(predicate::str::contains(
+            "\"record\":2,\"name\":\"Bob\",\"gender\":\"other\",\"locale\":\"en\",\"greeting\":null,\"error\":\"invalid gender: other\"",
+        ))
+        .stdout(predicate::str::contains("\"record\":3,").and(predicate::str::contains("invalid record:")))
+        .stdout(predicate::str::contains("\"greeting\":\"Hi, Dr. Carol, new world!!\""));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_batch_requires_format_for_stdin() {
+    brust_cmd()
+        .arg("batch")
+        .write_stdin("name\nAlice\n")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("cannot infer the batch format"));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_greet_json_output() {
+    let output = brust_cmd()
+        .args(["greet", "-n", "Bob", "-g", "dr", "-o", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(document["schema_version"], 2);
+    assert_eq!(document["command"], "greet");
+    assert_eq!(document["status"], "ok");
+    assert_eq!(document["result"]["honorific"], "dr");
+    assert_eq!(document["result"]["greeting"], "Hi, Dr. Bob, new world!!");
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_count_yaml_output() {
+    brust_cmd()
+        .args(["count", "-c", "0", "-o", "yaml"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("schema_version: 2"))
+        .stdout(predicate::str::contains("count: 0"))
+        .stdout(predicate::str::contains("finished iteration").not());
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_count_seeded_delays_are_reproducible() {
+    let run = || {
+        let output = brust_cmd()
+            .args([
+                "count",
+                "-c",
+                "3",
+                "--delay",
+                "uniform:1,20",
+                "--seed",
+                "42",
+            ])
+            .args(["-o", "json"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()["result"].clone()
+    };
+    let first = run();
+    assert_eq!(first["delay"], "uniform:1,20");
+    assert_eq!(first["seed"], 42);
+    assert_eq!(first["iterations"].as_array().unwrap().len(), 3);
+    assert_eq!(first["iterations"], run()["iterations"]);
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_count_fixed_delay_text_output() {
+    brust_cmd()
+        .args(["count", "-c", "2", "--delay", "fixed:5"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            "iteration 1: 0.005 s\niteration 2: 0.005 s\ncount          2\nerrors         0\nmin        0.005 s\n",
+        ))
+        .stdout(predicate::str::contains("\ntotal      0.010 s\nwall "));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_count_exports_iterations_to_csv() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("run.csv");
+    let output = brust_cmd()
+        .args([
+            "count", "-c", "3", "--delay", "fixed:2", "-o", "json", "--csv",
+        ])
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let summary = &document["result"]["summary"];
+    assert_eq!(summary["p50_secs"], 0.002);
+    assert_eq!(summary["stddev_secs"], 0.0);
+    assert!(summary["wall_secs"].as_f64().unwrap() >= 0.006);
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "iteration,delay_secs,lag_secs,error\n1,0.002,0.0,\n2,0.002,0.0,\n3,0.002,0.0,\n"
+    );
+
+    let output = brust_cmd()
+        .args([
+            "count", "-c", "1", "--delay", "fixed:0", "-o", "json", "--csv",
+        ])
+        .arg(dir.path().join("missing").join("run.csv"))
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(document["result"]["summary"]["count"], 1);
+    assert!(
+        document["error"]
+            .as_str()
+            .unwrap()
+            .starts_with("failed to write CSV to ")
+    );
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_count_injects_failures() {
+    let output = brust_cmd()
+        .args(["count", "-c", "20", "--delay", "fixed:0", "-o", "json"])
+        .args(["--error-rate", "1", "--error-kinds", "invalid"])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "injected failures keep exit code 0"
+    );
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(document["status"], "ok");
+    let result = &document["result"];
+    assert_eq!(result["summary"]["errors"], 20);
+    let iterations = result["iterations"].as_array().unwrap();
+    assert!(iterations.iter().all(|i| i["error"] == "invalid"));
+
+    let output = brust_cmd()
+        .args([
+            "count",
+            "-c",
+            "3",
+            "--delay",
+            "fixed:0",
+            "--error-rate",
+            "0",
+        ])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(!stdout.contains("failed"), "{stdout}");
+    assert!(stdout.contains("\nerrors         0\n"), "{stdout}");
+
+    for bad in [["--error-rate", "1.5"], ["--error-kinds", "oops"]] {
+        let output = brust_cmd()
+            .args(["count", "-c", "1"])
+            .args(bad)
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(2), "{bad:?}");
+    }
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_count_links_iteration_spans() {
+    let output = brust_cmd()
+        .args(["count", "-c", "4", "-j", "2", "--delay", "fixed:1"])
+        .args(["--span-links", "-o", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(document["result"]["summary"]["count"], 4);
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_count_concurrency_keeps_iteration_order() {
+    let output = brust_cmd()
+        .args(["count", "-c", "6", "-j", "3", "--delay", "uniform:1,30"])
+        .args(["--seed", "8", "-o", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let iterations: Vec<_> = document["result"]["iterations"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|i| i["iteration"].as_u64().unwrap())
+        .collect();
+    assert_eq!(iterations, [1, 2, 3, 4, 5, 6]);
+
+    brust_cmd()
+        .args(["count", "-c", "1", "--concurrency", "0"])
+        .assert()
+        .code(2);
+}
+
+#[cfg(unix)]
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_count_sigint_stops_with_partial_results() {
+    let child = std::process::Command::new(assert_cmd::cargo::cargo_bin!("brust"))
+        .args(["count", "-c", "100", "--delay", "fixed:100", "-o", "json"])
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    std::thread::sleep(Duration::from_millis(500));
+    let killed = std::process::Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(killed.success());
+
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(output.status.code(), Some(130));
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(document["status"], "error");
+    let done = document["result"]["summary"]["count"].as_u64().unwrap();
+    assert!((1..100).contains(&done), "{done} iterations completed");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("count cancelled after"), "{stderr}");
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_count_runs_at_a_rate_until_the_duration() {
+    let output = brust_cmd()
+        .args(["count", "--duration", "200ms", "--rate", "20/s"])
+        .args(["--delay", "fixed:1", "-o", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let result = &document["result"];
+    assert_eq!(result["rate"], "20/s");
+    assert_eq!(result["duration_secs"], 0.2);
+    assert_eq!(result["summary"]["count"], 4);
+    assert!(result["summary"]["missed_slots"].is_u64());
+    assert!(result["iterations"][0]["lag_secs"].is_number());
+
+    brust_cmd()
+        .args(["count", "--duration", "1m", "--rate", "fast"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("invalid rate"));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_count_rejects_unknown_distribution() {
+    brust_cmd()
+        .args(["count", "-c", "1", "--delay", "gamma:3"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("invalid delay distribution"));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_fetch_json_output_reports_error() {
+    let port = {
+        let l = TcpListener::bind("127.0.0.1:0").unwrap();
+        l.local_addr().unwrap().port()
+    };
+
+    let output = brust_cmd()
+        .args(["fetch", "-o", "json", "-u"])
+        .arg(format!("http://127.0.0.1:{port}/"))
+        .timeout(Duration::from_secs(15))
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(document["command"], "fetch");
+    assert_eq!(document["status"], "error");
+    assert!(document["error"].is_string());
+    assert_eq!(
+        document["result"]["requests"][0]["status"],
+        serde_json::Value::Null
+    );
+}
+
+/// Load the published output schema, with `additionalProperties: false` added
+/// to every object so that fields missing from the schema fail validation.
+fn strict_output_schema() -> jsonschema::Validator {
+    fn close_objects(schema: &mut serde_json::Value) {
+        match schema {
+            serde_json::Value::Object(map) => {
+                if map.get("type").is_some_and(|t| t == "object") && map.contains_key("properties")
+                {
+                    map.entry("additionalProperties")
+                        .or_insert(serde_json::Value::Bool(false));
+                }
+                map.values_mut().for_each(close_objects);
+            }
+            serde_json::Value::Array(items) => items.iter_mut().for_each(close_objects),
+            _ => {}
+        }
+    }
+
+    let path = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../docs/specs/schemas/brust-output-v2.schema.json"
+    );
+    let mut schema: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+    close_objects(&mut schema);
+    jsonschema::validator_for(&schema).unwrap()
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_json_output_matches_the_published_schema() {
+    let validator = strict_output_schema();
+    let port = start_fake_http_server();
+    let closed = {
+        let l = TcpListener::bind("127.0.0.1:0").unwrap();
+        l.local_addr().unwrap().port()
+    };
+    let runs: [&[&str]; 6] = [
+        &["greet", "-n", "Bob", "-g", "dr"],
+        &["count", "-c", "2", "--delay", "fixed:1", "--seed", "1"],
+        &["count", "--duration", "100ms", "--rate", "20/s"],
+        &["fetch", "-u", &format!("http://127.0.0.1:{port}/")],
+        &[
+            "fetch",
+            "-X",
+            "POST",
+            "-d",
+            "{}",
+            "-u",
+            &format!("http://127.0.0.1:{port}/a"),
+            "-u",
+            &format!("http://127.0.0.1:{closed}/b"),
+        ],
+        &["fetch", "-u", "not-a-url"],
+    ];
+
+    for args in runs {
+        let output = brust_cmd()
+            .args(args)
+            .args(["-o", "json"])
+            .timeout(Duration::from_secs(15))
+            .output()
+            .unwrap();
+        let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        let errors: Vec<_> = validator
+            .iter_errors(&document)
+            .map(|e| format!("{}: {e}", e.instance_path()))
+            .collect();
+        assert!(errors.is_empty(), "{args:?}: {errors:#?}\n{document:#}");
+    }
+}
+
+/// Parse the last stderr line, where `--error-format json` puts the error.
+fn json_error(stderr: &[u8]) -> serde_json::Value {
+    let stderr = String::from_utf8_lossy(stderr);
+    serde_json::from_str(stderr.lines().last().unwrap()).unwrap()
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_fetch_error_format_json_reports_network_error() {
+    let port = {
+        let l = TcpListener::bind("127.0.0.1:0").unwrap();
+        l.local_addr().unwrap().port()
+    };
+
+    let output = brust_cmd()
+        .args(["fetch", "--error-format", "json", "-u"])
+        .arg(format!("http://127.0.0.1:{port}/"))
+        .timeout(Duration::from_secs(15))
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(4));
+
+    let error = json_error(&output.stderr);
+    assert_eq!(error["kind"], "network");
+    assert_eq!(error["exit_code"], 4);
+    assert!(
+        error["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("HTTP request failed")
+    );
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_usage_errors_exit_2_with_error_format_json() {
+    let output = brust_cmd()
+        .args(["--error-format=json", "greet", "--bogus"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let error = json_error(&output.stderr);
+    assert_eq!(error["kind"], "usage");
+    assert_eq!(error["message"], "unexpected argument '--bogus' found");
+
+    let output = brust_cmd()
+        .args(["count", "--error-format", "json"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(json_error(&output.stderr)["kind"], "usage");
+}
+
+#[cfg(feature = "otel")]
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_invalid_telemetry_endpoint_exits_6() {
+    // The greeting is still printed; only the exit code reports telemetry.
+    brust_cmd()
+        .args(["greet", "-n", "Tess"])
+        .env("BRUST_TELEMETRY_ENDPOINT", "http://bad host")
+        .assert()
+        .code(6)
+        .stdout("Hi, Tess, new world!!\n")
+        .stderr(predicate::str::contains(
+            "failed to set up telemetry export to http://bad host",
+        ))
+        .stderr(predicate::str::contains("running without telemetry export"));
+
+    // A failing command keeps its own exit code.
+    brust_cmd()
+        .args(["greet", "-n", "Tess", "--template", "{nope}"])
+        .env("BRUST_TELEMETRY_ENDPOINT", "http://bad host")
+        .assert()
+        .code(3);
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_greet_output_survives_quiet_log_levels() {
+    brust_cmd()
+        .args(["greet", "-n", "Quinn"])
+        .env("RUST_LOG", "warn")
+        .assert()
+        .success()
+        .stdout(predicate::eq("Hi, Quinn, new world!!\n"));
+
+    brust_cmd()
+        .args(["-qqq", "greet", "-n", "Quinn", "--honorific", " "])
+        .assert()
+        .code(3)
+        .stdout(predicate::eq("Hi, Quinn, new world!!\n"))
+        .stderr(predicate::str::is_empty());
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_verbose_and_quiet_override_rust_log() {
+    brust_cmd()
+        .args(["greet", "-v", "--honorific", " "])
+        .env("RUST_LOG", "off")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid honorific prefix"));
+
+    brust_cmd()
+        .args(["--quiet", "--name", "Erin"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Hi, Erin, new world!!"))
+        .stderr(predicate::str::contains("deprecated"));
+
+    brust_cmd()
+        .args(["-qq", "--name", "Erin"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("deprecated").not());
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_completions_bash() {
+    brust_cmd()
+        .args(["completions", "bash"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("_brust()"))
+        .stdout(predicate::str::contains("--template"))
+        .stderr(predicate::str::is_empty());
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_completions_rejects_unknown_shell() {
+    brust_cmd()
+        .args(["completions", "tcsh"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("invalid value 'tcsh'"));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cli_man_writes_page_per_subcommand() {
+    let dir = tempfile::tempdir().unwrap();
+    let out_dir = dir.path().join("man1");
+
+    brust_cmd()
+        .arg("man")
+        .arg(&out_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("brust-greet.1"));
+
+    for page in ["brust.1", "brust-greet.1", "brust-batch.1", "brust-man.1"] {
+        assert!(out_dir.join(page).is_file(), "missing {page}");
+    }
+    let greet = std::fs::read_to_string(out_dir.join("brust-greet.1")).unwrap();
+    assert!(greet.contains("\\-\\-honorific"));
+}
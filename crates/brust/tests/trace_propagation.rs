@@ -0,0 +1,92 @@
+//! Integration test for W3C trace context propagation from `libs::http` to a
+//! local axum server.
+#![cfg(feature = "otel")]
+#![allow(clippy::unwrap_used)]
+#![allow(missing_docs)]
+
+use std::sync::{Arc, Mutex};
+
+use axum::{Router, http::HeaderMap, routing::get};
+use brust::libs::http::{ClientOptions, Request, fetch, fetch_url, fetch_url_cancellable};
+use brust::{CancelToken, Meters};
+use opentelemetry::trace::{SpanKind, TracerProvider as _};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider};
+use tracing_subscriber::layer::SubscriberExt as _;
+
+/// Serve `/` on a random port and remember the `traceparent` of every request.
+async fn start_traceparent_server() -> (u16, Arc<Mutex<Vec<Option<String>>>>) {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let seen_in_handler = Arc::clone(&seen);
+    let app = Router::new().route(
+        "/",
+        get(move |headers: HeaderMap| async move {
+            seen_in_handler.lock().unwrap().push(
+                headers
+                    .get("traceparent")
+                    .and_then(|v| v.to_str().ok())
+                    .map(ToOwned::to_owned),
+            );
+            "ok"
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap(); // NOTEST(unreachable): test server panic path; unreachable in passing tests
+    });
+    (port, seen)
+}
+
+#[tokio::test]
+#[cfg_attr(miri, ignore)] // reqwest blocking -> setsockopt SO_KEEPALIVE unsupported under Miri
+async fn server_sees_the_client_trace_id() {
+    let _ = rustls::crypto::ring::default_provider().install_default();
+    opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+    let (port, seen) = start_traceparent_server().await;
+
+    let exporter = InMemorySpanExporter::default();
+    let provider = SdkTracerProvider::builder()
+        .with_simple_exporter(exporter.clone())
+        .build();
+    let subscriber = tracing_subscriber::registry()
+        .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+    let url = format!("http://127.0.0.1:{port}/");
+    tokio::task::spawn_blocking(move || {
+        tracing::subscriber::with_default(subscriber, || {
+            let options = ClientOptions::default();
+            fetch_url(&url, &options, &Meters::noop()).unwrap();
+            // The cancellable fetch sends from a background thread.
+            fetch_url_cancellable(&url, &options, &Meters::noop(), &CancelToken::new()).unwrap();
+            // A traceparent set by the caller wins over the injected one.
+            let own = "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01";
+            let request =
+                Request::get(&url).with_header(format!("traceparent: {own}").parse().unwrap());
+            fetch(&request, &options, &Meters::noop()).unwrap();
+        });
+    })
+    .await
+    .unwrap();
+
+    provider.force_flush().unwrap();
+    let spans = exporter.get_finished_spans().unwrap();
+    let traceparents = spans
+        .iter()
+        .filter(|span| span.span_kind == SpanKind::Client)
+        .take(2)
+        .map(|span| {
+            let context = &span.span_context;
+            Some(format!(
+                "00-{}-{}-01",
+                context.trace_id(),
+                context.span_id()
+            ))
+        });
+    let expected: Vec<_> = traceparents
+        .chain([Some(String::from(
+            "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+        ))])
+        .collect();
+    assert_eq!(*seen.lock().unwrap(), expected);
+}